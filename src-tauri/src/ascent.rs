@@ -0,0 +1,365 @@
+//! Balloon ascent physics.
+//!
+//! The lift gas expands as the balloon climbs: its volume follows the
+//! ambient T/P of the standard atmosphere. The buoyancy of that gas stays
+//! equal to the gross lift at launch, so the net upward force is the free
+//! lift all the way up. The balloon rises at the speed where drag on its
+//! growing cross-section, ½ ρ v² Cd π r², balances that force, which makes
+//! the ascent rate creep up with altitude instead of staying constant. The
+//! balloon bursts where its diameter reaches the rated burst diameter.
+
+use serde::{Deserialize, Serialize};
+
+use crate::atmosphere::{state_at, AIR_MOLAR_MASS, G0, MIN_ALTITUDE_M};
+
+/// Drag coefficient of a latex sounding balloon, as `BALLOON_DRAG_COEFFICIENT`
+/// in `src/constants/index.ts`.
+pub const BALLOON_DRAG_COEFFICIENT: f64 = 0.3;
+/// Altitude above which the burst search gives up, m.
+pub const MAX_BURST_ALTITUDE_M: f64 = 60000.0;
+/// Altitude spacing of the expansion curve, m.
+const CURVE_STEP_M: f64 = 250.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LiftGas {
+  Helium,
+  Hydrogen,
+}
+
+impl LiftGas {
+  /// kg/mol
+  pub fn molar_mass(self) -> f64 {
+    match self {
+      LiftGas::Helium => 0.0040026,
+      LiftGas::Hydrogen => 0.00201588,
+    }
+  }
+
+  /// Lift per cubic meter, kg/m³, of the gas diluted with air to `purity`
+  /// (mole fraction), in air of `density` (kg/m³) at the same temperature
+  /// and pressure.
+  pub fn lift_density(self, purity: f64, density: f64) -> f64 {
+    let molar_mass = purity * self.molar_mass() + (1.0 - purity) * AIR_MOLAR_MASS;
+    density * (1.0 - molar_mass / AIR_MOLAR_MASS)
+  }
+}
+
+fn default_drag_coefficient() -> f64 {
+  BALLOON_DRAG_COEFFICIENT
+}
+
+fn default_gas_purity() -> f64 {
+  1.0
+}
+
+/// A filled balloon and what it carries.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Balloon {
+  /// Envelope mass, kg
+  pub balloon_mass: f64,
+  /// Payload, parachute and rigging, kg
+  pub payload_mass: f64,
+  /// Net lift at launch (neck lift minus payload), kg
+  pub free_lift: f64,
+  pub gas: LiftGas,
+  /// Mole fraction of the lift gas in the fill, the rest being air
+  #[serde(default = "default_gas_purity")]
+  pub gas_purity: f64,
+  #[serde(default = "default_drag_coefficient")]
+  pub drag_coefficient: f64,
+  /// Rated burst diameter, m. Without it the flight bursts at the
+  /// prediction's `burstAltitude`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub burst_diameter: Option<f64>,
+}
+
+impl Balloon {
+  pub fn is_valid(&self) -> bool {
+    let positive = |v: f64| v.is_finite() && v > 0.0;
+    positive(self.balloon_mass)
+      && positive(self.gas_purity)
+      && self.gas_purity <= 1.0
+      && self.payload_mass.is_finite()
+      && self.payload_mass >= 0.0
+      && positive(self.free_lift)
+      && positive(self.drag_coefficient)
+      && self.burst_diameter.is_none_or(positive)
+  }
+
+  /// Lift of the fill per cubic meter in air of `density` (kg/m³), kg/m³
+  pub fn lift_density(&self, density: f64) -> f64 {
+    self.gas.lift_density(self.gas_purity, density)
+  }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AscentError {
+  #[error("invalid balloon: {0}")]
+  InvalidBalloon(&'static str),
+}
+
+impl Serialize for AscentError {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.to_string())
+  }
+}
+
+/// One point of the expansion curve.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpansionPoint {
+  /// m
+  pub altitude: f64,
+  /// Balloon radius, m
+  pub radius: f64,
+  /// m/s
+  pub ascent_rate: f64,
+}
+
+/// A [`Balloon`] filled at a given launch altitude.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AscentModel {
+  pub balloon: Balloon,
+  /// Gas volume at launch, m³
+  launch_volume: f64,
+  /// Ambient T/P at launch, K/Pa
+  launch_t_over_p: f64,
+}
+
+impl AscentModel {
+  pub fn new(balloon: Balloon, launch_altitude: f64) -> Self {
+    let launch = state_at(launch_altitude);
+    // Gross lift is what the displaced air outweighs the gas by.
+    let gross_lift = balloon.free_lift + balloon.payload_mass + balloon.balloon_mass;
+    AscentModel {
+      balloon,
+      launch_volume: gross_lift / balloon.lift_density(launch.density),
+      launch_t_over_p: launch.temperature / launch.pressure,
+    }
+  }
+
+  /// Gas volume at `altitude`, m³
+  pub fn volume(&self, altitude: f64) -> f64 {
+    let state = state_at(altitude);
+    self.launch_volume * (state.temperature / state.pressure) / self.launch_t_over_p
+  }
+
+  /// Balloon radius at `altitude`, m
+  pub fn radius(&self, altitude: f64) -> f64 {
+    (3.0 * self.volume(altitude) / (4.0 * std::f64::consts::PI)).cbrt()
+  }
+
+  /// Steady ascent rate at `altitude`, m/s
+  pub fn ascent_rate(&self, altitude: f64) -> f64 {
+    let density = state_at(altitude).density;
+    let area = std::f64::consts::PI * self.radius(altitude).powi(2);
+    (2.0 * self.balloon.free_lift * G0 / (density * self.balloon.drag_coefficient * area)).sqrt()
+  }
+
+  /// Altitude at which the balloon reaches its burst diameter, if it has
+  /// one and reaches it below `MAX_BURST_ALTITUDE_M`.
+  pub fn burst_altitude(&self, launch_altitude: f64) -> Option<f64> {
+    let burst_radius = self.balloon.burst_diameter? / 2.0;
+    if self.radius(launch_altitude) >= burst_radius {
+      return Some(launch_altitude);
+    }
+    if self.radius(MAX_BURST_ALTITUDE_M) < burst_radius {
+      return None;
+    }
+    // The radius grows monotonically with altitude.
+    let (mut low, mut high) = (launch_altitude, MAX_BURST_ALTITUDE_M);
+    while high - low > 1.0 {
+      let mid = (low + high) / 2.0;
+      if self.radius(mid) < burst_radius {
+        low = mid;
+      } else {
+        high = mid;
+      }
+    }
+    Some((low + high) / 2.0)
+  }
+
+  /// Radius and ascent rate from `launch_altitude` up to `top`.
+  pub fn expansion_curve(&self, launch_altitude: f64, top: f64) -> Vec<ExpansionPoint> {
+    let steps = ((top - launch_altitude) / CURVE_STEP_M).ceil().max(0.0) as usize;
+    (0..=steps)
+      .map(|i| {
+        let altitude = (launch_altitude + i as f64 * CURVE_STEP_M).min(top);
+        ExpansionPoint {
+          altitude,
+          radius: self.radius(altitude),
+          ascent_rate: self.ascent_rate(altitude),
+        }
+      })
+      .collect()
+  }
+}
+
+/// Ascent profile for the calculator chart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AscentProfile {
+  /// m; `None` without a burst diameter, or if it is never reached
+  pub burst_altitude: Option<f64>,
+  pub curve: Vec<ExpansionPoint>,
+}
+
+/// Balloon expansion from launch to burst, or to `top` (m, at most
+/// `MAX_BURST_ALTITUDE_M`) when the burst altitude is unknown.
+#[tauri::command]
+pub fn ascent_profile(
+  balloon: Balloon,
+  launch_altitude: f64,
+  top: Option<f64>,
+) -> Result<AscentProfile, AscentError> {
+  if !balloon.is_valid() {
+    return Err(AscentError::InvalidBalloon(
+      "masses, free lift, drag coefficient and burst diameter must be positive",
+    ));
+  }
+  if !(MIN_ALTITUDE_M..MAX_BURST_ALTITUDE_M).contains(&launch_altitude) {
+    return Err(AscentError::InvalidBalloon(
+      "launch altitude must be between -1 km and the highest burst altitude",
+    ));
+  }
+  if top.is_some_and(|top| !(top.is_finite() && top <= MAX_BURST_ALTITUDE_M)) {
+    return Err(AscentError::InvalidBalloon(
+      "the profile cannot reach above 60 km",
+    ));
+  }
+  let model = AscentModel::new(balloon, launch_altitude);
+  let burst_altitude = model.burst_altitude(launch_altitude);
+  let top = burst_altitude.or(top).unwrap_or(MAX_BURST_ALTITUDE_M);
+  Ok(AscentProfile {
+    burst_altitude,
+    curve: model.expansion_curve(launch_altitude, top),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A 1200 g balloon lifting 1 kg with 1.5 kg of free lift on helium.
+  fn balloon() -> Balloon {
+    Balloon {
+      balloon_mass: 1.2,
+      payload_mass: 1.0,
+      free_lift: 1.5,
+      gas: LiftGas::Helium,
+      gas_purity: 1.0,
+      drag_coefficient: BALLOON_DRAG_COEFFICIENT,
+      burst_diameter: Some(8.63),
+    }
+  }
+
+  #[test]
+  fn balloon_expands_and_speeds_up() {
+    let model = AscentModel::new(balloon(), 0.0);
+
+    // 3.7 kg gross lift at ~1.05 kg/m³ of helium lift.
+    assert!(
+      (model.volume(0.0) - 3.52).abs() < 0.05,
+      "{}",
+      model.volume(0.0)
+    );
+    // T/P grows 7.4x by 16 km, so the radius almost doubles.
+    let ratio = model.radius(16000.0) / model.radius(0.0);
+    assert!((ratio - 1.94).abs() < 0.01, "{}", ratio);
+
+    let sea_level = model.ascent_rate(0.0);
+    assert!((sea_level - 5.3).abs() < 0.2, "{}", sea_level);
+    let curve = model.expansion_curve(0.0, 30000.0);
+    assert!(curve.windows(2).all(|w| w[1].radius > w[0].radius));
+    assert!(curve.last().unwrap().ascent_rate > sea_level);
+    assert_eq!(curve.last().unwrap().altitude, 30000.0);
+  }
+
+  #[test]
+  fn bursts_at_rated_diameter() {
+    let model = AscentModel::new(balloon(), 0.0);
+    let burst = model.burst_altitude(0.0).unwrap();
+
+    assert!(burst > 25000.0 && burst < 40000.0, "{}", burst);
+    assert!((model.radius(burst) * 2.0 - 8.63).abs() < 0.01);
+
+    let unrated = AscentModel::new(
+      Balloon {
+        burst_diameter: None,
+        ..balloon()
+      },
+      0.0,
+    );
+    assert_eq!(unrated.burst_altitude(0.0), None);
+  }
+
+  #[test]
+  fn hydrogen_needs_less_gas() {
+    let helium = AscentModel::new(balloon(), 0.0);
+    let hydrogen = AscentModel::new(
+      Balloon {
+        gas: LiftGas::Hydrogen,
+        ..balloon()
+      },
+      0.0,
+    );
+    assert!(hydrogen.volume(0.0) < helium.volume(0.0));
+    assert!(hydrogen.ascent_rate(0.0) > helium.ascent_rate(0.0));
+  }
+
+  #[test]
+  fn profile_rejects_invalid_balloons_and_heights() {
+    let profile = ascent_profile(balloon(), 0.0, None).unwrap();
+    assert!(profile.burst_altitude.is_some());
+    assert!(ascent_profile(
+      Balloon {
+        burst_diameter: None,
+        ..balloon()
+      },
+      0.0,
+      Some(30000.0)
+    )
+    .is_ok_and(|p| p.curve.last().unwrap().altitude == 30000.0));
+
+    for invalid in [
+      Balloon {
+        free_lift: 0.0,
+        ..balloon()
+      },
+      Balloon {
+        free_lift: -1.0,
+        ..balloon()
+      },
+      Balloon {
+        drag_coefficient: 0.0,
+        ..balloon()
+      },
+      Balloon {
+        balloon_mass: f64::NAN,
+        ..balloon()
+      },
+    ] {
+      assert!(matches!(
+        ascent_profile(invalid, 0.0, None),
+        Err(AscentError::InvalidBalloon(_))
+      ));
+    }
+    let unburst = Balloon {
+      burst_diameter: None,
+      ..balloon()
+    };
+    for (launch_altitude, top) in [
+      (0.0, Some(1e12)),
+      (0.0, Some(MAX_BURST_ALTITUDE_M + 1.0)),
+      (0.0, Some(f64::INFINITY)),
+      (-1e12, None),
+      (f64::NAN, None),
+    ] {
+      assert!(matches!(
+        ascent_profile(unburst, launch_altitude, top),
+        Err(AscentError::InvalidBalloon(_))
+      ));
+    }
+  }
+}
@@ -0,0 +1,317 @@
+//! 1976 US Standard Atmosphere.
+//!
+//! Covers all seven layers from sea level to 86 km geometric altitude. The
+//! model works in geopotential altitude internally; every public function
+//! takes and returns geometric altitude in meters, which is what GPS and the
+//! rest of the app use. Above 86 km the mesosphere lapse rate is simply
+//! extended, and below sea level the troposphere is; the commands only
+//! answer between `MIN_ALTITUDE_M` and the model top, `MAX_ALTITUDE_M`,
+//! since the extended lapse rate reaches 0 K near 178 km.
+
+use serde::{Deserialize, Serialize};
+
+/// Standard gravity, m/s²
+pub const G0: f64 = 9.80665;
+/// Effective Earth radius used for geopotential conversion, m
+pub const EARTH_RADIUS_M: f64 = 6356766.0;
+/// Universal gas constant, J/(mol·K)
+pub const GAS_CONSTANT: f64 = 8.31432;
+/// Mean molar mass of air below 86 km, kg/mol
+pub const AIR_MOLAR_MASS: f64 = 0.0289644;
+/// Ratio of specific heats for air
+pub const GAMMA: f64 = 1.4;
+/// Specific gas constant for dry air, J/(kg·K)
+pub const R_AIR: f64 = GAS_CONSTANT / AIR_MOLAR_MASS;
+/// Top of the 1976 model, geometric altitude, m
+pub const MAX_ALTITUDE_M: f64 = 86000.0;
+/// Lowest altitude the commands answer for, m; below the Dead Sea shore
+pub const MIN_ALTITUDE_M: f64 = -1000.0;
+
+struct Layer {
+  /// Geopotential altitude at the layer base, m
+  base: f64,
+  /// Temperature at the layer base, K
+  temperature: f64,
+  /// Temperature lapse rate, K/m
+  lapse: f64,
+  /// Pressure at the layer base, Pa
+  pressure: f64,
+}
+
+const LAYERS: [Layer; 7] = [
+  Layer {
+    base: 0.0,
+    temperature: 288.15,
+    lapse: -0.0065,
+    pressure: 101325.0,
+  },
+  Layer {
+    base: 11000.0,
+    temperature: 216.65,
+    lapse: 0.0,
+    pressure: 22632.06,
+  },
+  Layer {
+    base: 20000.0,
+    temperature: 216.65,
+    lapse: 0.001,
+    pressure: 5474.889,
+  },
+  Layer {
+    base: 32000.0,
+    temperature: 228.65,
+    lapse: 0.0028,
+    pressure: 868.0187,
+  },
+  Layer {
+    base: 47000.0,
+    temperature: 270.65,
+    lapse: 0.0,
+    pressure: 110.9063,
+  },
+  Layer {
+    base: 51000.0,
+    temperature: 270.65,
+    lapse: -0.0028,
+    pressure: 66.93887,
+  },
+  Layer {
+    base: 71000.0,
+    temperature: 214.65,
+    lapse: -0.002,
+    pressure: 3.956420,
+  },
+];
+
+#[derive(Debug, thiserror::Error)]
+pub enum AtmosphereError {
+  #[error("altitude {0} m is outside the standard atmosphere (-1 to 86 km)")]
+  AltitudeOutOfRange(f64),
+  #[error("pressure {0} hPa is outside the standard atmosphere (-1 to 86 km)")]
+  PressureOutOfRange(f64),
+}
+
+impl Serialize for AtmosphereError {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.to_string())
+  }
+}
+
+fn in_model(altitude: f64) -> bool {
+  (MIN_ALTITUDE_M..=MAX_ALTITUDE_M).contains(&altitude)
+}
+
+/// Atmospheric state at one altitude.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtmosphereState {
+  /// Geometric altitude, m
+  pub altitude: f64,
+  /// Geopotential altitude, m
+  pub geopotential_altitude: f64,
+  /// K
+  pub temperature: f64,
+  /// Pa
+  pub pressure: f64,
+  /// kg/m³
+  pub density: f64,
+  /// m/s
+  pub speed_of_sound: f64,
+}
+
+/// Converts geometric altitude to geopotential altitude.
+pub fn geopotential_altitude(altitude: f64) -> f64 {
+  EARTH_RADIUS_M * altitude / (EARTH_RADIUS_M + altitude)
+}
+
+/// Converts geopotential altitude to geometric altitude.
+pub fn geometric_altitude(geopotential: f64) -> f64 {
+  EARTH_RADIUS_M * geopotential / (EARTH_RADIUS_M - geopotential)
+}
+
+fn layer_for_geopotential(h: f64) -> &'static Layer {
+  LAYERS
+    .iter()
+    .rev()
+    .find(|layer| h >= layer.base)
+    .unwrap_or(&LAYERS[0])
+}
+
+fn layer_for_pressure(pressure: f64) -> &'static Layer {
+  LAYERS
+    .iter()
+    .rev()
+    .find(|layer| pressure <= layer.pressure)
+    .unwrap_or(&LAYERS[0])
+}
+
+/// Full atmospheric state at a geometric altitude in meters.
+pub fn state_at(altitude: f64) -> AtmosphereState {
+  let h = geopotential_altitude(altitude);
+  let layer = layer_for_geopotential(h);
+  let dh = h - layer.base;
+  let temperature = layer.temperature + layer.lapse * dh;
+
+  let exponent = G0 * AIR_MOLAR_MASS / GAS_CONSTANT;
+  let pressure = if layer.lapse == 0.0 {
+    layer.pressure * (-exponent * dh / layer.temperature).exp()
+  } else {
+    layer.pressure * (layer.temperature / temperature).powf(exponent / layer.lapse)
+  };
+
+  AtmosphereState {
+    altitude,
+    geopotential_altitude: h,
+    temperature,
+    pressure,
+    density: pressure / (R_AIR * temperature),
+    speed_of_sound: (GAMMA * R_AIR * temperature).sqrt(),
+  }
+}
+
+/// Pressure in Pa at a geometric altitude.
+pub fn pressure(altitude: f64) -> f64 {
+  state_at(altitude).pressure
+}
+
+/// Pressure in hPa at a geometric altitude, for matching forecast levels.
+pub fn altitude_to_pressure(altitude: f64) -> f64 {
+  pressure(altitude) / 100.0
+}
+
+/// Geometric altitude in meters at which the standard atmosphere reaches
+/// `pressure_hpa`. Exact inverse of [`altitude_to_pressure`].
+pub fn pressure_to_altitude(pressure_hpa: f64) -> f64 {
+  let pressure = pressure_hpa * 100.0;
+  let layer = layer_for_pressure(pressure);
+  let exponent = G0 * AIR_MOLAR_MASS / GAS_CONSTANT;
+
+  let h = if layer.lapse == 0.0 {
+    layer.base - layer.temperature / exponent * (pressure / layer.pressure).ln()
+  } else {
+    layer.base
+      + layer.temperature / layer.lapse
+        * ((pressure / layer.pressure).powf(-layer.lapse / exponent) - 1.0)
+  };
+
+  geometric_altitude(h)
+}
+
+/// Atmospheric state at a geometric altitude in meters.
+#[tauri::command]
+pub fn atmosphere_at(altitude: f64) -> Result<AtmosphereState, AtmosphereError> {
+  if !in_model(altitude) {
+    return Err(AtmosphereError::AltitudeOutOfRange(altitude));
+  }
+  Ok(state_at(altitude))
+}
+
+/// Standard-atmosphere altitude in meters for a pressure in hPa.
+#[tauri::command]
+pub fn pressure_altitude(pressure_hpa: f64) -> Result<f64, AtmosphereError> {
+  let altitude = pressure_to_altitude(pressure_hpa);
+  if !(pressure_hpa > 0.0 && in_model(altitude)) {
+    return Err(AtmosphereError::PressureOutOfRange(pressure_hpa));
+  }
+  Ok(altitude)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+    let error = ((actual - expected) / expected).abs();
+    assert!(
+      error < tolerance,
+      "expected {}, got {} ({:.4}% off)",
+      expected,
+      actual,
+      error * 100.0
+    );
+  }
+
+  /// Geometric altitude, temperature, pressure and density from the
+  /// published U.S. Standard Atmosphere, 1976 tables.
+  const TABLE: [(f64, f64, f64, f64); 10] = [
+    (0.0, 288.150, 101325.0, 1.2250),
+    (5000.0, 255.676, 54048.0, 7.3643e-1),
+    (10000.0, 223.252, 26500.0, 4.1351e-1),
+    (20000.0, 216.650, 5529.3, 8.8910e-2),
+    (30000.0, 226.509, 1197.0, 1.8410e-2),
+    (40000.0, 250.350, 287.14, 3.9957e-3),
+    (50000.0, 270.650, 79.779, 1.0269e-3),
+    (60000.0, 247.021, 21.959, 3.0968e-4),
+    (70000.0, 219.585, 5.2209, 8.2829e-5),
+    (80000.0, 198.639, 1.0524, 1.8458e-5),
+  ];
+
+  #[test]
+  fn matches_published_tables() {
+    for (altitude, t, p, rho) in TABLE {
+      let state = state_at(altitude);
+      assert_close(state.temperature, t, 1e-4);
+      assert_close(state.pressure, p, 1e-3);
+      assert_close(state.density, rho, 1e-3);
+    }
+  }
+
+  #[test]
+  fn speed_of_sound_matches_tables() {
+    assert_close(state_at(0.0).speed_of_sound, 340.294, 1e-4);
+    assert_close(state_at(10000.0).speed_of_sound, 299.532, 1e-4);
+    assert_close(state_at(20000.0).speed_of_sound, 295.070, 1e-4);
+  }
+
+  #[test]
+  fn layers_are_continuous() {
+    for layer in &LAYERS[1..] {
+      let altitude = geometric_altitude(layer.base);
+      let below = state_at(altitude - 1e-6);
+      let above = state_at(altitude + 1e-6);
+      assert_close(below.temperature, above.temperature, 1e-6);
+      assert_close(below.pressure, above.pressure, 1e-5);
+    }
+  }
+
+  #[test]
+  fn pressure_to_altitude_inverts_pressure() {
+    for altitude in [
+      -400.0, 0.0, 8000.0, 11000.0, 25000.0, 33000.0, 42000.0, 48000.0, 60000.0, 85000.0,
+    ] {
+      let roundtrip = pressure_to_altitude(altitude_to_pressure(altitude));
+      assert!(
+        (roundtrip - altitude).abs() < 0.01,
+        "{} -> {}",
+        altitude,
+        roundtrip
+      );
+    }
+  }
+
+  #[test]
+  fn commands_reject_altitudes_outside_the_model() {
+    assert_eq!(atmosphere_at(86000.0).unwrap(), state_at(86000.0));
+    assert!(pressure_altitude(1013.25).unwrap().abs() < 0.01);
+    for altitude in [86001.0, 200000.0, -5000.0, f64::NAN, f64::INFINITY] {
+      assert!(
+        matches!(
+          atmosphere_at(altitude),
+          Err(AtmosphereError::AltitudeOutOfRange(_))
+        ),
+        "{}",
+        altitude
+      );
+    }
+    for pressure in [0.0, -10.0, 1e-5, 1500.0, f64::NAN, f64::INFINITY] {
+      assert!(
+        matches!(
+          pressure_altitude(pressure),
+          Err(AtmosphereError::PressureOutOfRange(_))
+        ),
+        "{}",
+        pressure
+      );
+    }
+  }
+}
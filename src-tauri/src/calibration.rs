@@ -0,0 +1,506 @@
+//! Flight history and ascent calibration.
+//!
+//! Each flown balloon is recorded with its fill and what telemetry measured:
+//! the mean ascent rate and, when it was seen, the burst altitude. The
+//! history is kept in an SQLite database in the app data directory.
+//!
+//! Every flight is inverted through the [`AscentModel`] for the balloon
+//! model's drag coefficient and burst diameter. The mean ascent rate goes as
+//! 1/√Cd at a given fill, so the drag coefficient follows in closed form from
+//! the rate the model predicts; the burst diameter is the balloon's diameter
+//! at the observed burst altitude, which the drag does not affect. The
+//! per-flight values are averaged per balloon model with a 95% Student's t
+//! confidence interval, and once at least two flights back an estimate it
+//! replaces the catalog value in later calculations.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::ascent::AscentModel;
+use crate::catalog::{BalloonSpec, CatalogError, CatalogStore};
+use crate::neck_lift::{mean_ascent_rate, Fill};
+
+/// Flights needed before an estimate replaces the catalog value.
+const MIN_FLIGHTS: usize = 2;
+
+/// Two-sided 95% Student's t quantiles for 1 to 30 degrees of freedom.
+const T_95: [f64; 30] = [
+  12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+  2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052,
+  2.048, 2.045, 2.042,
+];
+
+/// A flown balloon and what telemetry measured.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlightRecord {
+  /// Set when the flight is recorded
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub id: Option<i64>,
+  /// Catalog balloon id
+  pub balloon: String,
+  /// Catalog gas id
+  pub gas: String,
+  /// Lift measured at the neck, payload plus free lift, kg
+  pub neck_lift: f64,
+  /// Payload, parachute and rigging, kg
+  pub payload_mass: f64,
+  /// m
+  pub launch_altitude: f64,
+  /// Mean ascent rate from launch to burst, m/s
+  pub ascent_rate: f64,
+  /// m; `None` when telemetry lost the balloon before it burst
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub burst_altitude: Option<f64>,
+  pub flown_at: DateTime<Utc>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub notes: Option<String>,
+}
+
+impl FlightRecord {
+  fn is_valid(&self) -> bool {
+    let positive = |v: f64| v.is_finite() && v > 0.0;
+    positive(self.ascent_rate)
+      && self.payload_mass.is_finite()
+      && self.payload_mass >= 0.0
+      && positive(self.neck_lift - self.payload_mass)
+      && self.launch_altitude.is_finite()
+      && self
+        .burst_altitude
+        .is_none_or(|burst| burst.is_finite() && burst > self.launch_altitude)
+  }
+}
+
+/// A fitted value with its 95% confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Estimate {
+  pub value: f64,
+  /// `None` with a single flight
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub low: Option<f64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub high: Option<f64>,
+  pub flights: usize,
+}
+
+impl Estimate {
+  fn of(values: &[f64]) -> Option<Estimate> {
+    let n = values.len();
+    if n == 0 {
+      return None;
+    }
+    let value = values.iter().sum::<f64>() / n as f64;
+    let half_width = (n > 1).then(|| {
+      let variance = values.iter().map(|v| (v - value).powi(2)).sum::<f64>() / (n - 1) as f64;
+      let t = T_95.get(n - 2).copied().unwrap_or(1.96);
+      t * (variance / n as f64).sqrt()
+    });
+    Some(Estimate {
+      value,
+      low: half_width.map(|h| value - h),
+      high: half_width.map(|h| value + h),
+      flights: n,
+    })
+  }
+
+  /// The value, if enough flights back it.
+  fn applied(self) -> Option<f64> {
+    (self.flights >= MIN_FLIGHTS).then_some(self.value)
+  }
+}
+
+/// What one flight says about its balloon.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlightFit {
+  pub id: Option<i64>,
+  pub drag_coefficient: f64,
+  /// m; only for flights with an observed burst
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub burst_diameter: Option<f64>,
+}
+
+/// Fitted corrections for one balloon model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Calibration {
+  /// The catalog spec with the fitted values applied
+  pub spec: BalloonSpec,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub drag_coefficient: Option<Estimate>,
+  /// m
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub burst_diameter: Option<Estimate>,
+  pub fits: Vec<FlightFit>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CalibrationError {
+  #[error(transparent)]
+  Catalog(#[from] CatalogError),
+  #[error("flight history error: {0}")]
+  Store(String),
+  #[error("invalid flight: {0}")]
+  InvalidFlight(&'static str),
+}
+
+impl Serialize for CalibrationError {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.to_string())
+  }
+}
+
+fn store_error(error: impl std::fmt::Display) -> CalibrationError {
+  CalibrationError::Store(error.to_string())
+}
+
+/// Inverts one flight of `spec` (as catalogued, before calibration).
+fn fit(spec: &BalloonSpec, flight: &FlightRecord, catalog: &CatalogStore) -> Option<FlightFit> {
+  // Flights whose gas has since left the catalog are not fitted.
+  let gas = catalog.gas(&flight.gas).ok()?;
+  let fill = Fill {
+    spec,
+    gas: &gas,
+    launch_altitude: flight.launch_altitude,
+  };
+  let balloon = fill.balloon(flight.neck_lift, flight.payload_mass);
+  let model = AscentModel::new(balloon, flight.launch_altitude);
+  let top = flight
+    .burst_altitude
+    .or_else(|| model.burst_altitude(flight.launch_altitude))?;
+  let rate = mean_ascent_rate(&model, flight.launch_altitude, top);
+  Some(FlightFit {
+    id: flight.id,
+    drag_coefficient: balloon.drag_coefficient * (rate / flight.ascent_rate).powi(2),
+    burst_diameter: flight.burst_altitude.map(|burst| 2.0 * model.radius(burst)),
+  })
+}
+
+/// Fits `spec` to its recorded `flights`.
+pub fn calibrate(
+  spec: &BalloonSpec,
+  flights: &[FlightRecord],
+  catalog: &CatalogStore,
+) -> Calibration {
+  let fits: Vec<FlightFit> = flights
+    .iter()
+    .filter(|flight| flight.balloon == spec.id)
+    .filter_map(|flight| fit(spec, flight, catalog))
+    .collect();
+  let drag: Vec<f64> = fits.iter().map(|f| f.drag_coefficient).collect();
+  let burst: Vec<f64> = fits.iter().filter_map(|f| f.burst_diameter).collect();
+  let drag_coefficient = Estimate::of(&drag);
+  let burst_diameter = Estimate::of(&burst);
+  let mut calibrated = spec.clone();
+  if let Some(value) = drag_coefficient.and_then(Estimate::applied) {
+    calibrated.drag_coefficient = Some(value);
+  }
+  if let Some(value) = burst_diameter.and_then(Estimate::applied) {
+    calibrated.burst_diameter = value;
+  }
+  Calibration {
+    spec: calibrated,
+    drag_coefficient,
+    burst_diameter,
+    fits,
+  }
+}
+
+pub struct FlightHistory {
+  conn: Mutex<Connection>,
+}
+
+impl FlightHistory {
+  /// Opens (or creates) the flight history database in `dir`.
+  pub fn open(dir: &Path) -> Result<Self, CalibrationError> {
+    std::fs::create_dir_all(dir).map_err(store_error)?;
+    Self::with_connection(Connection::open(dir.join("flights.sqlite")).map_err(store_error)?)
+  }
+
+  #[cfg(test)]
+  pub fn open_in_memory() -> Result<Self, CalibrationError> {
+    Self::with_connection(Connection::open_in_memory().map_err(store_error)?)
+  }
+
+  fn with_connection(conn: Connection) -> Result<Self, CalibrationError> {
+    conn
+      .execute_batch(
+        "CREATE TABLE IF NOT EXISTS flights (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          balloon TEXT NOT NULL,
+          flown_at INTEGER NOT NULL,
+          flight TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS flights_balloon ON flights (balloon);",
+      )
+      .map_err(store_error)?;
+    Ok(FlightHistory {
+      conn: Mutex::new(conn),
+    })
+  }
+
+  fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+    self
+      .conn
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  /// Records `flight` under a new id.
+  pub fn add(&self, flight: FlightRecord) -> Result<FlightRecord, CalibrationError> {
+    if !flight.is_valid() {
+      return Err(CalibrationError::InvalidFlight(
+        "the ascent rate and free lift must be positive, the payload non-negative and any \
+         burst above the launch",
+      ));
+    }
+    let flight = FlightRecord { id: None, ..flight };
+    let json = serde_json::to_string(&flight).map_err(store_error)?;
+    let conn = self.conn();
+    conn
+      .execute(
+        "INSERT INTO flights (balloon, flown_at, flight) VALUES (?1, ?2, ?3)",
+        params![flight.balloon, flight.flown_at.timestamp(), json],
+      )
+      .map_err(store_error)?;
+    Ok(FlightRecord {
+      id: Some(conn.last_insert_rowid()),
+      ..flight
+    })
+  }
+
+  /// Lists recorded flights, optionally of one balloon model only, most
+  /// recent first.
+  pub fn list(&self, balloon: Option<&str>) -> Result<Vec<FlightRecord>, CalibrationError> {
+    let conn = self.conn();
+    let mut statement = conn
+      .prepare(
+        "SELECT id, flight FROM flights WHERE ?1 IS NULL OR balloon = ?1
+         ORDER BY flown_at DESC, id DESC",
+      )
+      .map_err(store_error)?;
+    let rows = statement
+      .query_map(params![balloon], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+      })
+      .map_err(store_error)?;
+    rows
+      .map(|row| {
+        let (id, json) = row.map_err(store_error)?;
+        let flight: FlightRecord = serde_json::from_str(&json).map_err(store_error)?;
+        Ok(FlightRecord {
+          id: Some(id),
+          ..flight
+        })
+      })
+      .collect()
+  }
+
+  /// Removes a flight. Returns whether it existed.
+  pub fn delete(&self, id: i64) -> Result<bool, CalibrationError> {
+    let removed = self
+      .conn()
+      .execute("DELETE FROM flights WHERE id = ?1", params![id])
+      .map_err(store_error)?;
+    Ok(removed > 0)
+  }
+
+  /// The calibration of catalog balloon `id` from its recorded flights.
+  pub fn calibration(
+    &self,
+    catalog: &CatalogStore,
+    id: &str,
+  ) -> Result<Calibration, CalibrationError> {
+    let spec = catalog.balloon(id)?;
+    Ok(calibrate(&spec, &self.list(Some(id))?, catalog))
+  }
+
+  /// Catalog balloon `id` with its fitted corrections applied.
+  pub fn calibrated_balloon(
+    &self,
+    catalog: &CatalogStore,
+    id: &str,
+  ) -> Result<BalloonSpec, CalibrationError> {
+    Ok(self.calibration(catalog, id)?.spec)
+  }
+}
+
+#[tauri::command]
+pub fn record_flight(
+  history: State<'_, FlightHistory>,
+  catalog: State<'_, CatalogStore>,
+  flight: FlightRecord,
+) -> Result<FlightRecord, CalibrationError> {
+  catalog.balloon(&flight.balloon)?;
+  catalog.gas(&flight.gas)?;
+  history.add(flight)
+}
+
+#[tauri::command]
+pub fn list_flights(
+  history: State<'_, FlightHistory>,
+  balloon: Option<String>,
+) -> Result<Vec<FlightRecord>, CalibrationError> {
+  history.list(balloon.as_deref())
+}
+
+#[tauri::command]
+pub fn delete_flight(history: State<'_, FlightHistory>, id: i64) -> Result<bool, CalibrationError> {
+  history.delete(id)
+}
+
+#[tauri::command]
+pub fn balloon_calibration(
+  history: State<'_, FlightHistory>,
+  catalog: State<'_, CatalogStore>,
+  balloon: String,
+) -> Result<Calibration, CalibrationError> {
+  history.calibration(&catalog, &balloon)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::weather::parse_time;
+
+  /// Flights of a TA-1200 that really has `drag` and bursts at
+  /// `burst_scale` times its rated diameter, on slightly different fills.
+  fn flights(catalog: &CatalogStore, drag: f64, burst_scale: f64) -> Vec<FlightRecord> {
+    let rated = catalog.balloon("totex-ta-1200").unwrap();
+    let real = BalloonSpec {
+      drag_coefficient: Some(drag),
+      burst_diameter: rated.burst_diameter * burst_scale,
+      ..rated
+    };
+    let gas = catalog.gas("helium").unwrap();
+    [2.3, 2.5, 2.7]
+      .into_iter()
+      .map(|neck_lift| {
+        let fill = Fill {
+          spec: &real,
+          gas: &gas,
+          launch_altitude: 200.0,
+        };
+        let model = AscentModel::new(fill.balloon(neck_lift, 1.0), 200.0);
+        let burst = model.burst_altitude(200.0).unwrap();
+        FlightRecord {
+          id: None,
+          balloon: "totex-ta-1200".into(),
+          gas: "helium".into(),
+          neck_lift,
+          payload_mass: 1.0,
+          launch_altitude: 200.0,
+          ascent_rate: mean_ascent_rate(&model, 200.0, burst),
+          burst_altitude: Some(burst),
+          flown_at: parse_time("2025-06-01T12:00:00Z").unwrap(),
+          notes: None,
+        }
+      })
+      .collect()
+  }
+
+  #[test]
+  fn recovers_drag_and_burst_diameter_from_flights() {
+    let catalog = CatalogStore::in_memory().unwrap();
+    let rated = catalog.balloon("totex-ta-1200").unwrap();
+    let flown = flights(&catalog, 0.36, 1.05);
+
+    let calibration = calibrate(&rated, &flown, &catalog);
+    let drag = calibration.drag_coefficient.unwrap();
+    assert_eq!(drag.flights, 3);
+    assert!((drag.value - 0.36).abs() < 1e-3, "{:?}", drag);
+    let burst = calibration.burst_diameter.unwrap();
+    // Within the 1 m burst search.
+    assert!(
+      (burst.value - rated.burst_diameter * 1.05).abs() < 0.01,
+      "{:?}",
+      burst
+    );
+    assert!(burst.low.unwrap() <= burst.value && burst.high.unwrap() >= burst.value);
+    assert_eq!(calibration.spec.drag_coefficient, Some(drag.value));
+    assert_eq!(calibration.spec.burst_diameter, burst.value);
+
+    // One flight reports an estimate but does not change the spec.
+    let single = calibrate(&rated, &flown[..1], &catalog);
+    assert_eq!(single.drag_coefficient.unwrap().low, None);
+    assert_eq!(single.spec, rated);
+  }
+
+  #[test]
+  fn confidence_interval_widens_with_scatter() {
+    let catalog = CatalogStore::in_memory().unwrap();
+    let rated = catalog.balloon("totex-ta-1200").unwrap();
+    let mut flown = flights(&catalog, 0.33, 1.0);
+    let tight = calibrate(&rated, &flown, &catalog)
+      .drag_coefficient
+      .unwrap();
+
+    // A slow and a fast flight: Cd goes as 1/rate².
+    flown[0].ascent_rate *= 0.95;
+    flown[2].ascent_rate *= 1.05;
+    let loose = calibrate(&rated, &flown, &catalog)
+      .drag_coefficient
+      .unwrap();
+    let width = |e: Estimate| e.high.unwrap() - e.low.unwrap();
+    assert!(width(tight) < 1e-3, "{:?}", tight);
+    assert!(width(loose) > 0.05, "{:?}", loose);
+    assert!(loose.low.unwrap() < 0.33 && loose.high.unwrap() > 0.33);
+
+    // Flights without a burst still fit the drag.
+    for flight in &mut flown {
+      flight.burst_altitude = None;
+    }
+    let unburst = calibrate(&rated, &flown, &catalog);
+    assert_eq!(unburst.drag_coefficient.unwrap().flights, 3);
+    assert_eq!(unburst.burst_diameter, None);
+    assert_eq!(unburst.spec.burst_diameter, rated.burst_diameter);
+  }
+
+  #[test]
+  fn stores_flights_and_calibrates_from_them() {
+    let catalog = CatalogStore::in_memory().unwrap();
+    let history = FlightHistory::open_in_memory().unwrap();
+    let mut flown = flights(&catalog, 0.36, 1.0);
+    flown[2].flown_at = parse_time("2025-07-01T12:00:00Z").unwrap();
+    let ids: Vec<i64> = flown
+      .iter()
+      .map(|flight| history.add(flight.clone()).unwrap().id.unwrap())
+      .collect();
+    history
+      .add(FlightRecord {
+        balloon: "totex-ta-300".into(),
+        ..flown[0].clone()
+      })
+      .unwrap();
+
+    let listed = history.list(Some("totex-ta-1200")).unwrap();
+    assert_eq!(listed.len(), 3);
+    assert_eq!(listed[0].id, Some(ids[2]));
+    assert_eq!(history.list(None).unwrap().len(), 4);
+
+    let spec = history
+      .calibrated_balloon(&catalog, "totex-ta-1200")
+      .unwrap();
+    assert!((spec.drag_coefficient.unwrap() - 0.36).abs() < 1e-3);
+
+    assert!(history.delete(ids[0]).unwrap());
+    assert!(!history.delete(ids[0]).unwrap());
+    let calibration = history.calibration(&catalog, "totex-ta-1200").unwrap();
+    assert_eq!(calibration.fits.len(), 2);
+
+    let sinking = FlightRecord {
+      neck_lift: 0.9,
+      ..flown[0].clone()
+    };
+    assert!(matches!(
+      history.add(sinking),
+      Err(CalibrationError::InvalidFlight(_))
+    ));
+  }
+}
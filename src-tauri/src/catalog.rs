@@ -0,0 +1,569 @@
+//! Balloon, parachute, lift gas and gas cylinder catalog.
+//!
+//! Manufacturer specs ship with the app in `data/catalog.json`, versioned so
+//! predictions can be traced to the numbers they used. Users add their own
+//! entries, or correct bundled ones under the same id, in `catalog.json` in
+//! the app data directory; those take precedence over the bundled specs.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::ascent::LiftGas;
+
+const BUNDLED_CATALOG: &str = include_str!("../data/catalog.json");
+
+/// A latex balloon model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalloonSpec {
+  pub id: String,
+  pub manufacturer: String,
+  pub model: String,
+  /// Envelope mass, kg
+  pub mass: f64,
+  /// Rated burst diameter, m
+  pub burst_diameter: f64,
+  /// Manufacturer's recommended free lift, kg. Hwoyee and Pawan publish
+  /// none, so their bundled entries carry the Totex figure for the same
+  /// envelope mass
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub recommended_free_lift: Option<f64>,
+  /// Overrides the default balloon drag coefficient
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub drag_coefficient: Option<f64>,
+  /// Whether the entry comes from the user's catalog
+  #[serde(default)]
+  pub custom: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParachuteSpec {
+  pub id: String,
+  pub manufacturer: String,
+  pub model: String,
+  /// Canopy diameter, m
+  pub diameter: f64,
+  pub drag_coefficient: f64,
+  /// kg
+  pub mass: f64,
+  #[serde(default)]
+  pub custom: bool,
+}
+
+/// A lift gas as supplied, diluted with air to `purity`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasSpec {
+  pub id: String,
+  pub name: String,
+  pub gas: LiftGas,
+  /// Mole fraction of the lift gas, 0-1
+  pub purity: f64,
+  #[serde(default)]
+  pub custom: bool,
+}
+
+/// A gas cylinder size.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CylinderSpec {
+  pub id: String,
+  pub name: String,
+  /// Internal volume, L
+  pub water_volume: f64,
+  /// Full pressure, bar gauge
+  pub fill_pressure: f64,
+  #[serde(default)]
+  pub custom: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Catalog {
+  /// Version of the bundled specs
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub version: String,
+  #[serde(default)]
+  pub balloons: Vec<BalloonSpec>,
+  #[serde(default)]
+  pub parachutes: Vec<ParachuteSpec>,
+  #[serde(default)]
+  pub gases: Vec<GasSpec>,
+  #[serde(default)]
+  pub cylinders: Vec<CylinderSpec>,
+}
+
+/// One entry of any kind, as saved by the user.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CatalogEntry {
+  Balloon(BalloonSpec),
+  Parachute(ParachuteSpec),
+  Gas(GasSpec),
+  Cylinder(CylinderSpec),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EntryKind {
+  Balloon,
+  Parachute,
+  Gas,
+  Cylinder,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CatalogError {
+  #[error("catalog file error: {0}")]
+  Io(String),
+  #[error("invalid catalog file: {0}")]
+  Parse(String),
+  #[error("invalid catalog entry: {0}")]
+  InvalidEntry(&'static str),
+  #[error("no catalog entry {0:?}")]
+  NotFound(String),
+  #[error("{0:?} is a bundled entry and cannot be deleted")]
+  Bundled(String),
+}
+
+impl Serialize for CatalogError {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.to_string())
+  }
+}
+
+/// What the catalog needs from every kind of entry.
+trait Spec: Clone {
+  fn id(&self) -> &str;
+  fn set_custom(&mut self);
+  fn check(&self) -> Result<(), &'static str>;
+}
+
+fn positive(value: f64) -> bool {
+  value.is_finite() && value > 0.0
+}
+
+impl Spec for BalloonSpec {
+  fn id(&self) -> &str {
+    &self.id
+  }
+
+  fn set_custom(&mut self) {
+    self.custom = true;
+  }
+
+  fn check(&self) -> Result<(), &'static str> {
+    let optional = [self.recommended_free_lift, self.drag_coefficient];
+    if positive(self.mass)
+      && positive(self.burst_diameter)
+      && optional.into_iter().flatten().all(positive)
+    {
+      Ok(())
+    } else {
+      Err("balloon mass, burst diameter, lift and drag coefficient must be positive")
+    }
+  }
+}
+
+impl Spec for ParachuteSpec {
+  fn id(&self) -> &str {
+    &self.id
+  }
+
+  fn set_custom(&mut self) {
+    self.custom = true;
+  }
+
+  fn check(&self) -> Result<(), &'static str> {
+    if positive(self.diameter) && positive(self.drag_coefficient) && self.mass >= 0.0 {
+      Ok(())
+    } else {
+      Err("parachute diameter and drag coefficient must be positive")
+    }
+  }
+}
+
+impl Spec for GasSpec {
+  fn id(&self) -> &str {
+    &self.id
+  }
+
+  fn set_custom(&mut self) {
+    self.custom = true;
+  }
+
+  fn check(&self) -> Result<(), &'static str> {
+    if positive(self.purity) && self.purity <= 1.0 {
+      Ok(())
+    } else {
+      Err("gas purity must be above 0 and at most 1")
+    }
+  }
+}
+
+impl Spec for CylinderSpec {
+  fn id(&self) -> &str {
+    &self.id
+  }
+
+  fn set_custom(&mut self) {
+    self.custom = true;
+  }
+
+  fn check(&self) -> Result<(), &'static str> {
+    if positive(self.water_volume) && positive(self.fill_pressure) {
+      Ok(())
+    } else {
+      Err("cylinder volume and fill pressure must be positive")
+    }
+  }
+}
+
+/// Bundled entries, with user entries replacing those of the same id and
+/// following the rest.
+fn merged<T: Spec>(bundled: &[T], user: &[T]) -> Vec<T> {
+  let mut entries: Vec<T> = bundled
+    .iter()
+    .filter(|entry| user.iter().all(|u| u.id() != entry.id()))
+    .cloned()
+    .collect();
+  entries.extend(user.iter().cloned());
+  entries
+}
+
+/// Adds or replaces `entry` in `entries`.
+fn upsert<T: Spec>(entries: &mut Vec<T>, mut entry: T) -> Result<T, CatalogError> {
+  entry.check().map_err(CatalogError::InvalidEntry)?;
+  if entry.id().trim().is_empty() {
+    return Err(CatalogError::InvalidEntry("entries need an id"));
+  }
+  entry.set_custom();
+  match entries.iter_mut().find(|e| e.id() == entry.id()) {
+    Some(existing) => *existing = entry.clone(),
+    None => entries.push(entry.clone()),
+  }
+  Ok(entry)
+}
+
+/// Removes the entry `id`. Returns whether it existed.
+fn remove<T: Spec>(entries: &mut Vec<T>, id: &str) -> bool {
+  let before = entries.len();
+  entries.retain(|e| e.id() != id);
+  entries.len() < before
+}
+
+pub struct CatalogStore {
+  bundled: Catalog,
+  /// User catalog file; `None` keeps user entries in memory only
+  path: Option<PathBuf>,
+  user: Mutex<Catalog>,
+}
+
+impl CatalogStore {
+  /// Loads the bundled catalog and the user's entries from `dir`.
+  pub fn open(dir: &Path) -> Result<Self, CatalogError> {
+    let path = dir.join("catalog.json");
+    let user = match std::fs::read_to_string(&path) {
+      Ok(json) => serde_json::from_str(&json).map_err(|e| CatalogError::Parse(e.to_string()))?,
+      Err(error) if error.kind() == std::io::ErrorKind::NotFound => Catalog::default(),
+      Err(error) => return Err(CatalogError::Io(error.to_string())),
+    };
+    Self::with_user(Some(path), user)
+  }
+
+  #[cfg(test)]
+  pub fn in_memory() -> Result<Self, CatalogError> {
+    Self::with_user(None, Catalog::default())
+  }
+
+  fn with_user(path: Option<PathBuf>, mut user: Catalog) -> Result<Self, CatalogError> {
+    let bundled = serde_json::from_str(BUNDLED_CATALOG)
+      .map_err(|e| CatalogError::Parse(format!("bundled catalog: {}", e)))?;
+    user.balloons.iter_mut().for_each(Spec::set_custom);
+    user.parachutes.iter_mut().for_each(Spec::set_custom);
+    user.gases.iter_mut().for_each(Spec::set_custom);
+    user.cylinders.iter_mut().for_each(Spec::set_custom);
+    Ok(CatalogStore {
+      bundled,
+      path,
+      user: Mutex::new(user),
+    })
+  }
+
+  fn user(&self) -> std::sync::MutexGuard<'_, Catalog> {
+    self
+      .user
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  /// The bundled catalog merged with the user's entries.
+  pub fn catalog(&self) -> Catalog {
+    let user = self.user();
+    Catalog {
+      version: self.bundled.version.clone(),
+      balloons: merged(&self.bundled.balloons, &user.balloons),
+      parachutes: merged(&self.bundled.parachutes, &user.parachutes),
+      gases: merged(&self.bundled.gases, &user.gases),
+      cylinders: merged(&self.bundled.cylinders, &user.cylinders),
+    }
+  }
+
+  pub fn balloon(&self, id: &str) -> Result<BalloonSpec, CatalogError> {
+    let balloons = self.catalog().balloons;
+    balloons
+      .into_iter()
+      .find(|b| b.id == id)
+      .ok_or_else(|| CatalogError::NotFound(id.to_string()))
+  }
+
+  pub fn parachute(&self, id: &str) -> Result<ParachuteSpec, CatalogError> {
+    let parachutes = self.catalog().parachutes;
+    parachutes
+      .into_iter()
+      .find(|p| p.id == id)
+      .ok_or_else(|| CatalogError::NotFound(id.to_string()))
+  }
+
+  pub fn gas(&self, id: &str) -> Result<GasSpec, CatalogError> {
+    let gases = self.catalog().gases;
+    gases
+      .into_iter()
+      .find(|g| g.id == id)
+      .ok_or_else(|| CatalogError::NotFound(id.to_string()))
+  }
+
+  /// Adds or replaces a user entry and writes the user catalog.
+  pub fn save(&self, entry: CatalogEntry) -> Result<CatalogEntry, CatalogError> {
+    let mut user = self.user();
+    let saved = match entry {
+      CatalogEntry::Balloon(spec) => CatalogEntry::Balloon(upsert(&mut user.balloons, spec)?),
+      CatalogEntry::Parachute(spec) => CatalogEntry::Parachute(upsert(&mut user.parachutes, spec)?),
+      CatalogEntry::Gas(spec) => CatalogEntry::Gas(upsert(&mut user.gases, spec)?),
+      CatalogEntry::Cylinder(spec) => CatalogEntry::Cylinder(upsert(&mut user.cylinders, spec)?),
+    };
+    self.write(&user)?;
+    Ok(saved)
+  }
+
+  /// Removes a user entry, which restores a bundled entry it replaced.
+  /// Returns whether it existed.
+  pub fn delete(&self, kind: EntryKind, id: &str) -> Result<bool, CatalogError> {
+    let mut user = self.user();
+    let (removed, bundled) = match kind {
+      EntryKind::Balloon => (
+        remove(&mut user.balloons, id),
+        self.bundled.balloons.iter().any(|b| b.id == id),
+      ),
+      EntryKind::Parachute => (
+        remove(&mut user.parachutes, id),
+        self.bundled.parachutes.iter().any(|p| p.id == id),
+      ),
+      EntryKind::Gas => (
+        remove(&mut user.gases, id),
+        self.bundled.gases.iter().any(|g| g.id == id),
+      ),
+      EntryKind::Cylinder => (
+        remove(&mut user.cylinders, id),
+        self.bundled.cylinders.iter().any(|c| c.id == id),
+      ),
+    };
+    if !removed && bundled {
+      return Err(CatalogError::Bundled(id.to_string()));
+    }
+    if removed {
+      self.write(&user)?;
+    }
+    Ok(removed)
+  }
+
+  fn write(&self, user: &Catalog) -> Result<(), CatalogError> {
+    let Some(path) = &self.path else {
+      return Ok(());
+    };
+    let io = |e: std::io::Error| CatalogError::Io(e.to_string());
+    if let Some(dir) = path.parent() {
+      std::fs::create_dir_all(dir).map_err(io)?;
+    }
+    let json = serde_json::to_string_pretty(user).map_err(|e| CatalogError::Io(e.to_string()))?;
+    std::fs::write(path, json).map_err(io)
+  }
+}
+
+#[tauri::command]
+pub fn get_catalog(store: State<'_, CatalogStore>) -> Catalog {
+  store.catalog()
+}
+
+/// Balloons, optionally of one manufacturer only.
+#[tauri::command]
+pub fn list_balloons(
+  store: State<'_, CatalogStore>,
+  manufacturer: Option<String>,
+) -> Vec<BalloonSpec> {
+  let mut balloons = store.catalog().balloons;
+  if let Some(manufacturer) = manufacturer {
+    balloons.retain(|b| b.manufacturer.eq_ignore_ascii_case(&manufacturer));
+  }
+  balloons
+}
+
+#[tauri::command]
+pub fn list_parachutes(store: State<'_, CatalogStore>) -> Vec<ParachuteSpec> {
+  store.catalog().parachutes
+}
+
+#[tauri::command]
+pub fn list_gases(store: State<'_, CatalogStore>) -> Vec<GasSpec> {
+  store.catalog().gases
+}
+
+#[tauri::command]
+pub fn list_cylinders(store: State<'_, CatalogStore>) -> Vec<CylinderSpec> {
+  store.catalog().cylinders
+}
+
+#[tauri::command]
+pub fn get_balloon(
+  store: State<'_, CatalogStore>,
+  id: String,
+) -> Result<BalloonSpec, CatalogError> {
+  store.balloon(&id)
+}
+
+#[tauri::command]
+pub fn get_parachute(
+  store: State<'_, CatalogStore>,
+  id: String,
+) -> Result<ParachuteSpec, CatalogError> {
+  store.parachute(&id)
+}
+
+#[tauri::command]
+pub fn save_catalog_entry(
+  store: State<'_, CatalogStore>,
+  entry: CatalogEntry,
+) -> Result<CatalogEntry, CatalogError> {
+  store.save(entry)
+}
+
+#[tauri::command]
+pub fn delete_catalog_entry(
+  store: State<'_, CatalogStore>,
+  kind: EntryKind,
+  id: String,
+) -> Result<bool, CatalogError> {
+  store.delete(kind, &id)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn bundles_manufacturer_specs() {
+    let store = CatalogStore::in_memory().unwrap();
+    let catalog = store.catalog();
+    assert!(!catalog.version.is_empty());
+    for manufacturer in ["Kaymont/Totex", "Hwoyee", "Pawan"] {
+      assert!(catalog
+        .balloons
+        .iter()
+        .any(|b| b.manufacturer == manufacturer));
+    }
+    assert!(catalog
+      .balloons
+      .iter()
+      .all(|b| b.check().is_ok() && !b.custom && b.recommended_free_lift.is_some()));
+    assert!(catalog.parachutes.iter().all(|p| p.check().is_ok()));
+    assert!(catalog.gases.iter().all(|g| g.check().is_ok()));
+    assert!(catalog.cylinders.iter().all(|c| c.check().is_ok()));
+
+    let ta1200 = store.balloon("totex-ta-1200").unwrap();
+    assert_eq!((ta1200.mass, ta1200.burst_diameter), (1.2, 8.63));
+    assert!(matches!(
+      store.parachute("none"),
+      Err(CatalogError::NotFound(_))
+    ));
+  }
+
+  #[test]
+  fn user_entries_override_bundled_ones() {
+    struct TempDir(PathBuf);
+    impl Drop for TempDir {
+      fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+      }
+    }
+    let dir = TempDir(std::env::temp_dir().join(format!("blips-catalog-{}", std::process::id())));
+    let dir = &dir.0;
+    let store = CatalogStore::open(dir).unwrap();
+    let bundled = store.balloon("totex-ta-1200").unwrap();
+
+    let corrected = BalloonSpec {
+      burst_diameter: 8.2,
+      ..bundled.clone()
+    };
+    store.save(CatalogEntry::Balloon(corrected)).unwrap();
+    let chute = ParachuteSpec {
+      id: "club-chute".into(),
+      manufacturer: "Club".into(),
+      model: "Ripstop 1 m".into(),
+      diameter: 1.0,
+      drag_coefficient: 1.2,
+      mass: 0.08,
+      custom: false,
+    };
+    store.save(CatalogEntry::Parachute(chute)).unwrap();
+
+    // Entries persist in the user file.
+    let reopened = CatalogStore::open(dir).unwrap();
+    let balloon = reopened.balloon("totex-ta-1200").unwrap();
+    assert_eq!(balloon.burst_diameter, 8.2);
+    assert!(balloon.custom);
+    assert_eq!(
+      reopened.catalog().balloons.len(),
+      store.bundled.balloons.len()
+    );
+    assert!(reopened.parachute("club-chute").unwrap().custom);
+
+    // Deleting the override restores the bundled spec; bundled entries
+    // themselves stay.
+    assert!(reopened
+      .delete(EntryKind::Balloon, "totex-ta-1200")
+      .unwrap());
+    assert_eq!(reopened.balloon("totex-ta-1200").unwrap(), bundled);
+    assert!(matches!(
+      reopened.delete(EntryKind::Balloon, "totex-ta-1200"),
+      Err(CatalogError::Bundled(_))
+    ));
+    assert!(!reopened.delete(EntryKind::Gas, "argon").unwrap());
+    assert!(matches!(
+      reopened.save(CatalogEntry::Gas(GasSpec {
+        id: "weak".into(),
+        name: "Weak".into(),
+        gas: LiftGas::Helium,
+        purity: 1.5,
+        custom: true,
+      })),
+      Err(CatalogError::InvalidEntry(_))
+    ));
+  }
+
+  #[test]
+  fn entries_are_tagged_by_kind() {
+    let entry: CatalogEntry = serde_json::from_str(
+      r#"{"kind": "gas", "id": "h2", "name": "Hydrogen", "gas": "Hydrogen", "purity": 0.999}"#,
+    )
+    .unwrap();
+    let CatalogEntry::Gas(gas) = entry else {
+      panic!("{:?}", entry);
+    };
+    assert_eq!(gas.gas, LiftGas::Hydrogen);
+    assert!(!gas.custom);
+  }
+}
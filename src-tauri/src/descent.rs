@@ -0,0 +1,85 @@
+//! Descent under a parachute.
+//!
+//! A falling payload reaches terminal velocity within seconds, so its sink
+//! rate is where weight balances drag: v = √(2 m g / (ρ Cd A)). Thin air
+//! near burst makes it several times the landing speed. Without parachute
+//! specs, the entered descent rate is taken as the sea-level value and
+//! scaled by √(ρ₀ / ρ), as CUSF and Tawhiri do.
+
+use serde::{Deserialize, Serialize};
+
+use crate::atmosphere::{state_at, G0};
+
+/// Parachute and the mass hanging under it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Parachute {
+  /// Canopy diameter, m
+  pub diameter: f64,
+  pub drag_coefficient: f64,
+  /// Everything under the canopy, including the burst balloon remains, kg
+  pub payload_mass: f64,
+}
+
+impl Parachute {
+  /// Canopy reference area, m²
+  pub fn area(&self) -> f64 {
+    std::f64::consts::PI * (self.diameter / 2.0).powi(2)
+  }
+
+  /// Terminal velocity in air of `density` (kg/m³), m/s
+  pub fn terminal_velocity(&self, density: f64) -> f64 {
+    (2.0 * self.payload_mass * G0 / (density * self.drag_coefficient * self.area())).sqrt()
+  }
+
+  pub fn is_valid(&self) -> bool {
+    [self.diameter, self.drag_coefficient, self.payload_mass]
+      .iter()
+      .all(|v| v.is_finite() && *v > 0.0)
+  }
+}
+
+/// Sink rate at `altitude` (m), m/s. Uses the drag model when `parachute`
+/// is given, and otherwise scales `sea_level_rate` with air density.
+pub fn descent_rate(sea_level_rate: f64, parachute: Option<&Parachute>, altitude: f64) -> f64 {
+  let density = state_at(altitude).density;
+  match parachute {
+    Some(parachute) => parachute.terminal_velocity(density),
+    None => sea_level_rate * (state_at(0.0).density / density).sqrt(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sea_level_rate_speeds_up_in_thin_air() {
+    assert!((descent_rate(5.0, None, 0.0) - 5.0).abs() < 1e-12);
+    // ρ at 30 km is about 1/67 of sea level.
+    let at_burst = descent_rate(5.0, None, 30000.0);
+    assert!((at_burst - 40.8).abs() < 0.1, "{}", at_burst);
+    assert!(descent_rate(5.0, None, 10000.0) < at_burst);
+  }
+
+  #[test]
+  fn parachute_terminal_velocity() {
+    // A 1 kg payload under a 0.9 m chute with Cd 1.5.
+    let parachute = Parachute {
+      diameter: 0.9,
+      drag_coefficient: 1.5,
+      payload_mass: 1.0,
+    };
+    let sea_level = descent_rate(99.0, Some(&parachute), 0.0);
+    assert!((sea_level - 4.10).abs() < 0.01, "{}", sea_level);
+    // Same density scaling as the sea-level model.
+    let ratio = descent_rate(0.0, Some(&parachute), 25000.0) / sea_level;
+    assert!((ratio - descent_rate(1.0, None, 25000.0)).abs() < 1e-9);
+
+    assert!(!Parachute {
+      diameter: 0.0,
+      ..parachute
+    }
+    .is_valid());
+  }
+}
@@ -0,0 +1,402 @@
+//! On-disk elevation cache.
+//!
+//! Replaces the `blips_elevation_` entries `elevationService.ts` used to keep
+//! in `localStorage`. Single points are keyed by their coordinates rounded
+//! to four decimals (about 11 m), stored as integers so the primary key
+//! doubles as a spatial index. Grids keep the key the frontend used: rounded
+//! center, radius and grid size. Entries live for 24 hours, and once a table
+//! is over capacity the least recently used rows are evicted.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use super::ElevationError;
+
+/// Same as `COORDINATE_PRECISION` in the old frontend cache.
+const COORDINATE_SCALE: f64 = 10_000.0;
+const MAX_AGE_HOURS: i64 = 24;
+const MAX_POINTS: usize = 100_000;
+const MAX_GRIDS: usize = 1_000;
+
+/// A ground elevation at one coordinate.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElevationPoint {
+  pub lat: f64,
+  pub lon: f64,
+  /// meters
+  pub elevation: f64,
+}
+
+/// Same shape as `getCacheStats()` used to return.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElevationCacheStats {
+  pub single_points: usize,
+  pub grids: usize,
+  /// Size of the cache database, bytes
+  pub total_size: usize,
+}
+
+fn cache_error(error: impl std::fmt::Display) -> ElevationError {
+  ElevationError::Cache(error.to_string())
+}
+
+/// Rounds a coordinate to the cache resolution.
+fn cell(degrees: f64) -> i64 {
+  (degrees * COORDINATE_SCALE).round() as i64
+}
+
+pub struct ElevationCache {
+  conn: Mutex<Connection>,
+  max_points: usize,
+  max_grids: usize,
+}
+
+impl ElevationCache {
+  /// Opens (or creates) the cache database in `dir`.
+  pub fn open(dir: &Path) -> Result<Self, ElevationError> {
+    std::fs::create_dir_all(dir).map_err(cache_error)?;
+    Self::with_connection(
+      Connection::open(dir.join("elevation_cache.sqlite")).map_err(cache_error)?,
+      MAX_POINTS,
+      MAX_GRIDS,
+    )
+  }
+
+  #[cfg(test)]
+  pub fn open_in_memory(max_points: usize, max_grids: usize) -> Result<Self, ElevationError> {
+    Self::with_connection(
+      Connection::open_in_memory().map_err(cache_error)?,
+      max_points,
+      max_grids,
+    )
+  }
+
+  fn with_connection(
+    conn: Connection,
+    max_points: usize,
+    max_grids: usize,
+  ) -> Result<Self, ElevationError> {
+    conn
+      .execute_batch(
+        "CREATE TABLE IF NOT EXISTS points (
+          lat_cell INTEGER NOT NULL,
+          lon_cell INTEGER NOT NULL,
+          elevation REAL NOT NULL,
+          created_at INTEGER NOT NULL,
+          accessed_at INTEGER NOT NULL,
+          PRIMARY KEY (lat_cell, lon_cell)
+        );
+        CREATE INDEX IF NOT EXISTS points_accessed ON points (accessed_at);
+        CREATE TABLE IF NOT EXISTS grids (
+          lat_cell INTEGER NOT NULL,
+          lon_cell INTEGER NOT NULL,
+          radius REAL NOT NULL,
+          grid_size INTEGER NOT NULL,
+          grid TEXT NOT NULL,
+          created_at INTEGER NOT NULL,
+          accessed_at INTEGER NOT NULL,
+          PRIMARY KEY (lat_cell, lon_cell, radius, grid_size)
+        );
+        CREATE INDEX IF NOT EXISTS grids_accessed ON grids (accessed_at);",
+      )
+      .map_err(cache_error)?;
+    Ok(ElevationCache {
+      conn: Mutex::new(conn),
+      max_points,
+      max_grids,
+    })
+  }
+
+  fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+    self
+      .conn
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  fn cutoff(now: DateTime<Utc>) -> i64 {
+    (now - Duration::hours(MAX_AGE_HOURS)).timestamp()
+  }
+
+  /// Returns the cached elevation for each coordinate, `None` where the
+  /// cell is missing or expired. Hits are marked as recently used.
+  pub fn get_points(
+    &self,
+    coordinates: &[(f64, f64)],
+    now: DateTime<Utc>,
+  ) -> Result<Vec<Option<f64>>, ElevationError> {
+    let mut conn = self.conn();
+    let tx = conn.transaction().map_err(cache_error)?;
+    let mut elevations = Vec::with_capacity(coordinates.len());
+    {
+      let mut select = tx
+        .prepare_cached(
+          "SELECT elevation FROM points
+           WHERE lat_cell = ?1 AND lon_cell = ?2 AND created_at > ?3",
+        )
+        .map_err(cache_error)?;
+      let mut touch = tx
+        .prepare_cached("UPDATE points SET accessed_at = ?3 WHERE lat_cell = ?1 AND lon_cell = ?2")
+        .map_err(cache_error)?;
+      for &(lat, lon) in coordinates {
+        let key = (cell(lat), cell(lon));
+        let elevation: Option<f64> = select
+          .query_row(params![key.0, key.1, Self::cutoff(now)], |row| row.get(0))
+          .optional()
+          .map_err(cache_error)?;
+        if elevation.is_some() {
+          touch
+            .execute(params![key.0, key.1, now.timestamp()])
+            .map_err(cache_error)?;
+        }
+        elevations.push(elevation);
+      }
+    }
+    tx.commit().map_err(cache_error)?;
+    Ok(elevations)
+  }
+
+  pub fn get_point(
+    &self,
+    lat: f64,
+    lon: f64,
+    now: DateTime<Utc>,
+  ) -> Result<Option<f64>, ElevationError> {
+    Ok(self.get_points(&[(lat, lon)], now)?.pop().flatten())
+  }
+
+  /// Stores many points in one transaction, then evicts the least recently
+  /// used ones if the table is over capacity.
+  pub fn put_points(
+    &self,
+    points: &[ElevationPoint],
+    now: DateTime<Utc>,
+  ) -> Result<(), ElevationError> {
+    let mut conn = self.conn();
+    let tx = conn.transaction().map_err(cache_error)?;
+    {
+      let mut insert = tx
+        .prepare_cached(
+          "INSERT OR REPLACE INTO points (lat_cell, lon_cell, elevation, created_at, accessed_at)
+           VALUES (?1, ?2, ?3, ?4, ?4)",
+        )
+        .map_err(cache_error)?;
+      for point in points {
+        insert
+          .execute(params![
+            cell(point.lat),
+            cell(point.lon),
+            point.elevation,
+            now.timestamp()
+          ])
+          .map_err(cache_error)?;
+      }
+    }
+    evict(&tx, "points", self.max_points)?;
+    tx.commit().map_err(cache_error)
+  }
+
+  /// Returns the cached grid around a center, rows south to north and
+  /// columns west to east as `getElevationGrid` builds them.
+  pub fn get_grid(
+    &self,
+    lat: f64,
+    lon: f64,
+    radius: f64,
+    grid_size: usize,
+    now: DateTime<Utc>,
+  ) -> Result<Option<Vec<Vec<f64>>>, ElevationError> {
+    let conn = self.conn();
+    let key = (cell(lat), cell(lon), radius, grid_size as i64);
+    let grid: Option<String> = conn
+      .query_row(
+        "SELECT grid FROM grids
+         WHERE lat_cell = ?1 AND lon_cell = ?2 AND radius = ?3 AND grid_size = ?4
+           AND created_at > ?5",
+        params![key.0, key.1, key.2, key.3, Self::cutoff(now)],
+        |row| row.get(0),
+      )
+      .optional()
+      .map_err(cache_error)?;
+    if grid.is_some() {
+      conn
+        .execute(
+          "UPDATE grids SET accessed_at = ?5
+           WHERE lat_cell = ?1 AND lon_cell = ?2 AND radius = ?3 AND grid_size = ?4",
+          params![key.0, key.1, key.2, key.3, now.timestamp()],
+        )
+        .map_err(cache_error)?;
+    }
+    grid
+      .map(|g| serde_json::from_str(&g).map_err(cache_error))
+      .transpose()
+  }
+
+  pub fn put_grid(
+    &self,
+    lat: f64,
+    lon: f64,
+    radius: f64,
+    grid: &[Vec<f64>],
+    now: DateTime<Utc>,
+  ) -> Result<(), ElevationError> {
+    let json = serde_json::to_string(grid).map_err(cache_error)?;
+    let mut conn = self.conn();
+    let tx = conn.transaction().map_err(cache_error)?;
+    tx.execute(
+      "INSERT OR REPLACE INTO grids
+       (lat_cell, lon_cell, radius, grid_size, grid, created_at, accessed_at)
+       VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+      params![
+        cell(lat),
+        cell(lon),
+        radius,
+        grid.len() as i64,
+        json,
+        now.timestamp()
+      ],
+    )
+    .map_err(cache_error)?;
+    evict(&tx, "grids", self.max_grids)?;
+    tx.commit().map_err(cache_error)
+  }
+
+  /// Removes expired entries. Returns the number removed.
+  pub fn cleanup(&self, now: DateTime<Utc>) -> Result<usize, ElevationError> {
+    let conn = self.conn();
+    let cutoff = Self::cutoff(now);
+    let points = conn
+      .execute("DELETE FROM points WHERE created_at <= ?1", [cutoff])
+      .map_err(cache_error)?;
+    let grids = conn
+      .execute("DELETE FROM grids WHERE created_at <= ?1", [cutoff])
+      .map_err(cache_error)?;
+    Ok(points + grids)
+  }
+
+  pub fn clear(&self) -> Result<(), ElevationError> {
+    self
+      .conn()
+      .execute_batch("DELETE FROM points; DELETE FROM grids;")
+      .map_err(cache_error)
+  }
+
+  pub fn stats(&self) -> Result<ElevationCacheStats, ElevationError> {
+    let conn = self.conn();
+    let count = |table: &str| -> Result<usize, ElevationError> {
+      conn
+        .query_row(&format!("SELECT count(*) FROM {}", table), [], |row| {
+          row.get::<_, i64>(0)
+        })
+        .map(|n| n as usize)
+        .map_err(cache_error)
+    };
+    let total_size: i64 = conn
+      .query_row(
+        "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+        [],
+        |row| row.get(0),
+      )
+      .map_err(cache_error)?;
+    Ok(ElevationCacheStats {
+      single_points: count("points")?,
+      grids: count("grids")?,
+      total_size: total_size as usize,
+    })
+  }
+}
+
+/// Deletes the least recently used rows of `table` beyond `capacity`.
+fn evict(conn: &Connection, table: &str, capacity: usize) -> Result<usize, ElevationError> {
+  conn
+    .execute(
+      &format!(
+        "DELETE FROM {table} WHERE rowid IN (
+           SELECT rowid FROM {table} ORDER BY accessed_at DESC LIMIT -1 OFFSET ?1
+         )"
+      ),
+      [capacity as i64],
+    )
+    .map_err(cache_error)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::weather::parse_time;
+
+  fn point(lat: f64, lon: f64, elevation: f64) -> ElevationPoint {
+    ElevationPoint {
+      lat,
+      lon,
+      elevation,
+    }
+  }
+
+  #[test]
+  fn matches_points_by_rounded_coordinate() {
+    let cache = ElevationCache::open_in_memory(10, 10).unwrap();
+    let now = parse_time("2025-07-07T12:00:00Z").unwrap();
+    cache
+      .put_points(
+        &[
+          point(40.01501, -105.27049, 1655.0),
+          point(40.5, -105.0, 1500.0),
+        ],
+        now,
+      )
+      .unwrap();
+
+    assert_eq!(
+      cache.get_point(40.01499, -105.27051, now).unwrap(),
+      Some(1655.0)
+    );
+    assert_eq!(
+      cache
+        .get_points(&[(40.5, -105.0), (40.6, -105.0)], now)
+        .unwrap(),
+      vec![Some(1500.0), None]
+    );
+
+    let tomorrow = now + Duration::hours(25);
+    assert_eq!(cache.get_point(40.5, -105.0, tomorrow).unwrap(), None);
+    assert_eq!(cache.cleanup(tomorrow).unwrap(), 2);
+  }
+
+  #[test]
+  fn evicts_least_recently_used() {
+    let cache = ElevationCache::open_in_memory(2, 1).unwrap();
+    let start = parse_time("2025-07-07T12:00:00Z").unwrap();
+    let at = |minutes| start + Duration::minutes(minutes);
+
+    cache.put_points(&[point(1.0, 1.0, 1.0)], at(0)).unwrap();
+    cache.put_points(&[point(2.0, 2.0, 2.0)], at(1)).unwrap();
+    // Touch the oldest point so the second one becomes the LRU entry.
+    assert!(cache.get_point(1.0, 1.0, at(2)).unwrap().is_some());
+    cache.put_points(&[point(3.0, 3.0, 3.0)], at(3)).unwrap();
+
+    let hits = cache
+      .get_points(&[(1.0, 1.0), (2.0, 2.0), (3.0, 3.0)], at(4))
+      .unwrap();
+    assert_eq!(hits, vec![Some(1.0), None, Some(3.0)]);
+
+    let grid = vec![vec![100.0, 101.0], vec![102.0, 103.0]];
+    cache.put_grid(40.0, -105.0, 500.0, &grid, at(5)).unwrap();
+    cache.put_grid(41.0, -105.0, 500.0, &grid, at(6)).unwrap();
+    assert_eq!(cache.get_grid(40.0, -105.0, 500.0, 2, at(7)).unwrap(), None);
+    assert_eq!(
+      cache.get_grid(41.0, -105.0, 500.0, 2, at(7)).unwrap(),
+      Some(grid)
+    );
+
+    let stats = cache.stats().unwrap();
+    assert_eq!((stats.single_points, stats.grids), (2, 1));
+    assert!(stats.total_size > 0);
+  }
+}
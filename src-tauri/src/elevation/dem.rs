@@ -0,0 +1,499 @@
+//! Local digital elevation model.
+//!
+//! Reads user-supplied 1° tiles from a folder:
+//!
+//! - SRTM `.hgt` files (`N40W106.hgt`), 1201² or 3601² big-endian `i16`
+//!   samples, rows north to south, with the edges shared between tiles.
+//! - Copernicus DEM GeoTIFFs
+//!   (`Copernicus_DSM_COG_10_N40_00_W106_00_DEM.tif`), georeferenced by
+//!   their tie point and pixel scale.
+//!
+//! Tiles are indexed by file name when the folder is set and decoded on
+//! first use. Elevations are interpolated bilinearly between the four
+//! surrounding samples, skipping voids.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::tags::Tag;
+
+use super::ElevationError;
+
+/// Decoded tiles kept in memory. A 1" tile is about 50 MB.
+const MAX_LOADED_TILES: usize = 8;
+/// SRTM void value.
+const HGT_VOID: i16 = -32768;
+/// GeoTIFF `GTRasterTypeGeoKey` and its `RasterPixelIsPoint` value.
+const RASTER_TYPE_GEO_KEY: u16 = 1025;
+const RASTER_PIXEL_IS_POINT: u16 = 2;
+
+/// A regular lat/lon grid of elevation samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DemTile {
+  /// Latitude of the first row of samples
+  north: f64,
+  /// Longitude of the first column of samples
+  west: f64,
+  /// Sample spacing, degrees
+  lat_step: f64,
+  lon_step: f64,
+  rows: usize,
+  cols: usize,
+  /// Row-major, north to south; NaN for voids
+  data: Vec<f32>,
+}
+
+impl DemTile {
+  fn sample(&self, row: usize, col: usize) -> Option<f64> {
+    let value = *self.data.get(row * self.cols + col)?;
+    value.is_finite().then_some(value as f64)
+  }
+
+  /// Bilinearly interpolated elevation in meters, `None` outside the tile
+  /// or where every surrounding sample is void.
+  pub fn elevation_at(&self, lat: f64, lon: f64) -> Option<f64> {
+    // Positions up to one sample beyond the outer samples are clamped, so
+    // pixel-area tiles have no gap at their edges.
+    let row = (self.north - lat) / self.lat_step;
+    let col = (lon - self.west) / self.lon_step;
+    let max_row = (self.rows - 1) as f64;
+    let max_col = (self.cols - 1) as f64;
+    if !(-1.0..=max_row + 1.0).contains(&row) || !(-1.0..=max_col + 1.0).contains(&col) {
+      return None;
+    }
+    let row = row.clamp(0.0, max_row);
+    let col = col.clamp(0.0, max_col);
+
+    let (r0, c0) = (row.floor() as usize, col.floor() as usize);
+    let (r1, c1) = ((r0 + 1).min(self.rows - 1), (c0 + 1).min(self.cols - 1));
+    let (fr, fc) = (row - r0 as f64, col - c0 as f64);
+    let corners = [
+      (r0, c0, (1.0 - fr) * (1.0 - fc)),
+      (r0, c1, (1.0 - fr) * fc),
+      (r1, c0, fr * (1.0 - fc)),
+      (r1, c1, fr * fc),
+    ];
+
+    let (sum, weight) = corners
+      .iter()
+      .filter_map(|&(r, c, w)| self.sample(r, c).map(|v| (v * w, w)))
+      .fold((0.0, 0.0), |acc, (v, w)| (acc.0 + v, acc.1 + w));
+    (weight > 0.0).then(|| sum / weight)
+  }
+}
+
+fn dem_error(error: impl std::fmt::Display) -> ElevationError {
+  ElevationError::Dem(error.to_string())
+}
+
+/// Parses `N40` / `S05` / `W106` / `E010` style coordinate tokens.
+fn parse_hemisphere(token: &str, positive: char, negative: char) -> Option<i32> {
+  let sign = match token.chars().next()?.to_ascii_uppercase() {
+    c if c == positive => 1,
+    c if c == negative => -1,
+    _ => return None,
+  };
+  let digits = &token[1..];
+  if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+    return None;
+  }
+  digits.parse::<i32>().ok().map(|value| sign * value)
+}
+
+/// South-west corner of the 1° cell a tile file covers, from its name.
+fn tile_cell(path: &Path) -> Option<(i32, i32)> {
+  let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+  let stem = path.file_stem()?.to_str()?;
+  match extension.as_str() {
+    "hgt" => {
+      let split = stem.find(['E', 'W', 'e', 'w'])?;
+      let lat = parse_hemisphere(&stem[..split], 'N', 'S')?;
+      let lon = parse_hemisphere(&stem[split..], 'E', 'W')?;
+      Some((lat, lon))
+    }
+    "tif" | "tiff" => {
+      let tokens: Vec<&str> = stem.split('_').collect();
+      let lat = tokens.iter().find_map(|t| parse_hemisphere(t, 'N', 'S'))?;
+      let lon = tokens.iter().find_map(|t| parse_hemisphere(t, 'E', 'W'))?;
+      Some((lat, lon))
+    }
+    _ => None,
+  }
+}
+
+/// Decodes an SRTM `.hgt` tile covering the cell with south-west corner
+/// (`lat`, `lon`).
+pub fn parse_hgt(bytes: &[u8], lat: i32, lon: i32) -> Result<DemTile, ElevationError> {
+  let samples = bytes.len() / 2;
+  let size = (samples as f64).sqrt().round() as usize;
+  if size < 2 || size * size * 2 != bytes.len() {
+    return Err(ElevationError::Dem(format!(
+      "{} bytes is not a square SRTM tile",
+      bytes.len()
+    )));
+  }
+  let data = bytes
+    .chunks_exact(2)
+    .map(|pair| match i16::from_be_bytes([pair[0], pair[1]]) {
+      HGT_VOID => f32::NAN,
+      value => value as f32,
+    })
+    .collect();
+  let step = 1.0 / (size - 1) as f64;
+  Ok(DemTile {
+    north: lat as f64 + 1.0,
+    west: lon as f64,
+    lat_step: step,
+    lon_step: step,
+    rows: size,
+    cols: size,
+    data,
+  })
+}
+
+/// Whether the GeoKey directory marks samples as points rather than areas.
+fn is_pixel_is_point(geo_keys: &[u16]) -> bool {
+  geo_keys
+    .get(4..)
+    .unwrap_or_default()
+    .chunks_exact(4)
+    .any(|key| key[0] == RASTER_TYPE_GEO_KEY && key[1] == 0 && key[3] == RASTER_PIXEL_IS_POINT)
+}
+
+/// Decodes a single-band lat/lon GeoTIFF such as a Copernicus DEM tile.
+pub fn read_geotiff(path: &Path) -> Result<DemTile, ElevationError> {
+  let file = File::open(path).map_err(dem_error)?;
+  let mut decoder = Decoder::new(BufReader::new(file)).map_err(dem_error)?;
+  let (cols, rows) = decoder.dimensions().map_err(dem_error)?;
+  let scale = decoder
+    .get_tag_f64_vec(Tag::ModelPixelScaleTag)
+    .map_err(dem_error)?;
+  let tie = decoder
+    .get_tag_f64_vec(Tag::ModelTiepointTag)
+    .map_err(dem_error)?;
+  if scale.len() < 2 || tie.len() < 6 {
+    return Err(ElevationError::Dem("GeoTIFF is not georeferenced".into()));
+  }
+  let pixel_is_point = decoder
+    .get_tag_u16_vec(Tag::GeoKeyDirectoryTag)
+    .map(|keys| is_pixel_is_point(&keys))
+    .unwrap_or(false);
+  let nodata = decoder
+    .get_tag_ascii_string(Tag::GdalNodata)
+    .ok()
+    .and_then(|value| value.trim_matches(char::from(0)).trim().parse::<f32>().ok());
+
+  let data: Vec<f32> = match decoder.read_image().map_err(dem_error)? {
+    DecodingResult::F32(values) => values,
+    DecodingResult::F64(values) => values.into_iter().map(|v| v as f32).collect(),
+    DecodingResult::I16(values) => values.into_iter().map(f32::from).collect(),
+    DecodingResult::U16(values) => values.into_iter().map(f32::from).collect(),
+    DecodingResult::I32(values) => values.into_iter().map(|v| v as f32).collect(),
+    _ => {
+      return Err(ElevationError::Dem(
+        "unsupported GeoTIFF sample format".into(),
+      ))
+    }
+  };
+  let data = data
+    .into_iter()
+    .map(|v| if Some(v) == nodata { f32::NAN } else { v })
+    .collect();
+
+  let (lon_step, lat_step) = (scale[0], scale[1]);
+  // Tie point: raster (i, j) maps to model (x, y) = (lon, lat).
+  let mut west = tie[3] - tie[0] * lon_step;
+  let mut north = tie[4] + tie[1] * lat_step;
+  if !pixel_is_point {
+    west += lon_step / 2.0;
+    north -= lat_step / 2.0;
+  }
+  Ok(DemTile {
+    north,
+    west,
+    lat_step,
+    lon_step,
+    rows: rows as usize,
+    cols: cols as usize,
+    data,
+  })
+}
+
+fn load_tile(path: &Path, cell: (i32, i32)) -> Result<DemTile, ElevationError> {
+  if path
+    .extension()
+    .is_some_and(|e| e.eq_ignore_ascii_case("hgt"))
+  {
+    let bytes = std::fs::read(path).map_err(dem_error)?;
+    parse_hgt(&bytes, cell.0, cell.1)
+  } else {
+    read_geotiff(path)
+  }
+}
+
+/// Where the DEM is read from and how many tiles were found.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DemStatus {
+  pub folder: Option<String>,
+  pub tiles: usize,
+}
+
+/// A tile decoded once, on first use, by whichever lookup gets there
+/// first; the others wait on it rather than on the whole provider. A tile
+/// that fails to decode keeps its error until evicted or the folder is set.
+type TileSlot = Arc<OnceLock<Result<Arc<DemTile>, String>>>;
+
+#[derive(Default)]
+struct DemState {
+  folder: Option<PathBuf>,
+  index: HashMap<(i32, i32), PathBuf>,
+  /// Decoded tiles, most recently used last
+  loaded: Vec<((i32, i32), TileSlot)>,
+}
+
+/// Elevation lookups against the configured DEM folder. Cheap to clone.
+#[derive(Clone, Default)]
+pub struct DemProvider {
+  state: Arc<Mutex<DemState>>,
+}
+
+impl DemProvider {
+  fn state(&self) -> std::sync::MutexGuard<'_, DemState> {
+    self
+      .state
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  /// Indexes the tiles in `folder`, or disables the DEM when `None`.
+  pub fn set_folder(&self, folder: Option<PathBuf>) -> Result<DemStatus, ElevationError> {
+    let mut index = HashMap::new();
+    if let Some(folder) = &folder {
+      for entry in std::fs::read_dir(folder).map_err(dem_error)? {
+        let path = entry.map_err(dem_error)?.path();
+        if let Some(cell) = tile_cell(&path) {
+          index.insert(cell, path);
+        }
+      }
+    }
+    let mut state = self.state();
+    *state = DemState {
+      folder,
+      index,
+      loaded: Vec::new(),
+    };
+    Ok(status(&state))
+  }
+
+  pub fn status(&self) -> DemStatus {
+    status(&self.state())
+  }
+
+  fn tile(&self, cell: (i32, i32)) -> Result<Option<Arc<DemTile>>, ElevationError> {
+    // Only the LRU bookkeeping happens under the lock; decoding a 50 MB
+    // tile would otherwise stall every parallel run's terrain lookups.
+    let (path, slot) = {
+      let mut state = self.state();
+      let Some(path) = state.index.get(&cell).cloned() else {
+        return Ok(None);
+      };
+      let slot = match state.loaded.iter().position(|(c, _)| *c == cell) {
+        Some(position) => state.loaded.remove(position).1,
+        None => {
+          if state.loaded.len() >= MAX_LOADED_TILES {
+            state.loaded.remove(0);
+          }
+          TileSlot::default()
+        }
+      };
+      state.loaded.push((cell, slot.clone()));
+      (path, slot)
+    };
+    slot
+      .get_or_init(|| {
+        load_tile(&path, cell)
+          .map(Arc::new)
+          .map_err(|error| match error {
+            ElevationError::Dem(message) => message,
+            other => other.to_string(),
+          })
+      })
+      .clone()
+      .map(Some)
+      .map_err(ElevationError::Dem)
+  }
+
+  /// Terrain elevation in meters, `None` where no tile covers the point.
+  pub fn elevation(&self, lat: f64, lon: f64) -> Result<Option<f64>, ElevationError> {
+    let cell = (lat.floor() as i32, lon.floor() as i32);
+    Ok(
+      self
+        .tile(cell)?
+        .and_then(|tile| tile.elevation_at(lat, lon)),
+    )
+  }
+
+  /// Grid of elevations around a center, laid out like `getElevationGrid`:
+  /// rows south to north, columns west to east. `None` unless the DEM
+  /// covers every point.
+  pub fn grid(
+    &self,
+    lat: f64,
+    lon: f64,
+    radius: f64,
+    grid_size: usize,
+  ) -> Result<Option<Vec<Vec<f64>>>, ElevationError> {
+    let d_lat = (radius / crate::prediction::EARTH_RADIUS_M).to_degrees();
+    let d_lon = d_lat / lat.to_radians().cos();
+    let fraction = |i: usize| {
+      if grid_size > 1 {
+        i as f64 / (grid_size - 1) as f64
+      } else {
+        0.5
+      }
+    };
+    let mut grid = Vec::with_capacity(grid_size);
+    for i in 0..grid_size {
+      let mut row = Vec::with_capacity(grid_size);
+      for j in 0..grid_size {
+        let point_lat = lat - d_lat + 2.0 * d_lat * fraction(i);
+        let point_lon = lon - d_lon + 2.0 * d_lon * fraction(j);
+        match self.elevation(point_lat, point_lon)? {
+          Some(elevation) => row.push(elevation),
+          None => return Ok(None),
+        }
+      }
+      grid.push(row);
+    }
+    Ok(Some(grid))
+  }
+}
+
+fn status(state: &DemState) -> DemStatus {
+  DemStatus {
+    folder: state
+      .folder
+      .as_ref()
+      .map(|folder| folder.display().to_string()),
+    tiles: state.index.len(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// 3×3 SRTM-style tile for the cell N40W106.
+  fn hgt(values: [i16; 9]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_be_bytes()).collect()
+  }
+
+  #[test]
+  fn parses_tile_names() {
+    assert_eq!(tile_cell(Path::new("N40W106.hgt")), Some((40, -106)));
+    assert_eq!(tile_cell(Path::new("s05e010.HGT")), Some((-5, 10)));
+    assert_eq!(
+      tile_cell(Path::new("Copernicus_DSM_COG_10_N40_00_W106_00_DEM.tif")),
+      Some((40, -106))
+    );
+    assert_eq!(tile_cell(Path::new("readme.txt")), None);
+  }
+
+  #[test]
+  fn interpolates_hgt_samples() {
+    // Rows north to south: 41°, 40.5°, 40°.
+    let tile = parse_hgt(&hgt([200, 300, 400, 100, 200, 300, 0, 100, 200]), 40, -106).unwrap();
+
+    assert_eq!(tile.elevation_at(41.0, -106.0), Some(200.0));
+    assert_eq!(tile.elevation_at(40.0, -105.0), Some(200.0));
+    assert!((tile.elevation_at(40.25, -105.75).unwrap() - 100.0).abs() < 1e-9);
+    assert!((tile.elevation_at(40.5, -105.25).unwrap() - 250.0).abs() < 1e-9);
+    assert_eq!(tile.elevation_at(42.0, -105.5), None);
+  }
+
+  #[test]
+  fn skips_voids() {
+    let tile = parse_hgt(
+      &hgt([HGT_VOID, 300, 400, 100, 200, 300, 0, 100, 200]),
+      40,
+      -106,
+    )
+    .unwrap();
+    // Halfway between a void and 300 m: only the valid sample counts.
+    assert_eq!(tile.elevation_at(41.0, -105.75), Some(300.0));
+    assert!(parse_hgt(&[0; 7], 40, -106).is_err());
+  }
+
+  #[test]
+  fn reads_area_registered_geotiff() {
+    use tiff::encoder::{colortype::Gray32Float, TiffEncoder};
+
+    // 2×2 samples of 0.5°, tie point on the outer corner of the tile like
+    // Copernicus, so sample centers sit a quarter degree inside.
+    let path = std::env::temp_dir().join(format!("blips-dem-{}.tif", std::process::id()));
+    {
+      let mut encoder = TiffEncoder::new(File::create(&path).unwrap()).unwrap();
+      let mut image = encoder.new_image::<Gray32Float>(2, 2).unwrap();
+      let tags = image.encoder();
+      tags
+        .write_tag(Tag::ModelPixelScaleTag, &[0.5, 0.5, 0.0][..])
+        .unwrap();
+      tags
+        .write_tag(
+          Tag::ModelTiepointTag,
+          &[0.0, 0.0, 0.0, -106.0, 41.0, 0.0][..],
+        )
+        .unwrap();
+      tags.write_tag(Tag::GdalNodata, "-32767.0").unwrap();
+      image
+        .write_data(&[1000.0, 1100.0, 1200.0, -32767.0])
+        .unwrap();
+    }
+
+    let tile = read_geotiff(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(tile.elevation_at(40.75, -105.75), Some(1000.0));
+    assert_eq!(tile.elevation_at(40.75, -105.5), Some(1050.0));
+    // The south-east sample is void.
+    assert_eq!(tile.elevation_at(40.25, -105.5), Some(1200.0));
+    assert_eq!(tile.elevation_at(40.25, -105.25), None);
+  }
+
+  #[test]
+  fn looks_up_tiles_in_folder() {
+    let folder = std::env::temp_dir().join(format!("blips-dem-{}", std::process::id()));
+    std::fs::create_dir_all(&folder).unwrap();
+    std::fs::write(
+      folder.join("N40W106.hgt"),
+      hgt([200, 300, 400, 100, 200, 300, 0, 100, 200]),
+    )
+    .unwrap();
+
+    let dem = DemProvider::default();
+    assert_eq!(dem.set_folder(Some(folder.clone())).unwrap().tiles, 1);
+    assert_eq!(dem.elevation(40.5, -105.5).unwrap(), Some(200.0));
+    assert_eq!(dem.elevation(39.5, -105.5).unwrap(), None);
+
+    // Parallel lookups share one decoded tile.
+    let tiles: Vec<Arc<DemTile>> = std::thread::scope(|scope| {
+      let lookups: Vec<_> = (0..4)
+        .map(|_| scope.spawn(|| dem.tile((40, -106)).unwrap().unwrap()))
+        .collect();
+      lookups.into_iter().map(|l| l.join().unwrap()).collect()
+    });
+    assert!(tiles.iter().all(|tile| Arc::ptr_eq(tile, &tiles[0])));
+
+    let grid = dem.grid(40.5, -105.5, 500.0, 3).unwrap().unwrap();
+    assert_eq!(grid.len(), 3);
+    // South-west corner first, like getElevationGrid.
+    assert!(grid[0][0] < grid[2][2]);
+
+    std::fs::remove_dir_all(folder).unwrap();
+  }
+}
@@ -0,0 +1,179 @@
+//! Ground elevation.
+//!
+//! Terrain comes from the local [`dem::DemProvider`] when the user has
+//! configured a DEM folder that covers the point. Otherwise the frontend
+//! falls back to Open Elevation, keeping results in the native
+//! [`cache::ElevationCache`] through the commands below.
+
+pub mod cache;
+pub mod dem;
+
+use std::path::PathBuf;
+
+use chrono::Utc;
+use serde::Serialize;
+use tauri::State;
+
+use cache::{ElevationCache, ElevationCacheStats, ElevationPoint};
+use dem::{DemProvider, DemStatus};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ElevationError {
+  #[error("Elevation cache error: {0}")]
+  Cache(String),
+  #[error("Could not read DEM tile: {0}")]
+  Dem(String),
+}
+
+impl Serialize for ElevationError {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.to_string())
+  }
+}
+
+/// Ground surface the descent is checked against.
+pub trait Terrain {
+  /// Terrain elevation in meters.
+  fn elevation(&self, lat: f64, lon: f64) -> f64;
+}
+
+/// Flat ground at a fixed elevation.
+impl Terrain for f64 {
+  fn elevation(&self, _lat: f64, _lon: f64) -> f64 {
+    *self
+  }
+}
+
+/// The local DEM, with a flat fallback wherever it has no data.
+#[derive(Clone)]
+pub struct DemTerrain {
+  pub dem: DemProvider,
+  /// meters
+  pub fallback: f64,
+}
+
+impl Terrain for DemTerrain {
+  fn elevation(&self, lat: f64, lon: f64) -> f64 {
+    match self.dem.elevation(lat, lon) {
+      Ok(elevation) => elevation.unwrap_or(self.fallback),
+      Err(error) => {
+        log::warn!("DEM lookup failed at {}, {}: {}", lat, lon, error);
+        self.fallback
+      }
+    }
+  }
+}
+
+/// Points the DEM at a folder of `.hgt` / GeoTIFF tiles, or turns it off
+/// when `path` is `None`.
+#[tauri::command]
+pub async fn set_dem_folder(
+  dem: State<'_, DemProvider>,
+  path: Option<String>,
+) -> Result<DemStatus, ElevationError> {
+  let dem = dem.inner().clone();
+  tauri::async_runtime::spawn_blocking(move || dem.set_folder(path.map(PathBuf::from)))
+    .await
+    .map_err(|e| ElevationError::Dem(e.to_string()))?
+}
+
+#[tauri::command]
+pub fn dem_status(dem: State<'_, DemProvider>) -> DemStatus {
+  dem.status()
+}
+
+/// Terrain elevation from the local DEM; `None` where no tile covers the
+/// point.
+#[tauri::command]
+pub async fn get_ground_elevation(
+  dem: State<'_, DemProvider>,
+  lat: f64,
+  lon: f64,
+) -> Result<Option<f64>, ElevationError> {
+  let dem = dem.inner().clone();
+  tauri::async_runtime::spawn_blocking(move || dem.elevation(lat, lon))
+    .await
+    .map_err(|e| ElevationError::Dem(e.to_string()))?
+}
+
+/// Elevation grid from the local DEM, laid out like `getElevationGrid`;
+/// `None` unless the DEM covers every point.
+#[tauri::command]
+pub async fn get_elevation_grid(
+  dem: State<'_, DemProvider>,
+  lat: f64,
+  lon: f64,
+  radius: f64,
+  grid_size: usize,
+) -> Result<Option<Vec<Vec<f64>>>, ElevationError> {
+  let dem = dem.inner().clone();
+  tauri::async_runtime::spawn_blocking(move || dem.grid(lat, lon, radius, grid_size))
+    .await
+    .map_err(|e| ElevationError::Dem(e.to_string()))?
+}
+
+#[tauri::command]
+pub fn get_cached_elevation(
+  cache: State<'_, ElevationCache>,
+  lat: f64,
+  lon: f64,
+) -> Result<Option<f64>, ElevationError> {
+  cache.get_point(lat, lon, Utc::now())
+}
+
+/// Looks up many `[lat, lon]` pairs at once; misses are `null`.
+#[tauri::command]
+pub fn get_cached_elevations(
+  cache: State<'_, ElevationCache>,
+  coordinates: Vec<(f64, f64)>,
+) -> Result<Vec<Option<f64>>, ElevationError> {
+  cache.get_points(&coordinates, Utc::now())
+}
+
+#[tauri::command]
+pub fn cache_elevations(
+  cache: State<'_, ElevationCache>,
+  points: Vec<ElevationPoint>,
+) -> Result<(), ElevationError> {
+  cache.put_points(&points, Utc::now())
+}
+
+#[tauri::command]
+pub fn get_cached_elevation_grid(
+  cache: State<'_, ElevationCache>,
+  lat: f64,
+  lon: f64,
+  radius: f64,
+  grid_size: usize,
+) -> Result<Option<Vec<Vec<f64>>>, ElevationError> {
+  cache.get_grid(lat, lon, radius, grid_size, Utc::now())
+}
+
+#[tauri::command]
+pub fn cache_elevation_grid(
+  cache: State<'_, ElevationCache>,
+  lat: f64,
+  lon: f64,
+  radius: f64,
+  grid: Vec<Vec<f64>>,
+) -> Result<(), ElevationError> {
+  cache.put_grid(lat, lon, radius, &grid, Utc::now())
+}
+
+/// Removes expired entries. Returns the number removed.
+#[tauri::command]
+pub fn cleanup_elevation_cache(cache: State<'_, ElevationCache>) -> Result<usize, ElevationError> {
+  cache.cleanup(Utc::now())
+}
+
+#[tauri::command]
+pub fn clear_elevation_cache(cache: State<'_, ElevationCache>) -> Result<(), ElevationError> {
+  cache.clear()
+}
+
+#[tauri::command]
+pub fn elevation_cache_stats(
+  cache: State<'_, ElevationCache>,
+) -> Result<ElevationCacheStats, ElevationError> {
+  cache.stats()
+}
@@ -0,0 +1,643 @@
+//! Ensemble predictions.
+//!
+//! The Monte Carlo ensemble re-runs [`run_prediction_simulation`] with the
+//! ascent rate, descent rate and burst altitude drawn from normal
+//! distributions around the launch parameters, and with its own wind error
+//! on every pressure level. The forecast ensemble runs one prediction per
+//! GEFS member instead. Members run in parallel on the rayon pool, and the
+//! landing cloud is summarised by 1σ/2σ/3σ dispersion ellipses and a
+//! probability grid for chase planning.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, Normal};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::ascent::AscentModel;
+use crate::elevation::dem::DemProvider;
+use crate::elevation::{DemTerrain, Terrain};
+use crate::geodesy;
+use crate::prediction::{
+  resolve_weather, run_prediction_simulation, FlightPoint, LaunchParams, PredictionError,
+  PredictionResult,
+};
+use crate::weather::cache::ForecastCache;
+use crate::weather::open_meteo::WeatherData;
+use crate::weather::{self, grib, parse_time, WeatherGrid};
+
+const MAX_MEMBERS: usize = 10_000;
+/// Largest heat grid side; the cell size grows for very spread-out clouds.
+const MAX_HEAT_CELLS: usize = 200;
+/// Points in each ellipse outline.
+const OUTLINE_POINTS: usize = 72;
+
+/// Ensemble size and 1σ input errors.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EnsembleSettings {
+  pub members: usize,
+  /// Relative error of the ascent rate (0.1 = 10 %)
+  pub ascent_rate_error: f64,
+  /// Relative error of the descent rate
+  pub descent_rate_error: f64,
+  /// Relative error of the burst altitude
+  pub burst_altitude_error: f64,
+  /// Wind speed error on each pressure level, m/s
+  pub wind_speed_error: f64,
+  /// Wind direction error on each pressure level, degrees
+  pub wind_direction_error: f64,
+  /// Heat grid cell size, m
+  pub heat_cell_size: f64,
+  /// Fixes the random draws, for reproducible runs
+  pub seed: Option<u64>,
+}
+
+impl Default for EnsembleSettings {
+  fn default() -> Self {
+    EnsembleSettings {
+      members: 200,
+      ascent_rate_error: 0.1,
+      descent_rate_error: 0.1,
+      burst_altitude_error: 0.05,
+      wind_speed_error: 2.0,
+      wind_direction_error: 10.0,
+      heat_cell_size: 1000.0,
+      seed: None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LatLon {
+  pub lat: f64,
+  pub lon: f64,
+}
+
+/// Landing dispersion ellipse: `sigma` standard deviations along the
+/// principal axes of the landing covariance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DispersionEllipse {
+  pub sigma: u32,
+  pub center: LatLon,
+  /// meters
+  pub semi_major: f64,
+  /// meters
+  pub semi_minor: f64,
+  /// Bearing of the major axis, degrees clockwise from north in [0, 180)
+  pub orientation: f64,
+  /// Share of ensemble landings inside the ellipse
+  pub containment: f64,
+  pub outline: Vec<LatLon>,
+}
+
+/// Landing probability per cell, rows south to north and columns west to
+/// east. Cell (row, col) spans `south + row * latStep` and
+/// `west + col * lonStep`; east of the antimeridian longitudes exceed 180.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeatGrid {
+  pub south: f64,
+  pub west: f64,
+  /// degrees
+  pub lat_step: f64,
+  /// degrees
+  pub lon_step: f64,
+  /// meters
+  pub cell_size: f64,
+  pub rows: usize,
+  pub cols: usize,
+  /// Row-major; sums to 1
+  pub probabilities: Vec<f64>,
+}
+
+/// Spread of an ensemble's landing points.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LandingSpread {
+  pub mean_landing: LatLon,
+  /// Root-mean-square distance from the mean landing, m
+  pub rms_radius: f64,
+  /// Largest distance from the mean landing, m
+  pub max_radius: f64,
+  pub ellipses: Vec<DispersionEllipse>,
+  pub heat_map: HeatGrid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnsembleResult {
+  /// The unperturbed prediction
+  pub nominal: PredictionResult,
+  pub landings: Vec<FlightPoint>,
+  #[serde(flatten)]
+  pub spread: LandingSpread,
+  /// Members whose simulation failed
+  pub failed_members: usize,
+  /// Seed the members were drawn from
+  pub seed: u64,
+}
+
+/// One GEFS member's prediction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemberPrediction {
+  /// 0 is the control run
+  pub member: usize,
+  pub burst_point: FlightPoint,
+  pub landing_point: FlightPoint,
+  /// seconds
+  pub flight_duration: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForecastEnsembleResult {
+  pub members: Vec<MemberPrediction>,
+  #[serde(flatten)]
+  pub spread: LandingSpread,
+  /// Members whose simulation failed
+  pub failed_members: usize,
+}
+
+/// Local east/north plane in meters around a reference point: the
+/// azimuthal equidistant projection on the WGS84 ellipsoid, so distances
+/// from the origin are true geodesic distances at any latitude.
+#[derive(Debug, Clone, Copy)]
+struct Plane {
+  origin: LatLon,
+}
+
+impl Plane {
+  fn new(origin: LatLon) -> Self {
+    Plane { origin }
+  }
+
+  fn project(&self, lat: f64, lon: f64) -> (f64, f64) {
+    let line = geodesy::inverse(self.origin.lat, self.origin.lon, lat, lon);
+    let (sin, cos) = line.initial_bearing.to_radians().sin_cos();
+    (line.distance * sin, line.distance * cos)
+  }
+
+  fn unproject(&self, x: f64, y: f64) -> LatLon {
+    let bearing = x.atan2(y).to_degrees().rem_euclid(360.0);
+    let point = geodesy::direct(self.origin.lat, self.origin.lon, bearing, x.hypot(y));
+    LatLon {
+      lat: point.lat,
+      lon: point.lon,
+    }
+  }
+}
+
+fn normal(sigma: f64) -> Normal<f64> {
+  Normal::new(0.0, sigma.max(0.0)).expect("standard deviation is finite and non-negative")
+}
+
+/// Draws one member's launch parameters and forecast.
+fn perturb(
+  params: &LaunchParams,
+  weather: &WeatherGrid,
+  settings: &EnsembleSettings,
+  rng: &mut StdRng,
+) -> (LaunchParams, WeatherGrid) {
+  let mut member = params.clone();
+  let ascent_factor = (1.0 + normal(settings.ascent_rate_error).sample(rng)).max(0.1);
+  let descent_factor = (1.0 + normal(settings.descent_rate_error).sample(rng)).max(0.1);
+  let burst_factor = (1.0 + normal(settings.burst_altitude_error).sample(rng)).max(0.1);
+  member.ascent_rate *= ascent_factor;
+  member.descent_rate *= descent_factor;
+  // Terminal velocities go as 1/√Cd.
+  if let Some(balloon) = &mut member.balloon {
+    // Perturb the modelled burst altitude rather than the fallback.
+    if let Some(burst) =
+      AscentModel::new(*balloon, params.launch_altitude).burst_altitude(params.launch_altitude)
+    {
+      member.burst_altitude = burst;
+      balloon.burst_diameter = None;
+    }
+    balloon.drag_coefficient /= ascent_factor.powi(2);
+  }
+  if let Some(parachute) = &mut member.parachute {
+    parachute.drag_coefficient /= descent_factor.powi(2);
+  }
+  member.burst_altitude *= burst_factor;
+
+  let mut grid = weather.clone();
+  let levels = grid.levels.len();
+  let errors: Vec<(f64, f64)> = (0..levels)
+    .map(|_| {
+      (
+        normal(settings.wind_speed_error).sample(rng),
+        normal(settings.wind_direction_error).sample(rng),
+      )
+    })
+    .collect();
+  for (index, sample) in grid.samples.iter_mut().enumerate() {
+    if let Some(sample) = sample {
+      let (speed_error, direction_error) = errors[index % levels];
+      sample.wind_speed = (sample.wind_speed + speed_error).max(0.0);
+      sample.wind_direction = (sample.wind_direction + direction_error).rem_euclid(360.0);
+    }
+  }
+  (member, grid)
+}
+
+/// Covariance ellipses of the landing cloud, projected on `plane`.
+fn dispersion_ellipses(points: &[(f64, f64)], plane: &Plane) -> Vec<DispersionEllipse> {
+  let n = points.len() as f64;
+  let (sxx, syy, sxy) = points.iter().fold((0.0, 0.0, 0.0), |acc, &(x, y)| {
+    (acc.0 + x * x / n, acc.1 + y * y / n, acc.2 + x * y / n)
+  });
+
+  // Principal axes of [[sxx, sxy], [sxy, syy]].
+  let mid = (sxx + syy) / 2.0;
+  let radius = (((sxx - syy) / 2.0).powi(2) + sxy * sxy).sqrt();
+  let major_var = (mid + radius).max(0.0);
+  let minor_var = (mid - radius).max(0.0);
+  // Angle of the major axis from east, counter-clockwise.
+  let angle = 0.5 * (2.0 * sxy).atan2(sxx - syy);
+  let (sin, cos) = angle.sin_cos();
+
+  // Mahalanobis distance along the principal axes.
+  let distance = |&(x, y): &(f64, f64)| {
+    let along = x * cos + y * sin;
+    let across = -x * sin + y * cos;
+    (along * along / major_var.max(1e-9) + across * across / minor_var.max(1e-9)).sqrt()
+  };
+  let distances: Vec<f64> = points.iter().map(distance).collect();
+
+  (1..=3)
+    .map(|sigma| {
+      let k = sigma as f64;
+      let (a, b) = (k * major_var.sqrt(), k * minor_var.sqrt());
+      let outline = (0..OUTLINE_POINTS)
+        .map(|i| {
+          let phi = std::f64::consts::TAU * i as f64 / OUTLINE_POINTS as f64;
+          let (u, v) = (a * phi.cos(), b * phi.sin());
+          plane.unproject(u * cos - v * sin, u * sin + v * cos)
+        })
+        .collect();
+      DispersionEllipse {
+        sigma,
+        center: plane.origin,
+        semi_major: a,
+        semi_minor: b,
+        orientation: (90.0 - angle.to_degrees()).rem_euclid(180.0),
+        containment: distances.iter().filter(|&&d| d <= k + 1e-9).count() as f64 / n,
+        outline,
+      }
+    })
+    .collect()
+}
+
+/// Bins the landing cloud into a probability grid.
+fn heat_grid(points: &[(f64, f64)], plane: &Plane, cell_size: f64) -> HeatGrid {
+  let min_x = points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+  let max_x = points.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+  let min_y = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+  let max_y = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+  let span = (max_x - min_x).max(max_y - min_y);
+  let cell_size = cell_size.max(span / (MAX_HEAT_CELLS - 2) as f64).max(1.0);
+
+  // One empty cell of margin on each side.
+  let (west_x, south_y) = (min_x - cell_size, min_y - cell_size);
+  let cols = ((max_x - west_x) / cell_size).floor() as usize + 2;
+  let rows = ((max_y - south_y) / cell_size).floor() as usize + 2;
+  let mut probabilities = vec![0.0; rows * cols];
+  let weight = 1.0 / points.len() as f64;
+  for &(x, y) in points {
+    let col = ((x - west_x) / cell_size).floor() as usize;
+    let row = ((y - south_y) / cell_size).floor() as usize;
+    probabilities[row.min(rows - 1) * cols + col.min(cols - 1)] += weight;
+  }
+
+  // Cell sizes in degrees, measured at the centre of the plane.
+  let south_west = plane.unproject(west_x, south_y);
+  let north = plane.unproject(0.0, cell_size);
+  let east = plane.unproject(cell_size, 0.0);
+  HeatGrid {
+    south: south_west.lat,
+    west: south_west.lon,
+    lat_step: north.lat - plane.origin.lat,
+    lon_step: geodesy::normalize_lon(east.lon - plane.origin.lon),
+    cell_size,
+    rows,
+    cols,
+    probabilities,
+  }
+}
+
+/// Runs the nominal prediction and `settings.members` perturbed ones.
+pub fn run_ensemble_simulation(
+  params: &LaunchParams,
+  weather: &WeatherGrid,
+  terrain: &(dyn Terrain + Sync),
+  settings: &EnsembleSettings,
+) -> Result<EnsembleResult, PredictionError> {
+  if settings.members == 0 || settings.members > MAX_MEMBERS {
+    return Err(PredictionError::InvalidParams(
+      "ensemble needs between 1 and 10000 members",
+    ));
+  }
+  let nominal = run_prediction_simulation(params, weather, terrain)?;
+  let seed = settings.seed.unwrap_or_else(|| rand::rng().random());
+
+  let members: Vec<Option<FlightPoint>> = (0..settings.members)
+    .into_par_iter()
+    .map(|index| {
+      let mut rng = StdRng::seed_from_u64(seed.wrapping_add(index as u64));
+      let (member, grid) = perturb(params, weather, settings, &mut rng);
+      run_prediction_simulation(&member, &grid, terrain)
+        .ok()
+        .map(|result| result.landing_point)
+    })
+    .collect();
+  let failed_members = members.iter().filter(|m| m.is_none()).count();
+  let landings: Vec<FlightPoint> = members.into_iter().flatten().collect();
+  if landings.is_empty() {
+    return Err(PredictionError::Simulation(
+      "every ensemble member failed".into(),
+    ));
+  }
+
+  Ok(EnsembleResult {
+    spread: landing_spread(&landings, &nominal.landing_point, settings.heat_cell_size),
+    nominal,
+    landings,
+    failed_members,
+    seed,
+  })
+}
+
+/// Summarises landing points. `reference` should be close to the cloud;
+/// the mean is taken in the plane around it, which copes with clouds
+/// straddling the antimeridian.
+fn landing_spread(
+  landings: &[FlightPoint],
+  reference: &FlightPoint,
+  cell_size: f64,
+) -> LandingSpread {
+  let reference = Plane::new(LatLon {
+    lat: reference.lat,
+    lon: reference.lon,
+  });
+  let projected: Vec<(f64, f64)> = landings
+    .iter()
+    .map(|p| reference.project(p.lat, p.lon))
+    .collect();
+  let n = projected.len() as f64;
+  let mean = reference.unproject(
+    projected.iter().map(|p| p.0).sum::<f64>() / n,
+    projected.iter().map(|p| p.1).sum::<f64>() / n,
+  );
+
+  let plane = Plane::new(mean);
+  let points: Vec<(f64, f64)> = landings
+    .iter()
+    .map(|p| plane.project(p.lat, p.lon))
+    .collect();
+  let radii = points.iter().map(|p| p.0.hypot(p.1));
+
+  LandingSpread {
+    mean_landing: mean,
+    rms_radius: (radii.clone().map(|r| r * r).sum::<f64>() / n).sqrt(),
+    max_radius: radii.fold(0.0, f64::max),
+    ellipses: dispersion_ellipses(&points, &plane),
+    heat_map: heat_grid(&points, &plane, cell_size),
+  }
+}
+
+/// Runs one prediction per forecast ensemble member.
+pub fn run_forecast_ensemble_simulation(
+  params: &LaunchParams,
+  members: &[WeatherGrid],
+  terrain: &(dyn Terrain + Sync),
+  heat_cell_size: f64,
+) -> Result<ForecastEnsembleResult, PredictionError> {
+  let results: Vec<Option<MemberPrediction>> = members
+    .par_iter()
+    .enumerate()
+    .map(|(member, grid)| {
+      run_prediction_simulation(params, grid, terrain)
+        .ok()
+        .map(|result| MemberPrediction {
+          member,
+          burst_point: result.burst_point,
+          landing_point: result.landing_point,
+          flight_duration: result.flight_duration,
+        })
+    })
+    .collect();
+  let failed_members = results.iter().filter(|m| m.is_none()).count();
+  let members: Vec<MemberPrediction> = results.into_iter().flatten().collect();
+  let Some(reference) = members.first().map(|m| m.landing_point) else {
+    return Err(PredictionError::Simulation(
+      "every ensemble member failed".into(),
+    ));
+  };
+
+  let landings: Vec<FlightPoint> = members.iter().map(|m| m.landing_point).collect();
+  Ok(ForecastEnsembleResult {
+    spread: landing_spread(&landings, &reference, heat_cell_size),
+    members,
+    failed_members,
+  })
+}
+
+/// Runs a Monte Carlo ensemble around a launch. Weather and terrain are
+/// picked as for `run_prediction`.
+#[tauri::command]
+pub async fn run_ensemble(
+  cache: State<'_, ForecastCache>,
+  dem: State<'_, DemProvider>,
+  params: LaunchParams,
+  settings: Option<EnsembleSettings>,
+  weather_data: Option<WeatherData>,
+  grib_files: Option<Vec<String>>,
+  ground_elevation: Option<f64>,
+) -> Result<EnsembleResult, PredictionError> {
+  let grid = resolve_weather(&cache, &params, weather_data, grib_files).await?;
+  let terrain = DemTerrain {
+    dem: dem.inner().clone(),
+    fallback: ground_elevation.unwrap_or(0.0),
+  };
+  let settings = settings.unwrap_or_default();
+  tauri::async_runtime::spawn_blocking(move || {
+    run_ensemble_simulation(&params, &grid, &terrain, &settings)
+  })
+  .await
+  .map_err(|e| PredictionError::Simulation(e.to_string()))?
+}
+
+/// Runs one prediction per GEFS member. `grib_members` are local GRIB2
+/// files, one list per member; without them the members are fetched (or
+/// taken from the forecast cache).
+#[tauri::command]
+pub async fn run_forecast_ensemble(
+  cache: State<'_, ForecastCache>,
+  dem: State<'_, DemProvider>,
+  params: LaunchParams,
+  grib_members: Option<Vec<Vec<String>>>,
+  ground_elevation: Option<f64>,
+) -> Result<ForecastEnsembleResult, PredictionError> {
+  let members = match grib_members {
+    Some(paths) => grib::load_grib_ensemble(paths, params.lat, params.lon).await?,
+    None => {
+      let launch_time = parse_time(&params.launch_time)
+        .ok_or_else(|| PredictionError::InvalidLaunchTime(params.launch_time.clone()))?;
+      weather::fetch_gefs_cached(&cache, params.lat, params.lon, launch_time).await?
+    }
+  };
+  let terrain = DemTerrain {
+    dem: dem.inner().clone(),
+    fallback: ground_elevation.unwrap_or(0.0),
+  };
+  let heat_cell_size = EnsembleSettings::default().heat_cell_size;
+  tauri::async_runtime::spawn_blocking(move || {
+    run_forecast_ensemble_simulation(&params, &members, &terrain, heat_cell_size)
+  })
+  .await
+  .map_err(|e| PredictionError::Simulation(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn settings(members: usize) -> EnsembleSettings {
+    EnsembleSettings {
+      members,
+      seed: Some(7),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn without_errors_every_member_matches_the_nominal_run() {
+    let quiet = EnsembleSettings {
+      ascent_rate_error: 0.0,
+      descent_rate_error: 0.0,
+      burst_altitude_error: 0.0,
+      wind_speed_error: 0.0,
+      wind_direction_error: 0.0,
+      ..settings(8)
+    };
+    let result = run_ensemble_simulation(
+      &LaunchParams::test_default(),
+      &WeatherGrid::uniform(10.0, 270.0, 48),
+      &0.0,
+      &quiet,
+    )
+    .unwrap();
+
+    assert_eq!(result.landings.len(), 8);
+    for landing in &result.landings {
+      assert_eq!(*landing, result.nominal.landing_point);
+    }
+    assert!(result.spread.ellipses[2].semi_major < 1.0);
+    assert!(result.spread.max_radius < 1.0);
+    assert!((result.spread.heat_map.probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn seeded_runs_are_reproducible() {
+    let a = run_ensemble_simulation(
+      &LaunchParams::test_default(),
+      &WeatherGrid::uniform(10.0, 270.0, 48),
+      &0.0,
+      &settings(16),
+    )
+    .unwrap();
+    let b = run_ensemble_simulation(
+      &LaunchParams::test_default(),
+      &WeatherGrid::uniform(10.0, 270.0, 48),
+      &0.0,
+      &settings(16),
+    )
+    .unwrap();
+    assert_eq!(a.landings, b.landings);
+    assert_eq!(a.seed, 7);
+  }
+
+  #[test]
+  fn ellipses_contain_the_landing_cloud() {
+    let result = run_ensemble_simulation(
+      &LaunchParams::test_default(),
+      &WeatherGrid::uniform(10.0, 270.0, 48),
+      &0.0,
+      &settings(400),
+    )
+    .unwrap();
+    assert_eq!(result.failed_members, 0);
+
+    let [one, two, three] = &result.spread.ellipses[..] else {
+      panic!("expected three ellipses");
+    };
+    assert!(one.semi_major > 1000.0);
+    assert!(one.semi_major >= one.semi_minor);
+    assert!((two.semi_major / one.semi_major - 2.0).abs() < 1e-9);
+    // Close to the 39 % / 86 % / 99 % of a bivariate normal.
+    assert!((0.25..0.55).contains(&one.containment));
+    assert!((0.75..0.95).contains(&two.containment));
+    assert!(three.containment > 0.95);
+
+    // The westerly spreads the cloud along the wind, east-west.
+    assert!((45.0..135.0).contains(&one.orientation));
+    assert!(result.spread.mean_landing.lon > LaunchParams::test_default().lon);
+
+    let heat = &result.spread.heat_map;
+    assert_eq!(heat.probabilities.len(), heat.rows * heat.cols);
+    assert!((heat.probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn runs_one_prediction_per_forecast_member() {
+    let members = crate::weather::open_meteo::parse_ensemble_response(
+      200,
+      include_str!("../tests/fixtures/open_meteo_gefs.json"),
+    )
+    .unwrap();
+    let result =
+      run_forecast_ensemble_simulation(&LaunchParams::test_default(), &members, &1600.0, 1000.0)
+        .unwrap();
+
+    assert_eq!(result.failed_members, 0);
+    let numbers: Vec<usize> = result.members.iter().map(|m| m.member).collect();
+    assert_eq!(numbers, vec![0, 1, 2]);
+    // Members differ only in wind speed, so faster members land farther east.
+    let lons: Vec<f64> = result.members.iter().map(|m| m.landing_point.lon).collect();
+    assert!(lons[0] < lons[1] && lons[1] < lons[2]);
+    assert!(result.spread.rms_radius > 0.0);
+    assert!(result.spread.max_radius >= result.spread.rms_radius);
+  }
+
+  #[test]
+  fn projection_wraps_the_antimeridian() {
+    let plane = Plane::new(LatLon {
+      lat: 0.0,
+      lon: 179.9,
+    });
+    let (x, y) = plane.project(0.0, -179.9);
+    assert!((x - geodesy::distance(0.0, 179.9, 0.0, -179.9)).abs() < 1e-6);
+    assert!(y.abs() < 1e-6);
+    let back = plane.unproject(x, y);
+    assert!((back.lon + 179.9).abs() < 1e-9);
+  }
+
+  #[test]
+  fn projection_keeps_true_distances_near_the_pole() {
+    let plane = Plane::new(LatLon {
+      lat: 89.95,
+      lon: 10.0,
+    });
+    // Across the pole, where an equirectangular plane would be meaningless.
+    for (lat, lon) in [(89.95, -170.0), (89.9, 100.0), (89.99, 45.0)] {
+      let (x, y) = plane.project(lat, lon);
+      assert!((x.hypot(y) - geodesy::distance(89.95, 10.0, lat, lon)).abs() < 1e-6);
+      let back = plane.unproject(x, y);
+      assert!(geodesy::distance(lat, lon, back.lat, back.lon) < 1e-3);
+    }
+  }
+}
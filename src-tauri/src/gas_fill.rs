@@ -0,0 +1,551 @@
+//! Gas fill planning.
+//!
+//! Turns a target neck lift into the gas to put in the balloon: the gross
+//! lift (neck lift plus envelope) over the lift per cubic meter of the gas
+//! at the launch site's temperature and pressure gives the volume, and the
+//! cylinder gas law, with a linear compressibility correction, turns that
+//! into cylinders and the pressure drop on the last one. The plan carries a
+//! fill checklist; saved on a mission, it records each neck lift measured
+//! during the fill and what to add or vent to reach the target.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::ascent::LiftGas;
+use crate::atmosphere::{state_at, AIR_MOLAR_MASS, GAS_CONSTANT};
+use crate::catalog::{CatalogError, CatalogStore, CylinderSpec, GasSpec};
+use crate::mission::{MissionError, MissionStore};
+use crate::weather::SurfaceSample;
+
+/// A measured neck lift within this of the target completes the fill, kg.
+const FILL_TOLERANCE_KG: f64 = 0.005;
+/// Pressure left in a cylinder the regulator can no longer use, bar gauge.
+const DEFAULT_RESIDUAL_PRESSURE_BAR: f64 = 2.0;
+
+/// Compressibility Z = 1 + k·p of the gas near room temperature, with the
+/// absolute pressure p in bar; within a few percent up to 300 bar.
+fn compressibility(gas: LiftGas, pressure: f64) -> f64 {
+  let k = match gas {
+    LiftGas::Helium => 0.00050,
+    LiftGas::Hydrogen => 0.00063,
+  };
+  1.0 + k * pressure
+}
+
+fn default_residual_pressure() -> f64 {
+  DEFAULT_RESIDUAL_PRESSURE_BAR
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FillRequest {
+  /// Catalog gas id
+  pub gas: String,
+  /// Lift measured at the neck, payload plus free lift, kg
+  pub neck_lift: f64,
+  /// Envelope mass, kg
+  pub balloon_mass: f64,
+  /// Forecast surface conditions at launch; the standard atmosphere at the
+  /// launch altitude fills in what is missing
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub surface: Option<SurfaceSample>,
+  /// m
+  #[serde(default)]
+  pub launch_altitude: f64,
+  /// Catalog cylinder ids to plan for; every catalog cylinder if not given
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cylinders: Option<Vec<String>>,
+  /// Pressure each cylinder is emptied to, bar gauge
+  #[serde(default = "default_residual_pressure")]
+  pub residual_pressure: f64,
+}
+
+/// How much of one cylinder size the fill takes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CylinderUse {
+  pub cylinder: CylinderSpec,
+  /// Gas one full cylinder delivers down to the residual pressure, m³ at
+  /// ambient conditions
+  pub usable_volume: f64,
+  /// Cylinders to have on hand
+  pub cylinders: usize,
+  /// Cylinders emptied to the residual pressure
+  pub full_cylinders: usize,
+  /// Pressure drop on the last cylinder, bar
+  pub last_cylinder_drop: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecklistStep {
+  pub title: String,
+  pub detail: String,
+  #[serde(default)]
+  pub done: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FillPlan {
+  pub gas: GasSpec,
+  /// kg
+  pub neck_lift: f64,
+  /// Neck lift plus envelope, kg
+  pub gross_lift: f64,
+  /// Ambient temperature, °C
+  pub temperature: f64,
+  /// Ambient pressure, hPa
+  pub pressure: f64,
+  /// Whether the standard atmosphere stood in for forecast values
+  pub standard_atmosphere: bool,
+  /// Lift per cubic meter of gas at ambient conditions, kg/m³
+  pub lift_density: f64,
+  /// Gas to fill, m³ at ambient conditions
+  pub volume: f64,
+  pub cylinders: Vec<CylinderUse>,
+  pub checklist: Vec<ChecklistStep>,
+}
+
+/// A neck lift measured during the fill.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiftMeasurement {
+  /// kg
+  pub neck_lift: f64,
+  pub measured_at: DateTime<Utc>,
+}
+
+/// What a measurement means for the fill.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FillAdjustment {
+  /// kg
+  pub measured: f64,
+  /// Lift still to add, kg; negative to vent
+  pub lift_change: f64,
+  /// Gas to add, m³ at ambient conditions; negative to vent
+  pub volume_change: f64,
+  /// Whether the measurement is within 5 g of the target
+  pub complete: bool,
+}
+
+/// A fill plan in use on launch day, saved with the mission.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FillRecord {
+  pub plan: FillPlan,
+  #[serde(default)]
+  pub measurements: Vec<LiftMeasurement>,
+}
+
+impl FillRecord {
+  /// Records a measured neck lift and works out the adjustment.
+  pub fn record(&mut self, neck_lift: f64, now: DateTime<Utc>) -> FillAdjustment {
+    self.measurements.push(LiftMeasurement {
+      neck_lift,
+      measured_at: now,
+    });
+    let lift_change = self.plan.neck_lift - neck_lift;
+    FillAdjustment {
+      measured: neck_lift,
+      lift_change,
+      volume_change: lift_change / self.plan.lift_density,
+      complete: lift_change.abs() <= FILL_TOLERANCE_KG,
+    }
+  }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GasFillError {
+  #[error(transparent)]
+  Catalog(#[from] CatalogError),
+  #[error(transparent)]
+  Mission(#[from] MissionError),
+  #[error("invalid fill request: {0}")]
+  InvalidRequest(&'static str),
+  #[error("mission {0:?} has no fill plan")]
+  NoPlan(String),
+  #[error("the fill checklist has no step {0}")]
+  NoStep(usize),
+}
+
+impl Serialize for GasFillError {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.to_string())
+  }
+}
+
+/// Gas in a cylinder at `pressure` (bar gauge), as m³ at `ambient` (bar
+/// absolute) and the same temperature.
+fn cylinder_content(cylinder: &CylinderSpec, gas: LiftGas, pressure: f64, ambient: f64) -> f64 {
+  let absolute = pressure + ambient;
+  cylinder.water_volume / 1000.0 * absolute / compressibility(gas, absolute) / ambient
+}
+
+/// Gauge pressure (bar) at which a cylinder holds `content` (m³ at
+/// `ambient`); the inverse of [`cylinder_content`].
+fn cylinder_pressure(cylinder: &CylinderSpec, gas: LiftGas, content: f64, ambient: f64) -> f64 {
+  let k = compressibility(gas, 1.0) - 1.0;
+  // content = V/1000 · p / (1 + k p) / ambient, solved for p
+  let c = content * ambient * 1000.0 / cylinder.water_volume;
+  c / (1.0 - k * c) - ambient
+}
+
+fn cylinder_use(
+  cylinder: &CylinderSpec,
+  gas: LiftGas,
+  volume: f64,
+  ambient: f64,
+  residual: f64,
+) -> CylinderUse {
+  let full = cylinder_content(cylinder, gas, cylinder.fill_pressure, ambient);
+  let usable_volume = full - cylinder_content(cylinder, gas, residual, ambient);
+  let full_cylinders = (volume / usable_volume).floor() as usize;
+  let rest = volume - full_cylinders as f64 * usable_volume;
+  let last_cylinder_drop = if rest > 0.0 {
+    cylinder.fill_pressure - cylinder_pressure(cylinder, gas, full - rest, ambient)
+  } else {
+    0.0
+  };
+  CylinderUse {
+    cylinder: cylinder.clone(),
+    usable_volume,
+    cylinders: full_cylinders + usize::from(rest > 0.0),
+    full_cylinders,
+    last_cylinder_drop,
+  }
+}
+
+fn checklist(plan: &FillPlan) -> Vec<ChecklistStep> {
+  let step = |title: &str, detail: String| ChecklistStep {
+    title: title.into(),
+    detail,
+    done: false,
+  };
+  let supply = match plan.cylinders.first() {
+    Some(first) => format!(
+      "{} x {} at {:.0} bar or more, or the equivalent in other sizes; note the starting \
+       pressures.",
+      first.cylinders, first.cylinder.name, first.cylinder.fill_pressure
+    ),
+    None => format!("{:.2} m³ of {}.", plan.volume, plan.gas.name),
+  };
+  vec![
+    step("Check the gas supply", supply),
+    step(
+      "Set up the fill weight",
+      format!(
+        "Hang {:.0} g on the filler: the target neck lift, payload plus free lift.",
+        plan.neck_lift * 1000.0
+      ),
+    ),
+    step(
+      "Fill",
+      format!(
+        "About {:.2} m³ ({:.0} L) of {} at {:.1} °C and {:.0} hPa.",
+        plan.volume,
+        plan.volume * 1000.0,
+        plan.gas.name,
+        plan.temperature,
+        plan.pressure
+      ),
+    ),
+    step(
+      "Measure the neck lift",
+      "Let the balloon lift the fill weight; record the measured lift, then add or vent gas \
+       until it is within 5 g."
+        .into(),
+    ),
+    step(
+      "Seal the neck",
+      "Tie off and secure the neck, then attach the payload train.".into(),
+    ),
+  ]
+}
+
+/// Plans the fill for `request` with `gas` and the given cylinder sizes.
+pub fn plan_fill(
+  request: &FillRequest,
+  gas: GasSpec,
+  cylinders: &[CylinderSpec],
+) -> Result<FillPlan, GasFillError> {
+  let valid = |v: f64| v.is_finite() && v >= 0.0;
+  if !valid(request.neck_lift)
+    || request.neck_lift == 0.0
+    || !valid(request.balloon_mass)
+    || !valid(request.residual_pressure)
+    || !request.launch_altitude.is_finite()
+  {
+    return Err(GasFillError::InvalidRequest(
+      "neck lift must be positive and masses and pressures non-negative",
+    ));
+  }
+  if cylinders
+    .iter()
+    .any(|c| request.residual_pressure >= c.fill_pressure)
+  {
+    return Err(GasFillError::InvalidRequest(
+      "the residual pressure must be below the cylinder fill pressure",
+    ));
+  }
+
+  let standard = state_at(request.launch_altitude);
+  let surface = request.surface.unwrap_or_default();
+  let temperature = surface
+    .temperature_2m
+    .map_or(standard.temperature, |t| t + 273.15);
+  let pressure = surface
+    .surface_pressure
+    .map_or(standard.pressure, |p| p * 100.0);
+  let density = pressure * AIR_MOLAR_MASS / (GAS_CONSTANT * temperature);
+  let lift_density = gas.gas.lift_density(gas.purity, density);
+  let gross_lift = request.neck_lift + request.balloon_mass;
+  let volume = gross_lift / lift_density;
+
+  let ambient = pressure / 1e5;
+  let mut plan = FillPlan {
+    neck_lift: request.neck_lift,
+    gross_lift,
+    temperature: temperature - 273.15,
+    pressure: pressure / 100.0,
+    standard_atmosphere: surface.temperature_2m.is_none() || surface.surface_pressure.is_none(),
+    lift_density,
+    volume,
+    cylinders: cylinders
+      .iter()
+      .map(|c| cylinder_use(c, gas.gas, volume, ambient, request.residual_pressure))
+      .collect(),
+    checklist: Vec::new(),
+    gas,
+  };
+  plan.checklist = checklist(&plan);
+  Ok(plan)
+}
+
+#[tauri::command]
+pub fn plan_gas_fill(
+  catalog: State<'_, CatalogStore>,
+  request: FillRequest,
+) -> Result<FillPlan, GasFillError> {
+  let gas = catalog.gas(&request.gas)?;
+  let mut cylinders = catalog.catalog().cylinders;
+  if let Some(ids) = &request.cylinders {
+    if let Some(missing) = ids.iter().find(|id| cylinders.iter().all(|c| &c.id != *id)) {
+      return Err(CatalogError::NotFound(missing.clone()).into());
+    }
+    cylinders.retain(|c| ids.contains(&c.id));
+  }
+  plan_fill(&request, gas, &cylinders)
+}
+
+/// Saves `plan` on the mission, starting a fresh fill record.
+#[tauri::command]
+pub fn start_gas_fill(
+  missions: State<'_, MissionStore>,
+  mission: String,
+  plan: FillPlan,
+) -> Result<FillRecord, GasFillError> {
+  let mut saved = missions
+    .get(&mission)?
+    .ok_or(MissionError::NotFound(mission))?;
+  let record = FillRecord {
+    plan,
+    measurements: Vec::new(),
+  };
+  saved.fill = Some(record.clone());
+  missions.save(saved, Utc::now())?;
+  Ok(record)
+}
+
+/// Ticks a checklist step on or off.
+#[tauri::command]
+pub fn check_fill_step(
+  missions: State<'_, MissionStore>,
+  mission: String,
+  step: usize,
+  done: bool,
+) -> Result<FillRecord, GasFillError> {
+  let mut saved = missions
+    .get(&mission)?
+    .ok_or_else(|| MissionError::NotFound(mission.clone()))?;
+  let record = saved.fill.as_mut().ok_or(GasFillError::NoPlan(mission))?;
+  record
+    .plan
+    .checklist
+    .get_mut(step)
+    .ok_or(GasFillError::NoStep(step))?
+    .done = done;
+  let record = record.clone();
+  missions.save(saved, Utc::now())?;
+  Ok(record)
+}
+
+/// Records a measured neck lift (kg) on the mission's fill.
+#[tauri::command]
+pub fn record_neck_lift(
+  missions: State<'_, MissionStore>,
+  mission: String,
+  neck_lift: f64,
+) -> Result<FillAdjustment, GasFillError> {
+  if !(neck_lift.is_finite() && neck_lift >= 0.0) {
+    return Err(GasFillError::InvalidRequest(
+      "the measured neck lift must be non-negative",
+    ));
+  }
+  let mut saved = missions
+    .get(&mission)?
+    .ok_or_else(|| MissionError::NotFound(mission.clone()))?;
+  let now = Utc::now();
+  let adjustment = saved
+    .fill
+    .as_mut()
+    .ok_or(GasFillError::NoPlan(mission))?
+    .record(neck_lift, now);
+  missions.save(saved, now)?;
+  Ok(adjustment)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn request() -> FillRequest {
+    FillRequest {
+      gas: "helium".into(),
+      neck_lift: 2.5,
+      balloon_mass: 1.2,
+      surface: Some(SurfaceSample {
+        temperature_2m: Some(15.0),
+        surface_pressure: Some(1013.25),
+        ..Default::default()
+      }),
+      launch_altitude: 0.0,
+      cylinders: None,
+      residual_pressure: DEFAULT_RESIDUAL_PRESSURE_BAR,
+    }
+  }
+
+  fn plan(request: &FillRequest) -> FillPlan {
+    let catalog = CatalogStore::in_memory().unwrap();
+    plan_fill(
+      request,
+      catalog.gas(&request.gas).unwrap(),
+      &catalog.catalog().cylinders,
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn converts_lift_to_volume_at_ambient_conditions() {
+    let sea_level = plan(&request());
+    // Helium lifts about 1.05 kg/m³ at 15 °C and 1013 hPa.
+    assert!((sea_level.lift_density - 1.055).abs() < 0.005);
+    assert!((sea_level.volume - 3.7 / sea_level.lift_density).abs() < 1e-12);
+    assert!(!sea_level.standard_atmosphere);
+
+    // Thinner, warmer air at a high launch site takes more gas.
+    let mountain = plan(&FillRequest {
+      surface: Some(SurfaceSample {
+        temperature_2m: Some(30.0),
+        surface_pressure: Some(800.0),
+        ..Default::default()
+      }),
+      ..request()
+    });
+    assert!(mountain.volume / sea_level.volume > 1.3);
+
+    // Without forecast values, the standard atmosphere stands in.
+    let standard = plan(&FillRequest {
+      surface: None,
+      launch_altitude: 1600.0,
+      ..request()
+    });
+    assert!(standard.standard_atmosphere);
+    assert!(
+      (standard.pressure - 835.0).abs() < 2.0,
+      "{}",
+      standard.pressure
+    );
+  }
+
+  #[test]
+  fn counts_cylinders_and_pressure_drop() {
+    let plan = plan(&request());
+    let fifty = plan
+      .cylinders
+      .iter()
+      .find(|c| c.cylinder.id == "eu-50l-200")
+      .unwrap();
+    // 50 L at 200 bar holds about 9 m³ of helium, so one cylinder does.
+    assert!(
+      (fifty.usable_volume - 9.0).abs() < 0.3,
+      "{}",
+      fifty.usable_volume
+    );
+    assert_eq!((fifty.cylinders, fifty.full_cylinders), (1, 0));
+    let expected_drop = plan.volume / fifty.usable_volume * 198.0;
+    assert!(
+      (fifty.last_cylinder_drop - expected_drop).abs() < 5.0,
+      "{} vs {}",
+      fifty.last_cylinder_drop,
+      expected_drop
+    );
+
+    let ten = plan
+      .cylinders
+      .iter()
+      .find(|c| c.cylinder.id == "eu-10l-200")
+      .unwrap();
+    assert_eq!(ten.full_cylinders, 1);
+    assert_eq!(ten.cylinders, 2);
+    // The drop on the last cylinder delivers the rest exactly.
+    let gas = LiftGas::Helium;
+    let ambient = 1.01325;
+    let full = cylinder_content(&ten.cylinder, gas, 200.0, ambient);
+    let last = full - cylinder_content(&ten.cylinder, gas, 200.0 - ten.last_cylinder_drop, ambient);
+    assert!((ten.usable_volume + last - plan.volume).abs() < 1e-9);
+  }
+
+  #[test]
+  fn records_measured_lift() {
+    let plan = plan(&request());
+    assert_eq!(plan.checklist.len(), 5);
+    assert!(plan.checklist[1].detail.contains("2500 g"));
+
+    let mut record = FillRecord {
+      plan,
+      measurements: Vec::new(),
+    };
+    let now = crate::weather::parse_time("2025-07-07T11:00:00Z").unwrap();
+    let short = record.record(2.4, now);
+    assert!((short.lift_change - 0.1).abs() < 1e-12);
+    assert!(short.volume_change > 0.09 && !short.complete);
+    let over = record.record(2.503, now);
+    assert!(over.volume_change < 0.0 && over.complete);
+    assert_eq!(record.measurements.len(), 2);
+  }
+
+  #[test]
+  fn rejects_bad_requests() {
+    let catalog = CatalogStore::in_memory().unwrap();
+    let gas = catalog.gas("helium").unwrap();
+    let cylinders = catalog.catalog().cylinders;
+    for request in [
+      FillRequest {
+        neck_lift: 0.0,
+        ..request()
+      },
+      FillRequest {
+        residual_pressure: 250.0,
+        ..request()
+      },
+    ] {
+      assert!(matches!(
+        plan_fill(&request, gas.clone(), &cylinders),
+        Err(GasFillError::InvalidRequest(_))
+      ));
+    }
+  }
+}
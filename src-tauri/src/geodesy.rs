@@ -0,0 +1,282 @@
+//! Geodesics on the WGS84 ellipsoid.
+//!
+//! Vincenty's direct and inverse solutions, accurate to well under a
+//! millimetre. Longitudes are returned in [-180, 180), so tracks across the
+//! antimeridian stay valid, and nothing divides by cos(latitude), so the
+//! poles are handled like any other point. The inverse solution does not
+//! converge for nearly antipodal points; those fall back to the great circle
+//! on the mean sphere, which is never needed for step-sized distances.
+
+/// WGS84 semi-major axis, m
+pub const WGS84_A: f64 = 6378137.0;
+/// WGS84 flattening
+pub const WGS84_F: f64 = 1.0 / 298.257223563;
+/// WGS84 semi-minor axis, m
+pub const WGS84_B: f64 = WGS84_A * (1.0 - WGS84_F);
+/// Mean radius (2a + b) / 3, m
+pub const MEAN_RADIUS_M: f64 = (2.0 * WGS84_A + WGS84_B) / 3.0;
+
+const CONVERGENCE: f64 = 1e-12;
+const MAX_ITERATIONS: usize = 200;
+
+/// Wraps a longitude into [-180, 180).
+pub fn normalize_lon(lon: f64) -> f64 {
+  (lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Solution of the inverse problem between two points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Inverse {
+  /// m
+  pub distance: f64,
+  /// Forward azimuth at the first point, degrees clockwise from north
+  pub initial_bearing: f64,
+  /// Forward azimuth at the second point, degrees
+  pub final_bearing: f64,
+}
+
+/// Solution of the direct problem.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Direct {
+  pub lat: f64,
+  pub lon: f64,
+  /// Forward azimuth at the end point, degrees
+  pub final_bearing: f64,
+}
+
+fn reduced_latitude(lat: f64) -> (f64, f64) {
+  let tan_u = (1.0 - WGS84_F) * lat.to_radians().tan();
+  let cos_u = 1.0 / (1.0 + tan_u * tan_u).sqrt();
+  (tan_u * cos_u, cos_u)
+}
+
+fn bearing_degrees(radians: f64) -> f64 {
+  radians.to_degrees().rem_euclid(360.0)
+}
+
+/// Vincenty's series coefficients A and B for cos²α.
+fn series(cos_sq_alpha: f64) -> (f64, f64) {
+  let u_sq = cos_sq_alpha * (WGS84_A * WGS84_A - WGS84_B * WGS84_B) / (WGS84_B * WGS84_B);
+  let a = 1.0 + u_sq / 16384.0 * (4096.0 + u_sq * (-768.0 + u_sq * (320.0 - 175.0 * u_sq)));
+  let b = u_sq / 1024.0 * (256.0 + u_sq * (-128.0 + u_sq * (74.0 - 47.0 * u_sq)));
+  (a, b)
+}
+
+fn delta_sigma(b: f64, sin_sigma: f64, cos_sigma: f64, cos_2sigma_m: f64) -> f64 {
+  let c2 = cos_2sigma_m * cos_2sigma_m;
+  b * sin_sigma
+    * (cos_2sigma_m
+      + b / 4.0
+        * (cos_sigma * (-1.0 + 2.0 * c2)
+          - b / 6.0 * cos_2sigma_m * (-3.0 + 4.0 * sin_sigma * sin_sigma) * (-3.0 + 4.0 * c2)))
+}
+
+/// Travels `distance` meters from (`lat`, `lon`) along the geodesic leaving
+/// at `bearing` degrees.
+pub fn direct(lat: f64, lon: f64, bearing: f64, distance: f64) -> Direct {
+  let alpha1 = bearing.to_radians();
+  let (sin_alpha1, cos_alpha1) = alpha1.sin_cos();
+  let (sin_u1, cos_u1) = reduced_latitude(lat);
+  let sigma1 = (sin_u1 / cos_u1).atan2(cos_alpha1);
+  let sin_alpha = cos_u1 * sin_alpha1;
+  let cos_sq_alpha = 1.0 - sin_alpha * sin_alpha;
+  let (a, b) = series(cos_sq_alpha);
+
+  let sigma0 = distance / (WGS84_B * a);
+  let mut sigma = sigma0;
+  let (mut sin_sigma, mut cos_sigma, mut cos_2sigma_m);
+  let mut iterations = 0;
+  loop {
+    cos_2sigma_m = (2.0 * sigma1 + sigma).cos();
+    (sin_sigma, cos_sigma) = sigma.sin_cos();
+    let next = sigma0 + delta_sigma(b, sin_sigma, cos_sigma, cos_2sigma_m);
+    iterations += 1;
+    if (next - sigma).abs() < CONVERGENCE || iterations >= MAX_ITERATIONS {
+      sigma = next;
+      break;
+    }
+    sigma = next;
+  }
+  (sin_sigma, cos_sigma) = sigma.sin_cos();
+  cos_2sigma_m = (2.0 * sigma1 + sigma).cos();
+
+  let x = sin_u1 * sin_sigma - cos_u1 * cos_sigma * cos_alpha1;
+  let lat2 = (sin_u1 * cos_sigma + cos_u1 * sin_sigma * cos_alpha1)
+    .atan2((1.0 - WGS84_F) * sin_alpha.hypot(x));
+  let lambda = (sin_sigma * sin_alpha1).atan2(cos_u1 * cos_sigma - sin_u1 * sin_sigma * cos_alpha1);
+  let c = WGS84_F / 16.0 * cos_sq_alpha * (4.0 + WGS84_F * (4.0 - 3.0 * cos_sq_alpha));
+  let l = lambda
+    - (1.0 - c)
+      * WGS84_F
+      * sin_alpha
+      * (sigma
+        + c
+          * sin_sigma
+          * (cos_2sigma_m + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m)));
+
+  Direct {
+    lat: lat2.to_degrees(),
+    lon: normalize_lon(lon + l.to_degrees()),
+    final_bearing: bearing_degrees(sin_alpha.atan2(-x)),
+  }
+}
+
+/// Distance and bearings between two points.
+pub fn inverse(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> Inverse {
+  let l = normalize_lon(lon2 - lon1).to_radians();
+  let (sin_u1, cos_u1) = reduced_latitude(lat1);
+  let (sin_u2, cos_u2) = reduced_latitude(lat2);
+
+  let mut lambda = l;
+  for _ in 0..MAX_ITERATIONS {
+    let (sin_lambda, cos_lambda) = lambda.sin_cos();
+    let sin_sigma = (cos_u2 * sin_lambda).hypot(cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda);
+    if sin_sigma == 0.0 {
+      return Inverse {
+        distance: 0.0,
+        initial_bearing: 0.0,
+        final_bearing: 0.0,
+      };
+    }
+    let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+    let sigma = sin_sigma.atan2(cos_sigma);
+    let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+    let cos_sq_alpha = 1.0 - sin_alpha * sin_alpha;
+    // Along the equator cos²α is zero and so is this term.
+    let cos_2sigma_m = if cos_sq_alpha != 0.0 {
+      cos_sigma - 2.0 * sin_u1 * sin_u2 / cos_sq_alpha
+    } else {
+      0.0
+    };
+    let c = WGS84_F / 16.0 * cos_sq_alpha * (4.0 + WGS84_F * (4.0 - 3.0 * cos_sq_alpha));
+    let previous = lambda;
+    lambda = l
+      + (1.0 - c)
+        * WGS84_F
+        * sin_alpha
+        * (sigma
+          + c
+            * sin_sigma
+            * (cos_2sigma_m + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m)));
+    if lambda.abs() > std::f64::consts::PI {
+      break;
+    }
+    if (lambda - previous).abs() < CONVERGENCE {
+      let (a, b) = series(cos_sq_alpha);
+      let distance = WGS84_B * a * (sigma - delta_sigma(b, sin_sigma, cos_sigma, cos_2sigma_m));
+      let (sin_lambda, cos_lambda) = lambda.sin_cos();
+      return Inverse {
+        distance,
+        initial_bearing: bearing_degrees(
+          (cos_u2 * sin_lambda).atan2(cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda),
+        ),
+        final_bearing: bearing_degrees(
+          (cos_u1 * sin_lambda).atan2(-sin_u1 * cos_u2 + cos_u1 * sin_u2 * cos_lambda),
+        ),
+      };
+    }
+  }
+  spherical_inverse(lat1, lon1, lat2, lon2)
+}
+
+/// Great-circle solution on the mean sphere.
+fn spherical_inverse(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> Inverse {
+  let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+  let d_lon = normalize_lon(lon2 - lon1).to_radians();
+  let a =
+    ((phi2 - phi1) / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lon / 2.0).sin().powi(2);
+  let bearing = |p1: f64, p2: f64, dl: f64| {
+    bearing_degrees(
+      (dl.sin() * p2.cos()).atan2(p1.cos() * p2.sin() - p1.sin() * p2.cos() * dl.cos()),
+    )
+  };
+  Inverse {
+    distance: 2.0 * MEAN_RADIUS_M * a.sqrt().atan2((1.0 - a).sqrt()),
+    initial_bearing: bearing(phi1, phi2, d_lon),
+    final_bearing: (bearing(phi2, phi1, -d_lon) + 180.0) % 360.0,
+  }
+}
+
+/// Geodesic distance in meters.
+pub fn distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+  inverse(lat1, lon1, lat2, lon2).distance
+}
+
+/// The point a fraction `f` of the way along the geodesic between two
+/// points.
+pub fn intermediate(lat1: f64, lon1: f64, lat2: f64, lon2: f64, f: f64) -> (f64, f64) {
+  let path = inverse(lat1, lon1, lat2, lon2);
+  let point = direct(lat1, lon1, path.initial_bearing, path.distance * f);
+  (point.lat, point.lon)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn dms(degrees: f64, minutes: f64, seconds: f64) -> f64 {
+    degrees.signum() * (degrees.abs() + minutes / 60.0 + seconds / 3600.0)
+  }
+
+  #[test]
+  fn matches_vincenty_reference_line() {
+    // Flinders Peak to Buninyong, from Vincenty's paper via Geoscience Australia.
+    let (lat1, lon1) = (dms(-37.0, 57.0, 3.72030), dms(144.0, 25.0, 29.52440));
+    let (lat2, lon2) = (dms(-37.0, 39.0, 10.15610), dms(143.0, 55.0, 35.38390));
+
+    let line = inverse(lat1, lon1, lat2, lon2);
+    assert!(
+      (line.distance - 54972.271).abs() < 1e-3,
+      "{}",
+      line.distance
+    );
+    assert!((line.initial_bearing - dms(306.0, 52.0, 5.37)).abs() < 1e-5);
+    assert!((line.final_bearing - dms(307.0, 10.0, 25.07)).abs() < 1e-5);
+
+    let end = direct(lat1, lon1, line.initial_bearing, line.distance);
+    assert!((end.lat - lat2).abs() < 1e-9 && (end.lon - lon2).abs() < 1e-9);
+    assert!((end.final_bearing - line.final_bearing).abs() < 1e-7);
+  }
+
+  #[test]
+  fn crosses_the_antimeridian() {
+    let end = direct(10.0, 179.9, 90.0, 30_000.0);
+    assert!(end.lon < -179.0 && end.lon >= -180.0, "{}", end.lon);
+
+    // 0.2° of longitude on the equator, not 359.8°.
+    let line = inverse(0.0, 179.9, 0.0, -179.9);
+    assert!((line.distance - 22263.9).abs() < 0.1, "{}", line.distance);
+    assert!((line.initial_bearing - 90.0).abs() < 1e-9);
+    assert_eq!(normalize_lon(180.0), -180.0);
+    assert_eq!(normalize_lon(-190.0), 170.0);
+  }
+
+  #[test]
+  fn passes_over_the_pole() {
+    // 10 km short of the pole heading north, 20 km on is 10 km down the
+    // far side.
+    let start = direct(90.0, 0.0, 180.0, 10_000.0);
+    let end = direct(start.lat, start.lon, 0.0, 20_000.0);
+    assert!(
+      (end.lat - start.lat).abs() < 1e-6,
+      "{} {}",
+      start.lat,
+      end.lat
+    );
+    assert!((end.lon.abs() - 180.0).abs() < 1e-6, "{}", end.lon);
+    assert!((end.final_bearing - 180.0).abs() < 1e-6);
+    assert!((distance(start.lat, start.lon, end.lat, end.lon) - 20_000.0).abs() < 1e-3);
+    assert!((distance(90.0, 0.0, 90.0, 123.0)).abs() < 1e-9);
+  }
+
+  #[test]
+  fn falls_back_near_antipodes() {
+    let line = inverse(0.0, 0.0, 0.5, 179.7);
+    assert!(line.distance.is_finite());
+    assert!(
+      (line.distance - 19_936_000.0).abs() < 40_000.0,
+      "{}",
+      line.distance
+    );
+  }
+}
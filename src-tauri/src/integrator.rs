@@ -0,0 +1,222 @@
+//! Time integrators for the trajectory ODE.
+//!
+//! The state is a small fixed-size vector and the derivative a closure of
+//! time and state. For the flight it is the east and north offset in meters
+//! from the position at the start of the step, and the altitude; the
+//! prediction maps the offset onto the ellipsoid with
+//! [`displaced`](crate::prediction::displaced). Euler and RK4 take the step
+//! they are given; RK45 (Dormand–Prince) estimates its local error from the
+//! embedded fourth-order solution, retries rejected steps with a smaller one
+//! and proposes the next step size.
+
+use serde::{Deserialize, Serialize};
+
+/// Smallest and largest step the adaptive integrator will take, s.
+pub const MIN_STEP_S: f64 = 0.5;
+pub const MAX_STEP_S: f64 = 300.0;
+
+/// East m, north m, altitude m, for the flight.
+pub type StateVector = [f64; 3];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Integrator {
+  /// Forward Euler, the scheme of the TypeScript engine.
+  Euler,
+  /// Classic fourth-order Runge–Kutta.
+  #[default]
+  Rk4,
+  /// Adaptive Dormand–Prince 5(4).
+  Rk45,
+}
+
+/// Result of one step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Step {
+  pub state: StateVector,
+  /// Step actually taken, s
+  pub dt: f64,
+  /// Step to try next, s
+  pub next_dt: f64,
+}
+
+fn add(y: &StateVector, k: &[(f64, &StateVector)]) -> StateVector {
+  let mut out = *y;
+  for (weight, k) in k {
+    for i in 0..out.len() {
+      out[i] += weight * k[i];
+    }
+  }
+  out
+}
+
+impl Integrator {
+  /// The fixed-step scheme of the same order, for shortened final steps.
+  pub fn fixed(self) -> Integrator {
+    match self {
+      Integrator::Euler => Integrator::Euler,
+      Integrator::Rk4 | Integrator::Rk45 => Integrator::Rk4,
+    }
+  }
+
+  /// Advances `y` from `t` by up to `dt`. `error` maps the difference
+  /// between two states to a scalar compared against `tolerance`; only the
+  /// adaptive scheme uses them.
+  pub fn step(
+    self,
+    f: impl Fn(f64, &StateVector) -> StateVector,
+    t: f64,
+    y: &StateVector,
+    dt: f64,
+    tolerance: f64,
+    error: impl Fn(&StateVector, &StateVector) -> f64,
+  ) -> Step {
+    match self {
+      Integrator::Euler => Step {
+        state: add(y, &[(dt, &f(t, y))]),
+        dt,
+        next_dt: dt,
+      },
+      Integrator::Rk4 => {
+        let k1 = f(t, y);
+        let k2 = f(t + dt / 2.0, &add(y, &[(dt / 2.0, &k1)]));
+        let k3 = f(t + dt / 2.0, &add(y, &[(dt / 2.0, &k2)]));
+        let k4 = f(t + dt, &add(y, &[(dt, &k3)]));
+        Step {
+          state: add(
+            y,
+            &[
+              (dt / 6.0, &k1),
+              (dt / 3.0, &k2),
+              (dt / 3.0, &k3),
+              (dt / 6.0, &k4),
+            ],
+          ),
+          dt,
+          next_dt: dt,
+        }
+      }
+      Integrator::Rk45 => dormand_prince(f, t, y, dt, tolerance, error),
+    }
+  }
+}
+
+fn dormand_prince(
+  f: impl Fn(f64, &StateVector) -> StateVector,
+  t: f64,
+  y: &StateVector,
+  mut dt: f64,
+  tolerance: f64,
+  error: impl Fn(&StateVector, &StateVector) -> f64,
+) -> Step {
+  dt = dt.min(MAX_STEP_S);
+  loop {
+    let k1 = f(t, y);
+    let k2 = f(t + dt / 5.0, &add(y, &[(dt / 5.0, &k1)]));
+    let k3 = f(
+      t + 3.0 * dt / 10.0,
+      &add(y, &[(dt * 3.0 / 40.0, &k1), (dt * 9.0 / 40.0, &k2)]),
+    );
+    let k4 = f(
+      t + 4.0 * dt / 5.0,
+      &add(
+        y,
+        &[
+          (dt * 44.0 / 45.0, &k1),
+          (dt * -56.0 / 15.0, &k2),
+          (dt * 32.0 / 9.0, &k3),
+        ],
+      ),
+    );
+    let k5 = f(
+      t + 8.0 * dt / 9.0,
+      &add(
+        y,
+        &[
+          (dt * 19372.0 / 6561.0, &k1),
+          (dt * -25360.0 / 2187.0, &k2),
+          (dt * 64448.0 / 6561.0, &k3),
+          (dt * -212.0 / 729.0, &k4),
+        ],
+      ),
+    );
+    let k6 = f(
+      t + dt,
+      &add(
+        y,
+        &[
+          (dt * 9017.0 / 3168.0, &k1),
+          (dt * -355.0 / 33.0, &k2),
+          (dt * 46732.0 / 5247.0, &k3),
+          (dt * 49.0 / 176.0, &k4),
+          (dt * -5103.0 / 18656.0, &k5),
+        ],
+      ),
+    );
+    let fifth = add(
+      y,
+      &[
+        (dt * 35.0 / 384.0, &k1),
+        (dt * 500.0 / 1113.0, &k3),
+        (dt * 125.0 / 192.0, &k4),
+        (dt * -2187.0 / 6784.0, &k5),
+        (dt * 11.0 / 84.0, &k6),
+      ],
+    );
+    let k7 = f(t + dt, &fifth);
+    let fourth = add(
+      y,
+      &[
+        (dt * 5179.0 / 57600.0, &k1),
+        (dt * 7571.0 / 16695.0, &k3),
+        (dt * 393.0 / 640.0, &k4),
+        (dt * -92097.0 / 339200.0, &k5),
+        (dt * 187.0 / 2100.0, &k6),
+        (dt / 40.0, &k7),
+      ],
+    );
+
+    let err = error(&fifth, &fourth);
+    let scale = if err > 0.0 {
+      (0.9 * (tolerance / err).powf(0.2)).clamp(0.2, 5.0)
+    } else {
+      5.0
+    };
+    if err <= tolerance || dt <= MIN_STEP_S {
+      return Step {
+        state: fifth,
+        dt,
+        next_dt: (dt * scale).clamp(MIN_STEP_S, MAX_STEP_S),
+      };
+    }
+    dt = (dt * scale).max(MIN_STEP_S);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// y' = y from y(0) = 1 to t = 1, in the first component.
+  fn integrate(integrator: Integrator, dt: f64) -> f64 {
+    let f = |_t: f64, y: &StateVector| [y[0], 0.0, 0.0];
+    let error = |a: &StateVector, b: &StateVector| (a[0] - b[0]).abs();
+    let (mut t, mut y, mut dt) = (0.0, [1.0, 0.0, 0.0], dt);
+    while t < 1.0 - 1e-12 {
+      let step = integrator.step(f, t, &y, dt.min(1.0 - t), 1e-9, error);
+      (t, y, dt) = (t + step.dt, step.state, step.next_dt);
+    }
+    y[0]
+  }
+
+  #[test]
+  fn integrators_converge_at_their_order() {
+    let e = std::f64::consts::E;
+    let euler = |dt| (integrate(Integrator::Euler, dt) - e).abs();
+    let rk4 = |dt| (integrate(Integrator::Rk4, dt) - e).abs();
+
+    // Halving the step halves Euler's error and cuts RK4's sixteenfold.
+    assert!((euler(0.01) / euler(0.005) - 2.0).abs() < 0.05);
+    assert!((rk4(0.1) / rk4(0.05) - 16.0).abs() < 1.0);
+  }
+}
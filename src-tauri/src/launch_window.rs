@@ -0,0 +1,625 @@
+//! Launch window optimizer.
+//!
+//! Runs a prediction for every launch time in a window, e.g. hourly for the
+//! week ahead, and scores each one on how far from the launch site it
+//! lands, whether it lands in a hazard zone, whether the sun is up at the
+//! landing, and the surface wind at launch. Consecutive launch times that
+//! score above a threshold form the launch windows, ranked by their best
+//! launch. The forecast is fetched far enough ahead to cover the last
+//! launch's flight, as far as the GFS reaches; launch times past the end of
+//! the forecast are not run, and flights that outlast it are marked and
+//! kept out of the launch windows.
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::elevation::dem::DemProvider;
+use crate::elevation::{DemTerrain, Terrain};
+use crate::geodesy;
+use crate::prediction::{
+  launch_times, resolve_weather, run_prediction_simulation, FlightPoint, LaunchParams,
+  PredictionError, MAX_FLIGHT_TIME_S,
+};
+use crate::weather::cache::ForecastCache;
+use crate::weather::{self, parse_time, WeatherError, WeatherGrid};
+
+/// Sun elevation, degrees, below which a landing counts as at night; the
+/// end of civil twilight.
+const NIGHT_SUN_ELEVATION: f64 = -6.0;
+/// Longest window swept when no end is given.
+const DEFAULT_WINDOW_DAYS: i64 = 7;
+
+/// Relative weight of each score in a run's total.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScoreWeights {
+  pub distance: f64,
+  pub hazard: f64,
+  pub daylight: f64,
+  pub surface_wind: f64,
+}
+
+impl Default for ScoreWeights {
+  fn default() -> Self {
+    ScoreWeights {
+      distance: 1.0,
+      hazard: 1.0,
+      daylight: 1.0,
+      surface_wind: 1.0,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "shape", rename_all = "camelCase")]
+pub enum HazardShape {
+  /// `radius` meters around a point
+  Circle { lat: f64, lon: f64, radius: f64 },
+  /// `[lat, lon]` vertices of a simple polygon
+  Polygon { vertices: Vec<[f64; 2]> },
+}
+
+/// An area to keep the landing out of, e.g. a city, lake or airfield.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HazardZone {
+  pub name: String,
+  #[serde(flatten)]
+  pub shape: HazardShape,
+}
+
+impl HazardZone {
+  pub fn contains(&self, lat: f64, lon: f64) -> bool {
+    match &self.shape {
+      HazardShape::Circle {
+        lat: center_lat,
+        lon: center_lon,
+        radius,
+      } => geodesy::distance(lat, lon, *center_lat, *center_lon) <= *radius,
+      HazardShape::Polygon { vertices } => {
+        // Ray casting, with longitudes taken relative to the point so a
+        // polygon may straddle the antimeridian.
+        let relative = |v: &[f64; 2]| (v[0], geodesy::normalize_lon(v[1] - lon));
+        let mut inside = false;
+        for (i, vertex) in vertices.iter().enumerate() {
+          let (lat1, lon1) = relative(vertex);
+          let (lat2, lon2) = relative(&vertices[(i + 1) % vertices.len()]);
+          if (lat1 > lat) != (lat2 > lat)
+            && 0.0 < lon1 + (lat - lat1) / (lat2 - lat1) * (lon2 - lon1)
+          {
+            inside = !inside;
+          }
+        }
+        inside
+      }
+    }
+  }
+
+  fn validate(&self) -> Result<(), PredictionError> {
+    let valid = match &self.shape {
+      HazardShape::Circle { lat, lon, radius } => {
+        lat.is_finite() && lon.is_finite() && radius.is_finite() && *radius > 0.0
+      }
+      HazardShape::Polygon { vertices } => {
+        vertices.len() >= 3 && vertices.iter().flatten().all(|c| c.is_finite())
+      }
+    };
+    if valid {
+      Ok(())
+    } else {
+      Err(PredictionError::InvalidParams(
+        "hazard zones need a positive radius or at least three vertices",
+      ))
+    }
+  }
+}
+
+/// Launch times to sweep and how to score them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WindowSettings {
+  /// ISO format string; the launch time of the parameters if not given
+  pub window_start: Option<String>,
+  /// ISO format string, inclusive; a week after the start if not given.
+  /// Launch times past the end of the forecast are not run.
+  pub window_end: Option<String>,
+  /// Seconds between launch times
+  pub window_step: f64,
+  pub hazards: Vec<HazardZone>,
+  /// Landing distance from the launch site that scores zero, m
+  pub max_distance: f64,
+  /// Surface wind or gust at launch that scores zero, m/s
+  pub max_surface_wind: f64,
+  pub weights: ScoreWeights,
+  /// Score (0-100) a launch time needs to be part of a launch window
+  pub min_score: f64,
+  /// Launch windows to return
+  pub best_count: usize,
+}
+
+impl Default for WindowSettings {
+  fn default() -> Self {
+    WindowSettings {
+      window_start: None,
+      window_end: None,
+      window_step: 3600.0,
+      hazards: Vec::new(),
+      max_distance: 200_000.0,
+      max_surface_wind: 10.0,
+      weights: ScoreWeights::default(),
+      min_score: 70.0,
+      best_count: 5,
+    }
+  }
+}
+
+/// Each score runs from 0 (worst) to 1 (best).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scores {
+  pub distance: f64,
+  pub hazard: f64,
+  pub daylight: f64,
+  pub surface_wind: f64,
+}
+
+impl Scores {
+  /// Weighted mean, 0-100.
+  fn total(&self, weights: &ScoreWeights) -> f64 {
+    let sum = weights.distance + weights.hazard + weights.daylight + weights.surface_wind;
+    100.0
+      * (weights.distance * self.distance
+        + weights.hazard * self.hazard
+        + weights.daylight * self.daylight
+        + weights.surface_wind * self.surface_wind)
+      / sum
+  }
+}
+
+/// One row of the launch time table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowRun {
+  /// ISO format string
+  pub launch_time: String,
+  /// 0-100
+  pub score: f64,
+  pub scores: Scores,
+  pub landing_point: FlightPoint,
+  /// Straight-line distance from the launch site to the landing, m
+  pub landing_distance: f64,
+  /// seconds
+  pub flight_duration: f64,
+  /// Names of the hazard zones the landing falls in
+  pub hazards: Vec<String>,
+  /// Sun elevation at the landing, degrees
+  pub sun_elevation: f64,
+  /// Stronger of the 10 m wind and gusts at launch, m/s, if forecast
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub surface_wind: Option<f64>,
+  /// Whether the flight outlasted the forecast; such runs drift on the last
+  /// forecast hour's winds and never count towards a launch window
+  pub forecast_exceeded: bool,
+}
+
+/// Consecutive launch times scoring at least the threshold.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchWindow {
+  /// ISO format strings, inclusive
+  pub start: String,
+  pub end: String,
+  pub best_launch_time: String,
+  pub best_score: f64,
+  pub mean_score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowResult {
+  /// Every launch time in the window, in time order
+  pub runs: Vec<WindowRun>,
+  /// Best launch windows first
+  pub windows: Vec<LaunchWindow>,
+  /// Last forecast hour, ISO format string; no launch time after it is run
+  pub forecast_end: String,
+}
+
+/// Sun elevation above the horizon, degrees, from the NOAA low-precision
+/// solar position (good to about a tenth of a degree).
+pub fn sun_elevation(lat: f64, lon: f64, time: DateTime<Utc>) -> f64 {
+  let days = time.timestamp() as f64 / 86400.0 - 10957.5;
+  let mean_longitude = 280.460 + 0.985_647_4 * days;
+  let anomaly = (357.528 + 0.985_600_3 * days).to_radians();
+  let ecliptic_longitude =
+    (mean_longitude + 1.915 * anomaly.sin() + 0.020 * (2.0 * anomaly).sin()).to_radians();
+  let obliquity = (23.439 - 0.000_000_4 * days).to_radians();
+  let right_ascension = (obliquity.cos() * ecliptic_longitude.sin())
+    .atan2(ecliptic_longitude.cos())
+    .to_degrees();
+  let declination = (obliquity.sin() * ecliptic_longitude.sin()).asin();
+  let sidereal = 280.460_618_37 + 360.985_647_366_29 * days;
+  let hour_angle = (sidereal + lon - right_ascension).to_radians();
+  let lat = lat.to_radians();
+  (lat.sin() * declination.sin() + lat.cos() * declination.cos() * hour_angle.cos())
+    .asin()
+    .to_degrees()
+}
+
+/// Stronger of the 10 m wind and gusts at `time`, interpolated between
+/// forecast hours.
+fn surface_wind(grid: &WeatherGrid, time: DateTime<Utc>) -> Option<f64> {
+  let (index, weight) = grid.time_bracket(time.timestamp() as f64);
+  let at = |i: usize| {
+    let sample = grid.surface.get(i)?;
+    [sample.wind_speed_10m, sample.wind_gusts_10m]
+      .into_iter()
+      .flatten()
+      .reduce(f64::max)
+  };
+  match (at(index), at(index + 1)) {
+    (Some(a), Some(b)) => Some(a + (b - a) * weight),
+    (a, b) => a.or(b),
+  }
+}
+
+fn validate(settings: &WindowSettings) -> Result<(), PredictionError> {
+  let weights = &settings.weights;
+  let weights = [
+    weights.distance,
+    weights.hazard,
+    weights.daylight,
+    weights.surface_wind,
+  ];
+  if weights.iter().any(|w| !w.is_finite() || *w < 0.0) || weights.iter().sum::<f64>() <= 0.0 {
+    return Err(PredictionError::InvalidParams(
+      "score weights must be non-negative and not all zero",
+    ));
+  }
+  if !(settings.max_distance > 0.0 && settings.max_surface_wind > 0.0) {
+    return Err(PredictionError::InvalidParams(
+      "maximum landing distance and surface wind must be positive",
+    ));
+  }
+  settings.hazards.iter().try_for_each(HazardZone::validate)
+}
+
+/// First and last launch time of the window.
+fn window_bounds(
+  params: &LaunchParams,
+  settings: &WindowSettings,
+) -> Result<(DateTime<Utc>, DateTime<Utc>), PredictionError> {
+  let time =
+    |iso: &String| parse_time(iso).ok_or_else(|| PredictionError::InvalidLaunchTime(iso.clone()));
+  let start = time(
+    settings
+      .window_start
+      .as_ref()
+      .unwrap_or(&params.launch_time),
+  )?;
+  let end = match &settings.window_end {
+    Some(end) => time(end)?,
+    None => start + Duration::days(DEFAULT_WINDOW_DAYS),
+  };
+  Ok((start, end))
+}
+
+/// Splits the runs into launch windows and ranks them.
+fn best_windows(runs: &[WindowRun], settings: &WindowSettings) -> Vec<LaunchWindow> {
+  let mut windows: Vec<LaunchWindow> = runs
+    .split(|run| run.score < settings.min_score || run.forecast_exceeded)
+    .filter(|runs| !runs.is_empty())
+    .map(|runs| {
+      let best = runs
+        .iter()
+        .max_by(|a, b| a.score.total_cmp(&b.score))
+        .expect("split yields non-empty runs");
+      LaunchWindow {
+        start: runs[0].launch_time.clone(),
+        end: runs[runs.len() - 1].launch_time.clone(),
+        best_launch_time: best.launch_time.clone(),
+        best_score: best.score,
+        mean_score: runs.iter().map(|run| run.score).sum::<f64>() / runs.len() as f64,
+      }
+    })
+    .collect();
+  windows.sort_by(|a, b| {
+    b.best_score
+      .total_cmp(&a.best_score)
+      .then(b.mean_score.total_cmp(&a.mean_score))
+  });
+  windows.truncate(settings.best_count);
+  windows
+}
+
+/// Predicts and scores every launch time in the window on `grid`, up to
+/// the end of the forecast.
+pub fn run_window_simulation(
+  params: &LaunchParams,
+  settings: &WindowSettings,
+  grid: &WeatherGrid,
+  terrain: &(dyn Terrain + Sync),
+) -> Result<WindowResult, PredictionError> {
+  validate(settings)?;
+  let forecast_end = grid
+    .time_span()
+    .and_then(|(_, end)| DateTime::from_timestamp(end as i64, 0))
+    .ok_or(WeatherError::NoForecast)?;
+  let (start, end) = window_bounds(params, settings)?;
+  if start > forecast_end {
+    return Err(PredictionError::InvalidParams(
+      "launch window starts after the end of the forecast",
+    ));
+  }
+  let end = end.min(forecast_end);
+
+  let runs = launch_times(start, end, settings.window_step)?
+    .into_par_iter()
+    .map(|launch_time| {
+      let launch = LaunchParams {
+        launch_time: launch_time.to_rfc3339_opts(SecondsFormat::Secs, true),
+        ..params.clone()
+      };
+      let prediction = run_prediction_simulation(&launch, grid, terrain)?;
+      let landing = prediction.landing_point;
+      let landing_distance = geodesy::distance(params.lat, params.lon, landing.lat, landing.lon);
+      let hazards: Vec<String> = settings
+        .hazards
+        .iter()
+        .filter(|zone| zone.contains(landing.lat, landing.lon))
+        .map(|zone| zone.name.clone())
+        .collect();
+      let landed_at = launch_time + Duration::milliseconds((landing.time * 1000.0) as i64);
+      let sun_elevation = sun_elevation(landing.lat, landing.lon, landed_at);
+      let surface_wind = surface_wind(grid, launch_time);
+
+      let scores = Scores {
+        distance: (1.0 - landing_distance / settings.max_distance).clamp(0.0, 1.0),
+        hazard: if hazards.is_empty() { 1.0 } else { 0.0 },
+        daylight: ((sun_elevation - NIGHT_SUN_ELEVATION) / -NIGHT_SUN_ELEVATION).clamp(0.0, 1.0),
+        surface_wind: surface_wind.map_or(1.0, |wind| {
+          (1.0 - wind / settings.max_surface_wind).clamp(0.0, 1.0)
+        }),
+      };
+      Ok(WindowRun {
+        launch_time: launch.launch_time,
+        score: scores.total(&settings.weights),
+        scores,
+        landing_point: landing,
+        landing_distance,
+        flight_duration: prediction.flight_duration,
+        hazards,
+        sun_elevation,
+        surface_wind,
+        forecast_exceeded: prediction.forecast_exceeded_at.is_some(),
+      })
+    })
+    .collect::<Result<Vec<_>, PredictionError>>()?;
+
+  let windows = best_windows(&runs, settings);
+  Ok(WindowResult {
+    runs,
+    windows,
+    forecast_end: forecast_end.to_rfc3339_opts(SecondsFormat::Secs, true),
+  })
+}
+
+/// Sweeps launch times on the launch site's forecast (or local GRIB2 files)
+/// and ranks the launch windows. The forecast is fetched through the end of
+/// the window plus the longest flight the engine simulates, as far as the
+/// GFS reaches.
+#[tauri::command]
+pub async fn optimize_launch_window(
+  cache: State<'_, ForecastCache>,
+  dem: State<'_, DemProvider>,
+  params: LaunchParams,
+  settings: WindowSettings,
+  grib_files: Option<Vec<String>>,
+  ground_elevation: Option<f64>,
+) -> Result<WindowResult, PredictionError> {
+  let grid = match grib_files {
+    Some(paths) => resolve_weather(&cache, &params, None, Some(paths)).await?,
+    None => {
+      let (start, end) = window_bounds(&params, &settings)?;
+      let last_landing = end + Duration::seconds(MAX_FLIGHT_TIME_S as i64);
+      weather::fetch_gfs_range_cached(&cache, params.lat, params.lon, start, last_landing).await?
+    }
+  };
+  let terrain = DemTerrain {
+    dem: dem.inner().clone(),
+    fallback: ground_elevation.unwrap_or(0.0),
+  };
+  tauri::async_runtime::spawn_blocking(move || {
+    run_window_simulation(&params, &settings, &grid, &terrain)
+  })
+  .await
+  .map_err(|e| PredictionError::Simulation(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn params() -> LaunchParams {
+    LaunchParams {
+      launch_time: "2025-07-07T00:00:00Z".into(),
+      launch_altitude: 0.0,
+      ..LaunchParams::test_default()
+    }
+  }
+
+  /// A westerly at every level that strengthens by 1 m/s an hour, with the
+  /// surface wind picking up through the day.
+  fn weather() -> WeatherGrid {
+    let mut grid = WeatherGrid::strengthening(0.0, 1.0, 270.0, 36);
+    for (hour, surface) in grid.surface.iter_mut().enumerate() {
+      surface.wind_speed_10m = Some(hour as f64 * 0.25);
+      surface.wind_gusts_10m = Some(hour as f64 * 0.5);
+    }
+    WeatherGrid {
+      latitude: 40.0,
+      longitude: -105.0,
+      ..grid
+    }
+  }
+
+  #[test]
+  fn scores_every_launch_time() {
+    let settings = WindowSettings {
+      window_end: Some("2025-07-07T23:00:00Z".into()),
+      weights: ScoreWeights {
+        daylight: 0.0,
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+    let result = run_window_simulation(&params(), &settings, &weather(), &0.0).unwrap();
+
+    assert_eq!(result.runs.len(), 24);
+    assert_eq!(result.runs[0].launch_time, "2025-07-07T00:00:00Z");
+    // Later launches drift further in stronger winds.
+    assert!(result
+      .runs
+      .windows(2)
+      .all(|w| w[1].landing_distance > w[0].landing_distance && w[1].score <= w[0].score));
+    // Gusts set the surface wind: 10 m/s at 20:00.
+    let evening = &result.runs[20];
+    assert_eq!(evening.surface_wind, Some(10.0));
+    assert_eq!(evening.scores.surface_wind, 0.0);
+
+    let best = &result.windows[0];
+    assert_eq!(best.start, "2025-07-07T00:00:00Z");
+    assert_eq!(best.best_launch_time, "2025-07-07T00:00:00Z");
+    assert!(result.windows.len() == 1 && best.best_score >= best.mean_score);
+  }
+
+  #[test]
+  fn stops_at_the_end_of_the_forecast() {
+    // A week-long window on a 36-hour forecast.
+    let settings = WindowSettings {
+      window_end: Some("2025-07-14T00:00:00Z".into()),
+      min_score: 0.0,
+      ..Default::default()
+    };
+    let result = run_window_simulation(&params(), &settings, &weather(), &0.0).unwrap();
+
+    assert_eq!(result.forecast_end, "2025-07-08T11:00:00Z");
+    assert_eq!(result.runs.len(), 36);
+    assert_eq!(result.runs[35].launch_time, result.forecast_end);
+    // The last launches outlast the forecast and are kept out of the windows.
+    assert!(!result.runs[0].forecast_exceeded && result.runs[35].forecast_exceeded);
+    let exceeded = result
+      .runs
+      .iter()
+      .position(|run| run.forecast_exceeded)
+      .unwrap();
+    assert!(result.runs[exceeded..]
+      .iter()
+      .all(|run| run.forecast_exceeded));
+    assert_eq!(result.windows.len(), 1);
+    assert_eq!(result.windows[0].end, result.runs[exceeded - 1].launch_time);
+
+    let late = WindowSettings {
+      window_start: Some("2025-07-09T00:00:00Z".into()),
+      ..settings
+    };
+    assert!(matches!(
+      run_window_simulation(&params(), &late, &weather(), &0.0),
+      Err(PredictionError::InvalidParams(_))
+    ));
+  }
+
+  #[test]
+  fn marks_landings_in_hazard_zones() {
+    let mut settings = WindowSettings {
+      window_start: Some("2025-07-07T06:00:00Z".into()),
+      window_end: Some("2025-07-07T06:00:00Z".into()),
+      ..Default::default()
+    };
+    let clear = run_window_simulation(&params(), &settings, &weather(), &0.0).unwrap();
+    let landing = clear.runs[0].landing_point;
+    assert!(landing.lon > -105.0);
+
+    settings.hazards = vec![
+      HazardZone {
+        name: "Reservoir".into(),
+        shape: HazardShape::Circle {
+          lat: landing.lat,
+          lon: landing.lon + 0.01,
+          radius: 2000.0,
+        },
+      },
+      HazardZone {
+        name: "Town".into(),
+        shape: HazardShape::Polygon {
+          vertices: vec![
+            [39.0, -106.0],
+            [39.0, -104.0],
+            [41.0, -104.0],
+            [41.0, -106.0],
+          ],
+        },
+      },
+      HazardZone {
+        name: "Elsewhere".into(),
+        shape: HazardShape::Polygon {
+          vertices: vec![[0.0, 179.0], [1.0, -179.0], [-1.0, -179.0]],
+        },
+      },
+    ];
+    let run = &run_window_simulation(&params(), &settings, &weather(), &0.0)
+      .unwrap()
+      .runs[0];
+    assert_eq!(run.hazards, ["Reservoir", "Town"]);
+    assert_eq!(run.scores.hazard, 0.0);
+    assert!(run.score < clear.runs[0].score);
+
+    // Polygons across the antimeridian.
+    assert!(settings.hazards[2].contains(0.0, 179.5));
+    assert!(settings.hazards[2].contains(0.0, -179.5));
+    assert!(!settings.hazards[2].contains(0.0, 178.5));
+  }
+
+  #[test]
+  fn follows_the_sun() {
+    let noon = parse_time("2025-06-21T12:00:00Z").unwrap();
+    // Overhead sun near the tropic at local noon, below the horizon at the
+    // antimeridian.
+    assert!((sun_elevation(23.44, 0.0, noon) - 90.0).abs() < 0.5);
+    assert!((sun_elevation(40.0, 0.0, noon) - 73.4).abs() < 0.5);
+    assert!(sun_elevation(40.0, 180.0, noon) < -20.0);
+    // Midnight sun in the Arctic.
+    let midnight = parse_time("2025-06-21T00:00:00Z").unwrap();
+    assert!(sun_elevation(75.0, 0.0, midnight) > 0.0);
+  }
+
+  #[test]
+  fn rejects_bad_settings() {
+    let no_weight = WindowSettings {
+      weights: ScoreWeights {
+        distance: 0.0,
+        hazard: 0.0,
+        daylight: 0.0,
+        surface_wind: 0.0,
+      },
+      ..Default::default()
+    };
+    let line = WindowSettings {
+      hazards: vec![HazardZone {
+        name: "Line".into(),
+        shape: HazardShape::Polygon {
+          vertices: vec![[0.0, 0.0], [1.0, 1.0]],
+        },
+      }],
+      ..Default::default()
+    };
+    for settings in [no_weight, line] {
+      assert!(matches!(
+        run_window_simulation(&params(), &settings, &weather(), &0.0),
+        Err(PredictionError::InvalidParams(_))
+      ));
+    }
+  }
+}
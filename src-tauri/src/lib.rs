@@ -1,12 +1,95 @@
+use tauri::Manager;
+
+mod ascent;
+mod atmosphere;
+mod calibration;
+mod catalog;
+mod descent;
+mod elevation;
+mod ensemble;
+mod gas_fill;
+mod geodesy;
+mod integrator;
+mod launch_window;
+mod mission;
+mod neck_lift;
+mod prediction;
+mod profile;
+mod reverse;
+mod tawhiri;
+mod weather;
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
   tauri::Builder::default()
-    .setup(|_app| {
+    .setup(|app| {
+      let data_dir = app.path().app_data_dir()?;
+      app.manage(weather::cache::ForecastCache::open(&data_dir)?);
+      app.manage(elevation::cache::ElevationCache::open(&data_dir)?);
+      app.manage(elevation::dem::DemProvider::default());
+      app.manage(mission::MissionStore::open(&data_dir)?);
+      app.manage(catalog::CatalogStore::open(&data_dir)?);
+      app.manage(calibration::FlightHistory::open(&data_dir)?);
+      app.manage(tawhiri::PredictionApi::default());
       Ok(())
     })
     .plugin(tauri_plugin_http::init())
     .plugin(tauri_plugin_geolocation::init())
     .plugin(tauri_plugin_shell::init())
+    .invoke_handler(tauri::generate_handler![
+      prediction::run_prediction,
+      ensemble::run_ensemble,
+      ensemble::run_forecast_ensemble,
+      reverse::reverse_prediction,
+      launch_window::optimize_launch_window,
+      tawhiri::start_prediction_api,
+      tawhiri::stop_prediction_api,
+      tawhiri::prediction_api_status,
+      mission::save_mission,
+      mission::load_mission,
+      mission::list_missions,
+      mission::delete_mission,
+      catalog::get_catalog,
+      catalog::list_balloons,
+      catalog::list_parachutes,
+      catalog::list_gases,
+      catalog::list_cylinders,
+      catalog::get_balloon,
+      catalog::get_parachute,
+      catalog::save_catalog_entry,
+      catalog::delete_catalog_entry,
+      neck_lift::solve_neck_lift,
+      calibration::record_flight,
+      calibration::list_flights,
+      calibration::delete_flight,
+      calibration::balloon_calibration,
+      gas_fill::plan_gas_fill,
+      gas_fill::start_gas_fill,
+      gas_fill::check_fill_step,
+      gas_fill::record_neck_lift,
+      ascent::ascent_profile,
+      atmosphere::atmosphere_at,
+      atmosphere::pressure_altitude,
+      weather::fetch_weather,
+      weather::fetch_ensemble_weather,
+      weather::list_cached_forecasts,
+      weather::purge_forecast_cache,
+      weather::prefetch_forecast,
+      weather::grib::load_grib_weather,
+      weather::grib::load_grib_ensemble,
+      elevation::set_dem_folder,
+      elevation::dem_status,
+      elevation::get_ground_elevation,
+      elevation::get_elevation_grid,
+      elevation::get_cached_elevation,
+      elevation::get_cached_elevations,
+      elevation::cache_elevations,
+      elevation::get_cached_elevation_grid,
+      elevation::cache_elevation_grid,
+      elevation::cleanup_elevation_cache,
+      elevation::clear_elevation_cache,
+      elevation::elevation_cache_stats,
+    ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
@@ -0,0 +1,237 @@
+//! Saved missions.
+//!
+//! A mission is a named set of launch parameters, including the balloon,
+//! parachute and flight profile, kept in an SQLite database in the app data
+//! directory so a planned flight can be reopened and predicted again. The
+//! launch-day gas fill record is saved with it.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::gas_fill::FillRecord;
+use crate::prediction::LaunchParams;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Mission {
+  /// Unique; saving under an existing name replaces that mission
+  pub name: String,
+  pub params: LaunchParams,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub notes: Option<String>,
+  /// Set when the mission is saved
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub updated_at: Option<DateTime<Utc>>,
+  /// Gas fill plan and the neck lifts measured on launch day
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub fill: Option<FillRecord>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MissionError {
+  #[error("mission store error: {0}")]
+  Store(String),
+  #[error("a mission needs a name")]
+  EmptyName,
+  #[error("no mission named {0:?}")]
+  NotFound(String),
+}
+
+impl Serialize for MissionError {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.to_string())
+  }
+}
+
+fn store_error(error: impl std::fmt::Display) -> MissionError {
+  MissionError::Store(error.to_string())
+}
+
+pub struct MissionStore {
+  conn: Mutex<Connection>,
+}
+
+impl MissionStore {
+  /// Opens (or creates) the mission database in `dir`.
+  pub fn open(dir: &Path) -> Result<Self, MissionError> {
+    std::fs::create_dir_all(dir).map_err(store_error)?;
+    Self::with_connection(Connection::open(dir.join("missions.sqlite")).map_err(store_error)?)
+  }
+
+  #[cfg(test)]
+  pub fn open_in_memory() -> Result<Self, MissionError> {
+    Self::with_connection(Connection::open_in_memory().map_err(store_error)?)
+  }
+
+  fn with_connection(conn: Connection) -> Result<Self, MissionError> {
+    conn
+      .execute_batch(
+        "CREATE TABLE IF NOT EXISTS missions (
+          name TEXT PRIMARY KEY,
+          updated_at INTEGER NOT NULL,
+          mission TEXT NOT NULL
+        );",
+      )
+      .map_err(store_error)?;
+    Ok(MissionStore {
+      conn: Mutex::new(conn),
+    })
+  }
+
+  fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+    self
+      .conn
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  /// Saves `mission`, replacing any mission of the same name.
+  pub fn save(&self, mission: Mission, now: DateTime<Utc>) -> Result<Mission, MissionError> {
+    let name = mission.name.trim().to_string();
+    if name.is_empty() {
+      return Err(MissionError::EmptyName);
+    }
+    let mission = Mission {
+      name,
+      updated_at: Some(now),
+      ..mission
+    };
+    let json = serde_json::to_string(&mission).map_err(store_error)?;
+    self
+      .conn()
+      .execute(
+        "INSERT OR REPLACE INTO missions (name, updated_at, mission) VALUES (?1, ?2, ?3)",
+        params![mission.name, now.timestamp(), json],
+      )
+      .map_err(store_error)?;
+    Ok(mission)
+  }
+
+  pub fn get(&self, name: &str) -> Result<Option<Mission>, MissionError> {
+    let json: Option<String> = self
+      .conn()
+      .query_row(
+        "SELECT mission FROM missions WHERE name = ?1",
+        params![name],
+        |row| row.get(0),
+      )
+      .optional()
+      .map_err(store_error)?;
+    json
+      .map(|m| serde_json::from_str(&m).map_err(store_error))
+      .transpose()
+  }
+
+  /// Lists saved missions, most recently saved first.
+  pub fn list(&self) -> Result<Vec<Mission>, MissionError> {
+    let conn = self.conn();
+    let mut statement = conn
+      .prepare("SELECT mission FROM missions ORDER BY updated_at DESC, name")
+      .map_err(store_error)?;
+    let rows = statement
+      .query_map([], |row| row.get::<_, String>(0))
+      .map_err(store_error)?;
+    rows
+      .map(|json| serde_json::from_str(&json.map_err(store_error)?).map_err(store_error))
+      .collect()
+  }
+
+  /// Removes a mission. Returns whether it existed.
+  pub fn delete(&self, name: &str) -> Result<bool, MissionError> {
+    let removed = self
+      .conn()
+      .execute("DELETE FROM missions WHERE name = ?1", params![name])
+      .map_err(store_error)?;
+    Ok(removed > 0)
+  }
+}
+
+#[tauri::command]
+pub fn save_mission(
+  store: State<'_, MissionStore>,
+  mission: Mission,
+) -> Result<Mission, MissionError> {
+  store.save(mission, Utc::now())
+}
+
+#[tauri::command]
+pub fn load_mission(store: State<'_, MissionStore>, name: String) -> Result<Mission, MissionError> {
+  store.get(&name)?.ok_or(MissionError::NotFound(name))
+}
+
+#[tauri::command]
+pub fn list_missions(store: State<'_, MissionStore>) -> Result<Vec<Mission>, MissionError> {
+  store.list()
+}
+
+#[tauri::command]
+pub fn delete_mission(store: State<'_, MissionStore>, name: String) -> Result<bool, MissionError> {
+  store.delete(&name)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::profile::{FlightProfile, Segment};
+  use crate::weather::parse_time;
+
+  fn mission(name: &str) -> Mission {
+    let params = serde_json::from_value(serde_json::json!({
+      "lat": 40.0,
+      "lon": -105.0,
+      "launchTime": "2025-07-07T12:00:00Z",
+      "launchAltitude": 1600.0,
+      "ascentRate": 5.0,
+      "burstAltitude": 30000.0,
+      "descentRate": 5.0,
+      "profile": {"segments": [
+        {"type": "constantRate", "altitude": 18000},
+        {"type": "floatUntilTime", "duration": 7200},
+        {"type": "terminalDescent"}
+      ]}
+    }))
+    .unwrap();
+    Mission {
+      name: name.into(),
+      params,
+      notes: None,
+      updated_at: None,
+      fill: None,
+    }
+  }
+
+  #[test]
+  fn saves_the_flight_profile_with_the_mission() {
+    let store = MissionStore::open_in_memory().unwrap();
+    let now = parse_time("2025-07-01T09:00:00Z").unwrap();
+
+    let saved = store.save(mission(" Float test "), now).unwrap();
+    assert_eq!(saved.name, "Float test");
+    assert_eq!(saved.updated_at, Some(now));
+
+    let loaded = store.get("Float test").unwrap().unwrap();
+    let profile: &FlightProfile = loaded.params.profile.as_ref().unwrap();
+    assert_eq!(profile.segments.len(), 3);
+    assert_eq!(profile.segments[2], Segment::TerminalDescent);
+
+    // Saving again under the same name replaces it; newest first.
+    let later = parse_time("2025-07-02T09:00:00Z").unwrap();
+    store.save(mission("Burst"), now).unwrap();
+    store.save(mission("Float test"), later).unwrap();
+    let names: Vec<String> = store.list().unwrap().into_iter().map(|m| m.name).collect();
+    assert_eq!(names, ["Float test", "Burst"]);
+
+    assert!(store.delete("Burst").unwrap());
+    assert!(!store.delete("Burst").unwrap());
+    assert!(store.get("Burst").unwrap().is_none());
+    assert!(matches!(
+      store.save(mission("  "), now),
+      Err(MissionError::EmptyName)
+    ));
+  }
+}
@@ -0,0 +1,563 @@
+//! Neck-lift solver.
+//!
+//! For a catalog balloon and gas, the neck lift alone sets how much gas goes
+//! in and so the burst altitude, while the split of that lift between the
+//! payload and free lift sets the ascent rate. Given any two of ascent rate,
+//! burst altitude, neck lift and payload mass, the other two follow: the
+//! burst altitude in closed form from the gas law, and the ascent rate by
+//! Brent's method on the [`AscentModel`]. Neck lift and burst altitude
+//! determine each other, so that pair cannot be solved.
+//!
+//! The ascent rate here is the mean from launch to burst, which is what
+//! sets the flight time. The balloon's drag coefficient and burst diameter
+//! come from its calibration against past flights once there is one.
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::ascent::{AscentModel, Balloon, BALLOON_DRAG_COEFFICIENT, MAX_BURST_ALTITUDE_M};
+use crate::atmosphere::state_at;
+use crate::calibration::{CalibrationError, FlightHistory};
+use crate::catalog::{BalloonSpec, CatalogError, CatalogStore, GasSpec};
+
+/// Fill error the sensitivity is reported for, kg.
+const FILL_ERROR_KG: f64 = 0.010;
+/// Neck lift and payload tolerance of the root finder, kg.
+const MASS_TOLERANCE_KG: f64 = 1e-5;
+/// Altitude steps when averaging the ascent rate.
+const RATE_STEPS: usize = 200;
+
+/// The known quantities; exactly two must be given.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiftTargets {
+  /// Mean ascent rate to burst, m/s
+  pub ascent_rate: Option<f64>,
+  /// m
+  pub burst_altitude: Option<f64>,
+  /// Lift measured at the neck, payload plus free lift, kg
+  pub neck_lift: Option<f64>,
+  /// Payload, parachute and rigging, kg
+  pub payload_mass: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Range {
+  pub min: f64,
+  pub max: f64,
+}
+
+/// What the balloon can do with the solved payload: from the least neck lift
+/// that still lifts it (and bursts below the model ceiling) to a fill that
+/// reaches the burst diameter at launch.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiftBounds {
+  /// kg
+  pub neck_lift: Range,
+  /// m/s
+  pub ascent_rate: Range,
+  /// m
+  pub burst_altitude: Range,
+}
+
+/// The flight with the neck lift off by `neck_lift_change`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FillError {
+  /// kg
+  pub neck_lift_change: f64,
+  /// m/s; `None` if the balloon no longer lifts the payload
+  pub ascent_rate: Option<f64>,
+  /// m; `None` above the model ceiling
+  pub burst_altitude: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiftSolution {
+  /// kg
+  pub neck_lift: f64,
+  /// kg
+  pub payload_mass: f64,
+  /// kg
+  pub free_lift: f64,
+  /// Mean to burst, m/s
+  pub ascent_rate: f64,
+  /// m/s
+  pub launch_ascent_rate: f64,
+  /// m
+  pub burst_altitude: f64,
+  /// The filled balloon, for the prediction's balloon model
+  pub balloon: Balloon,
+  pub bounds: LiftBounds,
+  /// With the neck lift under and over by 10 g
+  pub sensitivity: Vec<FillError>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LiftError {
+  #[error(transparent)]
+  Catalog(#[from] CatalogError),
+  #[error(transparent)]
+  Calibration(#[from] CalibrationError),
+  #[error("invalid lift targets: {0}")]
+  InvalidTargets(&'static str),
+  #[error("{0}")]
+  Infeasible(String),
+}
+
+impl Serialize for LiftError {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.to_string())
+  }
+}
+
+/// Brent's method: a root of `f` between `a` and `b`, where `f` changes
+/// sign.
+fn brent(f: impl Fn(f64) -> f64, mut a: f64, mut b: f64, tolerance: f64) -> Option<f64> {
+  let (mut fa, mut fb) = (f(a), f(b));
+  if fa * fb > 0.0 || fa.is_nan() || fb.is_nan() {
+    return None;
+  }
+  if fa.abs() < fb.abs() {
+    std::mem::swap(&mut a, &mut b);
+    std::mem::swap(&mut fa, &mut fb);
+  }
+  let (mut c, mut fc) = (a, fa);
+  let mut d = c;
+  let mut bisected = true;
+  for _ in 0..100 {
+    if fb == 0.0 || (b - a).abs() < tolerance {
+      return Some(b);
+    }
+    let mut s = if fa != fc && fb != fc {
+      // Inverse quadratic interpolation
+      a * fb * fc / ((fa - fb) * (fa - fc))
+        + b * fa * fc / ((fb - fa) * (fb - fc))
+        + c * fa * fb / ((fc - fa) * (fc - fb))
+    } else {
+      // Secant
+      b - fb * (b - a) / (fb - fa)
+    };
+    let quarter = (3.0 * a + b) / 4.0;
+    let step = if bisected {
+      (b - c).abs()
+    } else {
+      (c - d).abs()
+    };
+    if !(quarter.min(b) < s && s < quarter.max(b))
+      || (s - b).abs() >= step / 2.0
+      || step < tolerance
+    {
+      s = (a + b) / 2.0;
+      bisected = true;
+    } else {
+      bisected = false;
+    }
+    let fs = f(s);
+    d = c;
+    (c, fc) = (b, fb);
+    if fa * fs < 0.0 {
+      (b, fb) = (s, fs);
+    } else {
+      (a, fa) = (s, fs);
+    }
+    if fa.abs() < fb.abs() {
+      std::mem::swap(&mut a, &mut b);
+      std::mem::swap(&mut fa, &mut fb);
+    }
+  }
+  Some(b)
+}
+
+/// Mean ascent rate of `model` from `launch_altitude` up to `top`, m/s
+pub fn mean_ascent_rate(model: &AscentModel, launch_altitude: f64, top: f64) -> f64 {
+  let climb = top - launch_altitude;
+  if climb <= 0.0 {
+    return model.ascent_rate(launch_altitude);
+  }
+  let step = climb / RATE_STEPS as f64;
+  let time: f64 = (0..RATE_STEPS)
+    .map(|i| step / model.ascent_rate(launch_altitude + (i as f64 + 0.5) * step))
+    .sum();
+  climb / time
+}
+
+/// A catalog balloon filled with a catalog gas at the launch site.
+pub struct Fill<'a> {
+  pub spec: &'a BalloonSpec,
+  pub gas: &'a GasSpec,
+  pub launch_altitude: f64,
+}
+
+impl Fill<'_> {
+  pub fn balloon(&self, neck_lift: f64, payload_mass: f64) -> Balloon {
+    Balloon {
+      balloon_mass: self.spec.mass,
+      payload_mass,
+      free_lift: neck_lift - payload_mass,
+      gas: self.gas.gas,
+      gas_purity: self.gas.purity,
+      drag_coefficient: self
+        .spec
+        .drag_coefficient
+        .unwrap_or(BALLOON_DRAG_COEFFICIENT),
+      burst_diameter: Some(self.spec.burst_diameter),
+    }
+  }
+
+  /// Neck lift of a fill that reaches the burst diameter at `altitude`, kg
+  fn neck_lift_for_burst(&self, altitude: f64) -> f64 {
+    let burst_volume = std::f64::consts::PI / 6.0 * self.spec.burst_diameter.powi(3);
+    let (launch, burst) = (state_at(self.launch_altitude), state_at(altitude));
+    let launch_volume =
+      burst_volume * (launch.temperature / launch.pressure) / (burst.temperature / burst.pressure);
+    launch_volume * self.balloon(0.0, 0.0).lift_density(launch.density) - self.spec.mass
+  }
+
+  fn burst_altitude(&self, neck_lift: f64) -> Option<f64> {
+    AscentModel::new(self.balloon(neck_lift, 0.0), self.launch_altitude)
+      .burst_altitude(self.launch_altitude)
+  }
+
+  /// Mean ascent rate from launch to burst, m/s
+  fn ascent_rate(&self, neck_lift: f64, payload_mass: f64) -> Option<f64> {
+    if neck_lift < payload_mass {
+      return None;
+    }
+    let model = AscentModel::new(self.balloon(neck_lift, payload_mass), self.launch_altitude);
+    let burst = model.burst_altitude(self.launch_altitude)?;
+    Some(mean_ascent_rate(&model, self.launch_altitude, burst))
+  }
+
+  /// Lightest and heaviest neck lift for `payload_mass`, kg
+  fn neck_lift_range(&self, payload_mass: f64) -> Range {
+    // Nudged inside so the burst search finds the ceiling.
+    let lightest = self.neck_lift_for_burst(MAX_BURST_ALTITUDE_M) + MASS_TOLERANCE_KG;
+    Range {
+      min: lightest.max(payload_mass),
+      max: self.neck_lift_for_burst(self.launch_altitude),
+    }
+  }
+
+  /// Neck lift that climbs at `rate` with `payload_mass`, kg
+  fn neck_lift_for_rate(&self, rate: f64, payload_mass: f64) -> Result<f64, LiftError> {
+    let range = self.neck_lift_range(payload_mass);
+    let miss = |neck_lift: f64| self.ascent_rate(neck_lift, payload_mass).unwrap_or(0.0) - rate;
+    brent(miss, range.min, range.max, MASS_TOLERANCE_KG).ok_or_else(|| {
+      LiftError::Infeasible(format!(
+        "an ascent rate of {:.2} m/s is out of reach with a {:.3} kg payload ({:.2}-{:.2} m/s)",
+        rate,
+        payload_mass,
+        miss(range.min) + rate,
+        miss(range.max) + rate,
+      ))
+    })
+  }
+
+  /// Payload that climbs at `rate` on `neck_lift`, kg
+  fn payload_for_rate(&self, rate: f64, neck_lift: f64) -> Result<f64, LiftError> {
+    let miss = |payload_mass: f64| self.ascent_rate(neck_lift, payload_mass).unwrap_or(0.0) - rate;
+    brent(miss, 0.0, neck_lift, MASS_TOLERANCE_KG).ok_or_else(|| {
+      LiftError::Infeasible(format!(
+        "an ascent rate of {:.2} m/s is out of reach on {:.3} kg of neck lift (at most {:.2} m/s)",
+        rate,
+        neck_lift,
+        miss(0.0) + rate,
+      ))
+    })
+  }
+
+  /// Neck lift for a burst at `altitude`, kg
+  fn neck_lift_at_burst(&self, altitude: f64) -> Result<f64, LiftError> {
+    if altitude <= self.launch_altitude || altitude > MAX_BURST_ALTITUDE_M {
+      return Err(LiftError::Infeasible(format!(
+        "the burst altitude must be between the launch altitude and {:.0} m",
+        MAX_BURST_ALTITUDE_M
+      )));
+    }
+    Ok(self.neck_lift_for_burst(altitude))
+  }
+
+  fn solution(&self, neck_lift: f64, payload_mass: f64) -> Result<LiftSolution, LiftError> {
+    let range = self.neck_lift_range(payload_mass);
+    if neck_lift <= payload_mass || neck_lift > range.max || neck_lift < range.min {
+      return Err(LiftError::Infeasible(format!(
+        "{:.3} kg of neck lift is outside what the balloon can fly with a {:.3} kg payload \
+         ({:.3}-{:.3} kg)",
+        neck_lift, payload_mass, range.min, range.max
+      )));
+    }
+    let infeasible =
+      || LiftError::Infeasible("the balloon does not burst below the model ceiling".into());
+    let balloon = self.balloon(neck_lift, payload_mass);
+    let model = AscentModel::new(balloon, self.launch_altitude);
+    let bounds = LiftBounds {
+      neck_lift: range,
+      ascent_rate: Range {
+        min: self
+          .ascent_rate(range.min, payload_mass)
+          .ok_or_else(infeasible)?,
+        max: self
+          .ascent_rate(range.max, payload_mass)
+          .ok_or_else(infeasible)?,
+      },
+      burst_altitude: Range {
+        min: self.launch_altitude,
+        max: self.burst_altitude(range.min).ok_or_else(infeasible)?,
+      },
+    };
+    let sensitivity = [-FILL_ERROR_KG, FILL_ERROR_KG]
+      .into_iter()
+      .map(|change| FillError {
+        neck_lift_change: change,
+        ascent_rate: self
+          .ascent_rate(neck_lift + change, payload_mass)
+          .filter(|_| neck_lift + change > payload_mass),
+        burst_altitude: self.burst_altitude(neck_lift + change),
+      })
+      .collect();
+    Ok(LiftSolution {
+      neck_lift,
+      payload_mass,
+      free_lift: neck_lift - payload_mass,
+      ascent_rate: self
+        .ascent_rate(neck_lift, payload_mass)
+        .ok_or_else(infeasible)?,
+      launch_ascent_rate: model.ascent_rate(self.launch_altitude),
+      burst_altitude: self.burst_altitude(neck_lift).ok_or_else(infeasible)?,
+      balloon,
+      bounds,
+      sensitivity,
+    })
+  }
+}
+
+/// Solves for the two quantities `targets` leaves out.
+pub fn solve(
+  spec: &BalloonSpec,
+  gas: &GasSpec,
+  launch_altitude: f64,
+  targets: &LiftTargets,
+) -> Result<LiftSolution, LiftError> {
+  let given = [
+    targets.ascent_rate,
+    targets.burst_altitude,
+    targets.neck_lift,
+    targets.payload_mass,
+  ];
+  if given.iter().flatten().count() != 2 {
+    return Err(LiftError::InvalidTargets(
+      "give exactly two of ascent rate, burst altitude, neck lift and payload mass",
+    ));
+  }
+  if given.iter().flatten().any(|v| !v.is_finite() || *v < 0.0)
+    || targets.ascent_rate.is_some_and(|rate| rate <= 0.0)
+    || !launch_altitude.is_finite()
+  {
+    return Err(LiftError::InvalidTargets(
+      "targets must be finite, the ascent rate positive and masses non-negative",
+    ));
+  }
+
+  let fill = Fill {
+    spec,
+    gas,
+    launch_altitude,
+  };
+  let (neck_lift, payload_mass) = match *targets {
+    LiftTargets {
+      neck_lift: Some(neck_lift),
+      payload_mass: Some(payload_mass),
+      ..
+    } => (neck_lift, payload_mass),
+    LiftTargets {
+      burst_altitude: Some(burst),
+      payload_mass: Some(payload_mass),
+      ..
+    } => (fill.neck_lift_at_burst(burst)?, payload_mass),
+    LiftTargets {
+      ascent_rate: Some(rate),
+      payload_mass: Some(payload_mass),
+      ..
+    } => (fill.neck_lift_for_rate(rate, payload_mass)?, payload_mass),
+    LiftTargets {
+      ascent_rate: Some(rate),
+      neck_lift: Some(neck_lift),
+      ..
+    } => (neck_lift, fill.payload_for_rate(rate, neck_lift)?),
+    LiftTargets {
+      ascent_rate: Some(rate),
+      burst_altitude: Some(burst),
+      ..
+    } => {
+      let neck_lift = fill.neck_lift_at_burst(burst)?;
+      (neck_lift, fill.payload_for_rate(rate, neck_lift)?)
+    }
+    _ => {
+      return Err(LiftError::InvalidTargets(
+        "the neck lift fixes the burst altitude; give the ascent rate or payload mass with it",
+      ))
+    }
+  };
+  fill.solution(neck_lift, payload_mass)
+}
+
+#[tauri::command]
+pub fn solve_neck_lift(
+  catalog: State<'_, CatalogStore>,
+  history: State<'_, FlightHistory>,
+  balloon: String,
+  gas: String,
+  launch_altitude: f64,
+  targets: LiftTargets,
+) -> Result<LiftSolution, LiftError> {
+  solve(
+    &history.calibrated_balloon(&catalog, &balloon)?,
+    &catalog.gas(&gas)?,
+    launch_altitude,
+    &targets,
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn specs() -> (BalloonSpec, GasSpec) {
+    let catalog = CatalogStore::in_memory().unwrap();
+    (
+      catalog.balloon("totex-ta-1200").unwrap(),
+      catalog.gas("helium").unwrap(),
+    )
+  }
+
+  fn targets() -> LiftTargets {
+    LiftTargets::default()
+  }
+
+  #[test]
+  fn solves_every_solvable_pair() {
+    let (balloon, gas) = specs();
+    let forward = solve(
+      &balloon,
+      &gas,
+      100.0,
+      &LiftTargets {
+        neck_lift: Some(2.5),
+        payload_mass: Some(1.0),
+        ..targets()
+      },
+    )
+    .unwrap();
+    assert!((forward.free_lift - 1.5).abs() < 1e-12);
+    assert!(forward.burst_altitude > 25000.0 && forward.burst_altitude < 40000.0);
+    // The climb speeds up as the balloon grows.
+    assert!(forward.ascent_rate > forward.launch_ascent_rate);
+
+    let rate = Some(forward.ascent_rate);
+    let burst = Some(forward.burst_altitude);
+    let pairs = [
+      LiftTargets {
+        burst_altitude: burst,
+        payload_mass: Some(1.0),
+        ..targets()
+      },
+      LiftTargets {
+        ascent_rate: rate,
+        payload_mass: Some(1.0),
+        ..targets()
+      },
+      LiftTargets {
+        ascent_rate: rate,
+        neck_lift: Some(2.5),
+        ..targets()
+      },
+      LiftTargets {
+        ascent_rate: rate,
+        burst_altitude: burst,
+        ..targets()
+      },
+    ];
+    for pair in pairs {
+      let solved = solve(&balloon, &gas, 100.0, &pair).unwrap();
+      // Within the 1 m burst search and the root finder's tolerance.
+      assert!((solved.neck_lift - 2.5).abs() < 1e-3, "{:?}", solved);
+      assert!((solved.payload_mass - 1.0).abs() < 1e-3, "{:?}", solved);
+      assert!((solved.ascent_rate - forward.ascent_rate).abs() < 1e-3);
+    }
+  }
+
+  #[test]
+  fn reports_bounds_and_fill_sensitivity() {
+    let (balloon, gas) = specs();
+    let solution = solve(
+      &balloon,
+      &gas,
+      0.0,
+      &LiftTargets {
+        ascent_rate: Some(5.0),
+        payload_mass: Some(1.0),
+        ..targets()
+      },
+    )
+    .unwrap();
+    let bounds = solution.bounds;
+    assert!(bounds.neck_lift.min <= 1.0 + 1e-3 && bounds.neck_lift.max > solution.neck_lift);
+    assert!(bounds.ascent_rate.min < 5.0 && bounds.ascent_rate.max > 5.0);
+    assert!(bounds.burst_altitude.max > solution.burst_altitude);
+
+    // 10 g more lift climbs faster and bursts lower.
+    let [under, over] = [solution.sensitivity[0], solution.sensitivity[1]];
+    assert_eq!(over.neck_lift_change, 0.01);
+    assert!(under.ascent_rate.unwrap() < 5.0 && over.ascent_rate.unwrap() > 5.0);
+    assert!(under.burst_altitude.unwrap() > solution.burst_altitude);
+    assert!(over.burst_altitude.unwrap() < solution.burst_altitude);
+
+    let unreachable = LiftTargets {
+      ascent_rate: Some(bounds.ascent_rate.max + 1.0),
+      payload_mass: Some(1.0),
+      ..targets()
+    };
+    assert!(matches!(
+      solve(&balloon, &gas, 0.0, &unreachable),
+      Err(LiftError::Infeasible(_))
+    ));
+  }
+
+  #[test]
+  fn needs_exactly_two_independent_targets() {
+    let (balloon, gas) = specs();
+    let invalid = [
+      LiftTargets {
+        ascent_rate: Some(5.0),
+        ..targets()
+      },
+      LiftTargets {
+        ascent_rate: Some(5.0),
+        neck_lift: Some(2.0),
+        payload_mass: Some(1.0),
+        ..targets()
+      },
+      LiftTargets {
+        burst_altitude: Some(30000.0),
+        neck_lift: Some(2.0),
+        ..targets()
+      },
+    ];
+    for targets in invalid {
+      assert!(matches!(
+        solve(&balloon, &gas, 0.0, &targets),
+        Err(LiftError::InvalidTargets(_))
+      ));
+    }
+  }
+
+  #[test]
+  fn brent_finds_roots() {
+    let root = brent(|x| x * x * x - 2.0 * x - 5.0, 2.0, 3.0, 1e-12).unwrap();
+    assert!((root - 2.0945514815423265).abs() < 1e-9);
+    assert_eq!(brent(|x| x * x + 1.0, -1.0, 1.0, 1e-9), None);
+  }
+}
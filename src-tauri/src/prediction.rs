@@ -0,0 +1,1506 @@
+//! Native trajectory engine.
+//!
+//! This is the Rust counterpart of `runPredictionSimulation` in
+//! `src/services/predictionService.ts`. The serde types mirror
+//! `src/types/index.ts` (camelCase on the wire), so the frontend can hand the
+//! same `LaunchParams` and Open-Meteo `WeatherData` it already has to the
+//! `run_prediction` command and get a `PredictionResult` back. Without
+//! weather data the command fetches the forecast itself, and then refines
+//! the trajectory on a [`WeatherField`] of columns along the flight path.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::atmosphere::altitude_to_pressure;
+use tauri::State;
+
+use crate::ascent::{AscentModel, Balloon, ExpansionPoint};
+use crate::descent::{descent_rate, Parachute};
+use crate::geodesy;
+use crate::integrator::{Integrator, StateVector};
+use crate::profile::{FlightProfile, Segment};
+
+use crate::elevation::dem::DemProvider;
+use crate::elevation::{DemTerrain, Terrain};
+use crate::weather::cache::ForecastCache;
+use crate::weather::field::{FieldLayout, WeatherField};
+use crate::weather::grib;
+use crate::weather::open_meteo::WeatherData;
+use crate::weather::{self, parse_time, wind_from_uv, wind_to_uv, WeatherError, WeatherGrid};
+
+pub const EARTH_RADIUS_M: f64 = 6371000.0;
+pub const TIME_STEP_S: f64 = 60.0;
+
+/// Upper bound on simulated flight time, so bad inputs cannot spin forever.
+pub const MAX_FLIGHT_TIME_S: f64 = 7.0 * 24.0 * 3600.0;
+/// Refinement steps and height tolerance when locating the terrain crossing
+/// within a descent step.
+const TERRAIN_ITERATIONS: usize = 8;
+const TERRAIN_TOLERANCE_M: f64 = 0.5;
+/// Upper bound on launch times in one sweep, a week of hourly launches.
+const MAX_LAUNCH_TIMES: usize = 169;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchParams {
+  pub lat: f64,
+  pub lon: f64,
+  /// ISO format string
+  pub launch_time: String,
+  /// meters
+  pub launch_altitude: f64,
+  /// m/s
+  pub ascent_rate: f64,
+  /// meters
+  pub burst_altitude: f64,
+  /// When given, the ascent rate comes from the balloon model and
+  /// `ascent_rate` is not used; so does the burst altitude if the balloon
+  /// has a burst diameter.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub balloon: Option<Balloon>,
+  /// Sink rate at sea level, m/s; faster in thinner air
+  pub descent_rate: f64,
+  /// When given, the sink rate comes from the drag model and
+  /// `descent_rate` is not used.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub parachute: Option<Parachute>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub tracking_callsign: Option<String>,
+  #[serde(default)]
+  pub integration: IntegrationSettings,
+  /// When given, the balloon levels off at the float altitude instead of
+  /// climbing to burst, and is cut down at the end of the float.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub float: Option<FloatProfile>,
+  /// Segments to fly instead of the standard or float flight
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub profile: Option<FlightProfile>,
+}
+
+/// Numerical settings for the trajectory integration.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IntegrationSettings {
+  pub integrator: Integrator,
+  /// Step for Euler and RK4, and the first step RK45 tries, seconds
+  pub time_step: f64,
+  /// Largest local position error RK45 accepts per step, meters
+  pub tolerance: f64,
+  /// Spacing of the returned path, seconds. The burst and landing points are
+  /// always included.
+  pub output_interval: f64,
+}
+
+impl Default for IntegrationSettings {
+  fn default() -> Self {
+    IntegrationSettings {
+      integrator: Integrator::default(),
+      time_step: TIME_STEP_S,
+      tolerance: 0.1,
+      output_interval: TIME_STEP_S,
+    }
+  }
+}
+
+/// A float at constant altitude between the ascent and the cutdown, for
+/// zero-pressure and valved latex balloons. The float ends after `duration`
+/// or at `end_time`, whichever is given.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FloatProfile {
+  /// meters
+  pub altitude: f64,
+  /// Seconds at float, counted from reaching the float altitude
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub duration: Option<f64>,
+  /// ISO time of the cutdown
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub end_time: Option<String>,
+  /// Vertical speed while floating, m/s: negative for a slow leak, positive
+  /// for a balloon still creeping up
+  #[serde(default)]
+  pub leak_rate: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FlightPoint {
+  /// seconds from launch
+  pub time: f64,
+  pub lat: f64,
+  pub lon: f64,
+  /// meters
+  pub altitude: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PredictionResult {
+  pub path: Vec<FlightPoint>,
+  pub launch_point: FlightPoint,
+  /// Top of the flight, where the descent starts: the burst, or the cutdown
+  /// of a float
+  pub burst_point: FlightPoint,
+  pub landing_point: FlightPoint,
+  /// seconds
+  pub total_time: f64,
+  pub max_altitude: f64,
+  /// meters along the ground track
+  pub distance: f64,
+  /// seconds
+  pub flight_duration: f64,
+  /// Seconds from launch at which the flight ran past the last forecast
+  /// hour; from then on it drifts on that hour's winds.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub forecast_exceeded_at: Option<f64>,
+  /// Balloon radius and ascent rate from launch to burst, when the ascent
+  /// follows the balloon model
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub expansion_curve: Option<Vec<ExpansionPoint>>,
+  /// Where a float flight reached its float altitude
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub float_point: Option<FlightPoint>,
+  /// Where a float flight was cut down; `None` if a leak brought it to the
+  /// ground first
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cutdown_point: Option<FlightPoint>,
+  /// Whether the winds came from a field of forecast columns along the
+  /// track; otherwise the launch site's column was used throughout
+  #[serde(default)]
+  pub wind_field: bool,
+  /// Why the wind field could not be assembled, when the prediction fell
+  /// back to the launch site's column
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub wind_field_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wind {
+  /// m/s
+  pub speed: f64,
+  /// Meteorological direction the wind blows from, degrees.
+  pub direction: f64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PredictionError {
+  #[error("invalid launch time: {0}")]
+  InvalidLaunchTime(String),
+  #[error("invalid launch parameters: {0}")]
+  InvalidParams(&'static str),
+  #[error(transparent)]
+  Weather(#[from] WeatherError),
+  #[error("prediction failed: {0}")]
+  Simulation(String),
+}
+
+impl Serialize for PredictionError {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.to_string())
+  }
+}
+
+/// Winds the trajectory is integrated through.
+pub trait WindSource {
+  /// Wind at a position and `altitude` (meters) at Unix time `timestamp`
+  /// (seconds).
+  fn wind_at(&self, lat: f64, lon: f64, altitude: f64, timestamp: f64) -> Wind;
+
+  /// First and last forecast hours as Unix timestamps; `None` when there is
+  /// no forecast to look winds up in.
+  fn time_span(&self) -> Option<(f64, f64)>;
+}
+
+/// Blends winds through their u/v components, so opposing directions cancel
+/// instead of averaging to a crosswind.
+fn blend_winds(winds: impl IntoIterator<Item = (Wind, f64)>) -> Wind {
+  let (mut u, mut v) = (0.0, 0.0);
+  for (wind, weight) in winds {
+    let (wu, wv) = wind_to_uv(wind.speed, wind.direction);
+    u += wu * weight;
+    v += wv * weight;
+  }
+  let (speed, direction) = wind_from_uv(u, v);
+  Wind { speed, direction }
+}
+
+/// Interpolates wind speed and direction between the pressure levels
+/// bracketing `current_pressure` (hPa) at one forecast hour.
+fn level_wind(grid: &WeatherGrid, time_index: usize, current_pressure: f64) -> Wind {
+  let mut upper: Option<(f64, Wind)> = None;
+  let mut lower: Option<(f64, Wind)> = None;
+
+  for (level_index, &level) in grid.levels.iter().enumerate() {
+    let Some(sample) = grid.sample(time_index, level_index) else {
+      continue;
+    };
+    let p = level as f64;
+    let wind = Wind {
+      speed: sample.wind_speed,
+      direction: sample.wind_direction,
+    };
+
+    if p >= current_pressure && lower.is_none_or(|(lp, _)| p < lp) {
+      lower = Some((p, wind));
+    }
+    if p <= current_pressure && upper.is_none_or(|(up, _)| p > up) {
+      upper = Some((p, wind));
+    }
+  }
+
+  match (upper, lower) {
+    (None, None) => Wind {
+      speed: 0.0,
+      direction: 0.0,
+    },
+    (None, Some((_, wind))) | (Some((_, wind)), None) => wind,
+    (Some((up, upper)), Some((lp, lower))) => {
+      if up == lp {
+        return lower;
+      }
+      let weight = (current_pressure - up) / (lp - up);
+      let speed = upper.speed * (1.0 - weight) + lower.speed * weight;
+
+      let mut dir_diff = lower.direction - upper.direction;
+      if dir_diff > 180.0 {
+        dir_diff -= 360.0;
+      }
+      if dir_diff < -180.0 {
+        dir_diff += 360.0;
+      }
+      let direction = (upper.direction + dir_diff * weight + 360.0) % 360.0;
+
+      Wind { speed, direction }
+    }
+  }
+}
+
+/// A single forecast column: the same winds everywhere.
+impl WindSource for WeatherGrid {
+  /// Interpolates between the pressure levels bracketing `altitude` and
+  /// then linearly between the forecast hours bracketing `timestamp`.
+  /// Outside the forecast the nearest hour is used.
+  fn wind_at(&self, _lat: f64, _lon: f64, altitude: f64, timestamp: f64) -> Wind {
+    let pressure = altitude_to_pressure(altitude);
+    let (index, weight) = self.time_bracket(timestamp);
+    if weight == 0.0 {
+      return level_wind(self, index, pressure);
+    }
+    blend_winds([
+      (level_wind(self, index, pressure), 1.0 - weight),
+      (level_wind(self, index + 1, pressure), weight),
+    ])
+  }
+
+  fn time_span(&self) -> Option<(f64, f64)> {
+    WeatherGrid::time_span(self)
+  }
+}
+
+/// Blends the surrounding columns' winds bilinearly.
+impl WindSource for WeatherField {
+  fn wind_at(&self, lat: f64, lon: f64, altitude: f64, timestamp: f64) -> Wind {
+    blend_winds(
+      self
+        .neighbours(lat, lon)
+        .into_iter()
+        .map(|(column, weight)| (column.wind_at(lat, lon, altitude, timestamp), weight)),
+    )
+  }
+
+  /// The hours every column covers.
+  fn time_span(&self) -> Option<(f64, f64)> {
+    self.columns.iter().try_fold(
+      (f64::NEG_INFINITY, f64::INFINITY),
+      |(start, end), column| {
+        let (s, e) = WeatherGrid::time_span(column)?;
+        Some((start.max(s), end.min(e)))
+      },
+    )
+  }
+}
+
+/// Where the local state `y` (east m, north m, altitude m) of a step starting
+/// at `origin` puts the balloon, and how many degrees the local frame there
+/// is turned from the one at `origin`.
+pub fn displaced(origin: (f64, f64), y: &StateVector) -> (f64, f64, f64) {
+  let distance = y[0].hypot(y[1]);
+  if distance == 0.0 {
+    return (origin.0, origin.1, 0.0);
+  }
+  let bearing = y[0].atan2(y[1]).to_degrees();
+  let end = geodesy::direct(origin.0, origin.1, bearing, distance);
+  (end.lat, end.lon, end.final_bearing - bearing)
+}
+
+/// Rate of change of the local state for a balloon carried by `wind` while
+/// climbing at `climb` m/s. The wind is given in the frame where it blows,
+/// turned by `rotation` degrees from the step's frame.
+pub fn velocity(wind: Wind, rotation: f64, climb: f64) -> StateVector {
+  // `wind_to_uv` gives the velocity of the air itself, which the balloon shares.
+  let (east, north) = wind_to_uv(wind.speed, wind.direction - rotation);
+  [east, north, climb]
+}
+
+/// Horizontal or vertical separation of two local states, whichever is
+/// larger, m.
+fn position_error(a: &StateVector, b: &StateVector) -> f64 {
+  (a[0] - b[0]).hypot(a[1] - b[1]).max((a[2] - b[2]).abs())
+}
+
+/// Samples `trajectory` every `interval` seconds by interpolation,
+/// keeping `burst` and the final point exactly.
+fn resample(trajectory: &[FlightPoint], interval: f64, burst: FlightPoint) -> Vec<FlightPoint> {
+  let Some(&last) = trajectory.last() else {
+    return Vec::new();
+  };
+  let mut path = Vec::new();
+  let mut segment = 0;
+  let mut burst_pending = true;
+  for k in 0.. {
+    let time = k as f64 * interval;
+    if time >= last.time {
+      break;
+    }
+    if burst_pending && burst.time <= time {
+      if burst.time < time {
+        path.push(burst);
+      }
+      burst_pending = false;
+    }
+    while trajectory[segment + 1].time < time {
+      segment += 1;
+    }
+    let (a, b) = (trajectory[segment], trajectory[segment + 1]);
+    let f = if b.time > a.time {
+      (time - a.time) / (b.time - a.time)
+    } else {
+      0.0
+    };
+    path.push(between(a, b, f));
+  }
+  if burst_pending && burst.time < last.time {
+    path.push(burst);
+  }
+  path.push(last);
+  path
+}
+
+/// The point a fraction `f` of the way from `a` to `b`, along the geodesic.
+fn between(a: FlightPoint, b: FlightPoint, f: f64) -> FlightPoint {
+  let (lat, lon) = geodesy::intermediate(a.lat, a.lon, b.lat, b.lon, f);
+  FlightPoint {
+    time: a.time + (b.time - a.time) * f,
+    lat,
+    lon,
+    altitude: a.altitude + (b.altitude - a.altitude) * f,
+  }
+}
+
+fn validate(params: &LaunchParams) -> Result<DateTime<Utc>, PredictionError> {
+  if !(-90.0..=90.0).contains(&params.lat) || !(-180.0..=180.0).contains(&params.lon) {
+    return Err(PredictionError::InvalidParams(
+      "launch coordinates are out of range",
+    ));
+  }
+  if !params.ascent_rate.is_finite() || params.ascent_rate <= 0.0 {
+    return Err(PredictionError::InvalidParams(
+      "ascent rate must be positive",
+    ));
+  }
+  if !params.descent_rate.is_finite() || params.descent_rate <= 0.0 {
+    return Err(PredictionError::InvalidParams(
+      "descent rate must be positive",
+    ));
+  }
+  if params.balloon.is_some_and(|b| !b.is_valid()) {
+    return Err(PredictionError::InvalidParams(
+      "balloon masses, free lift, drag coefficient and burst diameter must be positive",
+    ));
+  }
+  if params.parachute.is_some_and(|p| !p.is_valid()) {
+    return Err(PredictionError::InvalidParams(
+      "parachute diameter, drag coefficient and payload mass must be positive",
+    ));
+  }
+  let integration = &params.integration;
+  if ![
+    integration.time_step,
+    integration.tolerance,
+    integration.output_interval,
+  ]
+  .iter()
+  .all(|v| v.is_finite() && *v > 0.0)
+  {
+    return Err(PredictionError::InvalidParams(
+      "time step, tolerance and output interval must be positive",
+    ));
+  }
+  if !params.burst_altitude.is_finite() || !params.launch_altitude.is_finite() {
+    return Err(PredictionError::InvalidParams("altitudes must be finite"));
+  }
+  let launch_time = parse_time(&params.launch_time)
+    .ok_or_else(|| PredictionError::InvalidLaunchTime(params.launch_time.clone()))?;
+  if params.float.is_some() && params.profile.is_some() {
+    return Err(PredictionError::InvalidParams(
+      "give either a float or a flight profile, not both",
+    ));
+  }
+  if let Some(profile) = &params.profile {
+    profile.validate().map_err(PredictionError::InvalidParams)?;
+    for segment in &profile.segments {
+      if let Segment::FloatUntilTime {
+        end_time: Some(end_time),
+        ..
+      } = segment
+      {
+        parse_time(end_time).ok_or_else(|| PredictionError::InvalidLaunchTime(end_time.clone()))?;
+      }
+    }
+  }
+  if let Some(float) = &params.float {
+    if !float.altitude.is_finite() || float.altitude <= params.launch_altitude {
+      return Err(PredictionError::InvalidParams(
+        "float altitude must be above the launch site",
+      ));
+    }
+    if !float.leak_rate.is_finite() {
+      return Err(PredictionError::InvalidParams("leak rate must be finite"));
+    }
+    match (float.duration, &float.end_time) {
+      (Some(duration), None) if duration.is_finite() && duration >= 0.0 => {}
+      (None, Some(end_time)) => {
+        parse_time(end_time).ok_or_else(|| PredictionError::InvalidLaunchTime(end_time.clone()))?;
+      }
+      _ => {
+        return Err(PredictionError::InvalidParams(
+          "a float needs either a non-negative duration or an end time",
+        ))
+      }
+    }
+  }
+  Ok(launch_time)
+}
+
+/// Launch times every `step` seconds from `start` to `end` inclusive, for
+/// the commands that sweep a launch window.
+pub fn launch_times(
+  start: DateTime<Utc>,
+  end: DateTime<Utc>,
+  step: f64,
+) -> Result<Vec<DateTime<Utc>>, PredictionError> {
+  if !step.is_finite() || step < 1.0 {
+    return Err(PredictionError::InvalidParams(
+      "launch window step must be at least a second",
+    ));
+  }
+  if end < start {
+    return Err(PredictionError::InvalidParams(
+      "launch window ends before it starts",
+    ));
+  }
+  let count = ((end - start).num_seconds() as f64 / step).floor() as usize + 1;
+  if count > MAX_LAUNCH_TIMES {
+    return Err(PredictionError::InvalidParams(
+      "too many launch times in the window; use a longer step",
+    ));
+  }
+  let step = chrono::Duration::milliseconds((step * 1000.0).round() as i64);
+  Ok((0..count as i32).map(|i| start + step * i).collect())
+}
+
+/// Finds where a descent step from `start` to `end` meets the terrain,
+/// given the height above terrain at both ends (`above_start > 0`,
+/// `above_end <= 0`). Positions within the step are interpolated along the
+/// geodesic, and the crossing is refined by regula falsi against the terrain
+/// sampled there.
+fn terrain_crossing(
+  start: FlightPoint,
+  end: FlightPoint,
+  above_start: f64,
+  above_end: f64,
+  terrain: &dyn Terrain,
+) -> FlightPoint {
+  let at = |f: f64| between(start, end, f);
+
+  let (mut low, mut high) = ((0.0, above_start), (1.0, above_end));
+  let mut f = 1.0;
+  for _ in 0..TERRAIN_ITERATIONS {
+    f = low.0 + (high.0 - low.0) * low.1 / (low.1 - high.1);
+    let point = at(f);
+    let above = point.altitude - terrain.elevation(point.lat, point.lon);
+    if above.abs() < TERRAIN_TOLERANCE_M {
+      break;
+    }
+    if above > 0.0 {
+      low = (f, above);
+    } else {
+      high = (f, above);
+    }
+  }
+
+  let point = at(f);
+  FlightPoint {
+    altitude: terrain.elevation(point.lat, point.lon),
+    ..point
+  }
+}
+
+/// What drives the vertical motion during a step.
+#[derive(Debug, Clone, Copy)]
+enum Phase {
+  /// The launch ascent rate or balloon model
+  Ascent,
+  /// A fixed vertical speed, m/s
+  Rate(f64),
+  /// The descent rate or parachute model
+  Descent,
+}
+
+/// Where a segment ends within a step.
+#[derive(Debug, Clone, Copy)]
+enum Limit {
+  None,
+  /// Target altitude, m
+  Altitude(f64),
+  /// Distance from the launch site, m
+  Distance(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+  Flying,
+  /// The step was cut short at the segment's limit.
+  Reached,
+  Landed,
+}
+
+/// Simulates the flight by running the segments of its profile in order
+/// (see [`FlightProfile`]) with the integrator in `params.integration`:
+/// `params.profile` when given, else the float shorthand, else ascent to
+/// burst and descent. The ascent rate follows the balloon model when there
+/// is one (see [`AscentModel`]), and the descent speeds up with altitude
+/// (see [`descent_rate`]). A segment's last step is shortened to end at its
+/// target altitude, time or distance. Terrain is sampled at every step that
+/// does not climb, and the landing is placed where the trajectory meets it.
+pub fn run_prediction_simulation(
+  params: &LaunchParams,
+  weather: &(impl WindSource + ?Sized),
+  terrain: &dyn Terrain,
+) -> Result<PredictionResult, PredictionError> {
+  let launch_time = validate(params)?;
+  let Some((_, forecast_end)) = weather.time_span() else {
+    return Err(WeatherError::NoForecast.into());
+  };
+  let launch_timestamp = launch_time.timestamp() as f64;
+  let settings = params.integration;
+  let integrator = settings.integrator;
+
+  let balloon = params
+    .balloon
+    .map(|balloon| AscentModel::new(balloon, params.launch_altitude));
+  let burst_altitude = balloon
+    .and_then(|model| model.burst_altitude(params.launch_altitude))
+    .unwrap_or(params.burst_altitude);
+  let profile = match (&params.profile, &params.float) {
+    (Some(profile), _) => profile.clone(),
+    (None, Some(float)) => FlightProfile::float(float),
+    (None, None) => FlightProfile::standard(burst_altitude),
+  };
+  let shaped = params.profile.is_some() || params.float.is_some();
+  if shaped && profile.ceiling().is_some_and(|top| top >= burst_altitude) {
+    return Err(PredictionError::InvalidParams(
+      "float and profile altitudes must be below the burst altitude",
+    ));
+  }
+  let ascent_rate = |altitude| match &balloon {
+    Some(model) => model.ascent_rate(altitude),
+    None => params.ascent_rate,
+  };
+
+  // Each step is integrated in a local frame of east and north meters at
+  // its start, and mapped onto the ellipsoid by the direct geodesic; that
+  // way nothing divides by cos(latitude) and longitudes wrap at ±180°.
+  let rates = |origin: (f64, f64), phase: Phase| {
+    move |t: f64, y: &StateVector| {
+      let (lat, lon, rotation) = displaced(origin, y);
+      let climb = match phase {
+        Phase::Ascent => ascent_rate(y[2]),
+        Phase::Rate(rate) => rate,
+        Phase::Descent => -descent_rate(params.descent_rate, params.parachute.as_ref(), y[2]),
+      };
+      velocity(
+        weather.wind_at(lat, lon, y[2], launch_timestamp + t),
+        rotation,
+        climb,
+      )
+    }
+  };
+  let point = |time: f64, origin: (f64, f64), y: &StateVector| {
+    let (lat, lon, _) = displaced(origin, y);
+    FlightPoint {
+      time,
+      lat,
+      lon,
+      altitude: y[2],
+    }
+  };
+  let launch_point = point(
+    0.0,
+    (params.lat, params.lon),
+    &[0.0, 0.0, params.launch_altitude],
+  );
+  // How far past `limit` a point is; negative before it.
+  let overshoot = |limit: Limit, start: FlightPoint, p: FlightPoint| match limit {
+    Limit::None => f64::NEG_INFINITY,
+    Limit::Altitude(target) if start.altitude < target => p.altitude - target,
+    Limit::Altitude(target) => target - p.altitude,
+    Limit::Distance(distance) => {
+      geodesy::distance(launch_point.lat, launch_point.lon, p.lat, p.lon) - distance
+    }
+  };
+  // One step of up to `dt` seconds from `start`, cut short at `limit` and
+  // stopped where it meets the terrain. Returns the end point and the step
+  // to try next.
+  let advance = |start: FlightPoint, dt: f64, phase: Phase, limit: Limit| {
+    let origin = (start.lat, start.lon);
+    let y = [0.0, 0.0, start.altitude];
+    let f = rates(origin, phase);
+    let step = integrator.step(f, start.time, &y, dt, settings.tolerance, position_error);
+    let mut end = point(start.time + step.dt, origin, &step.state);
+    let mut outcome = Outcome::Flying;
+    let past = overshoot(limit, start, end);
+    if past >= 0.0 {
+      if past > 0.0 {
+        let before = overshoot(limit, start, start);
+        let fraction = before / (before - past);
+        let short =
+          integrator
+            .fixed()
+            .step(f, start.time, &y, step.dt * fraction, 0.0, position_error);
+        end = point(start.time + short.dt, origin, &short.state);
+      }
+      if let Limit::Altitude(target) = limit {
+        end.altitude = target;
+      }
+      outcome = Outcome::Reached;
+    }
+    if end.altitude <= start.altitude {
+      let above_end = end.altitude - terrain.elevation(end.lat, end.lon);
+      if above_end <= 0.0 {
+        let above_start = start.altitude - terrain.elevation(start.lat, start.lon);
+        let landing = terrain_crossing(start, end, above_start, above_end, terrain);
+        return (landing, step.next_dt, Outcome::Landed);
+      }
+    }
+    (end, step.next_dt, outcome)
+  };
+
+  let mut here = launch_point;
+  // Every integrator step; resampled for the returned path.
+  let mut trajectory = vec![launch_point];
+  let (mut float_point, mut cutdown_point, mut descent_start) = (None, None, None);
+
+  for segment in &profile.segments {
+    let climbing =
+      matches!(segment, Segment::ConstantRate { altitude, .. } if *altitude > here.altitude);
+    if !climbing && here.altitude <= terrain.elevation(here.lat, here.lon) {
+      break;
+    }
+    // Phase, limit and end time of the segment
+    let (phase, limit, until) = match segment {
+      Segment::ConstantRate { altitude, rate } => {
+        let phase = match (rate, climbing) {
+          (Some(rate), true) => Phase::Rate(*rate),
+          (Some(rate), false) => Phase::Rate(-rate),
+          (None, true) => Phase::Ascent,
+          (None, false) => Phase::Descent,
+        };
+        if *altitude == here.altitude {
+          continue;
+        }
+        (phase, Limit::Altitude(*altitude), MAX_FLIGHT_TIME_S)
+      }
+      Segment::FloatUntilTime {
+        duration,
+        end_time,
+        leak_rate,
+      } => {
+        float_point.get_or_insert(here);
+        let end = match (duration, end_time) {
+          (Some(duration), _) => here.time + duration,
+          (None, Some(end_time)) => parse_time(end_time).map_or(here.time, |end| {
+            (end - launch_time).num_milliseconds() as f64 / 1000.0
+          }),
+          (None, None) => here.time,
+        };
+        (
+          Phase::Rate(*leak_rate),
+          Limit::None,
+          end.min(MAX_FLIGHT_TIME_S),
+        )
+      }
+      Segment::FloatUntilDistance {
+        distance,
+        leak_rate,
+      } => {
+        float_point.get_or_insert(here);
+        if overshoot(Limit::Distance(*distance), here, here) >= 0.0 {
+          continue;
+        }
+        (
+          Phase::Rate(*leak_rate),
+          Limit::Distance(*distance),
+          MAX_FLIGHT_TIME_S,
+        )
+      }
+      Segment::TerminalDescent => {
+        descent_start = Some(here);
+        if shaped {
+          cutdown_point = Some(here);
+        }
+        (Phase::Descent, Limit::None, MAX_FLIGHT_TIME_S)
+      }
+    };
+
+    let (mut dt, mut outcome) = (settings.time_step, Outcome::Flying);
+    while outcome == Outcome::Flying && here.time < until {
+      (here, dt, outcome) = advance(here, dt.min(until - here.time), phase, limit);
+      trajectory.push(here);
+    }
+    if outcome == Outcome::Landed {
+      break;
+    }
+  }
+
+  // The top of the flight, even if it landed before a terminal descent.
+  let burst_point = descent_start.unwrap_or_else(|| {
+    trajectory
+      .iter()
+      .copied()
+      .reduce(|top, p| if p.altitude > top.altitude { p } else { top })
+      .unwrap_or(launch_point)
+  });
+  let t = here.time;
+
+  let landing_point = trajectory[trajectory.len() - 1];
+  let forecast_end = forecast_end - launch_timestamp;
+
+  let max_altitude = trajectory
+    .iter()
+    .fold(f64::NEG_INFINITY, |max, p| max.max(p.altitude));
+  let distance: f64 = trajectory
+    .windows(2)
+    .map(|w| geodesy::distance(w[0].lat, w[0].lon, w[1].lat, w[1].lon))
+    .sum();
+
+  Ok(PredictionResult {
+    path: resample(&trajectory, settings.output_interval, burst_point),
+    launch_point,
+    burst_point,
+    landing_point,
+    total_time: t,
+    max_altitude: if max_altitude.is_finite() {
+      max_altitude
+    } else {
+      0.0
+    },
+    distance: if distance.is_finite() { distance } else { 0.0 },
+    flight_duration: t,
+    forecast_exceeded_at: (t > forecast_end).then_some(forecast_end.max(0.0)),
+    expansion_curve: balloon.map(|model| {
+      model.expansion_curve(
+        params.launch_altitude,
+        profile.ceiling().unwrap_or(max_altitude),
+      )
+    }),
+    float_point,
+    cutdown_point,
+    wind_field: false,
+    wind_field_error: None,
+  })
+}
+
+/// Picks the forecast for a prediction: `weather_data` is the Open-Meteo
+/// forecast the frontend already fetched, and `grib_files` are local GFS
+/// GRIB2 files for offline use; with neither, the GFS forecast is fetched
+/// (or taken from the forecast cache) here.
+pub async fn resolve_weather(
+  cache: &ForecastCache,
+  params: &LaunchParams,
+  weather_data: Option<WeatherData>,
+  grib_files: Option<Vec<String>>,
+) -> Result<WeatherGrid, PredictionError> {
+  Ok(match (weather_data, grib_files) {
+    (Some(data), _) => WeatherGrid::try_from(data)?,
+    (None, Some(paths)) => grib::load_grib_weather(paths, params.lat, params.lon).await?,
+    (None, None) => {
+      let launch_time = validate(params)?;
+      weather::fetch_gfs_cached(cache, params.lat, params.lon, launch_time).await?
+    }
+  })
+}
+
+/// Builds a [`WeatherField`] around a first-pass trajectory, from local
+/// GRIB2 files when given and from the (cached) GFS forecast otherwise.
+pub async fn resolve_wind_field(
+  cache: &ForecastCache,
+  params: &LaunchParams,
+  path: &[FlightPoint],
+  grib_files: Option<Vec<String>>,
+) -> Result<WeatherField, PredictionError> {
+  let layout = FieldLayout::around(path.iter().map(|p| (p.lat, p.lon)))
+    .ok_or_else(|| PredictionError::Simulation("empty trajectory".into()))?;
+  let points = layout.points();
+  let columns = match grib_files {
+    Some(paths) => {
+      tauri::async_runtime::spawn_blocking(move || grib::load_gfs_columns(&paths, &points))
+        .await
+        .map_err(|e| PredictionError::Simulation(e.to_string()))??
+    }
+    None => weather::fetch_gfs_field_cached(cache, &points, validate(params)?).await?,
+  };
+  Ok(WeatherField::new(layout, columns)?)
+}
+
+/// Runs a prediction on the forecast picked by [`resolve_weather`]. Terrain
+/// comes from the local DEM where it has tiles, and is `ground_elevation`
+/// (sea level if not given) elsewhere.
+///
+/// Unless the frontend supplied `weather_data` (a single column), the flight
+/// is first run on the launch site's column and then re-run on a wind field
+/// covering that track. If the field cannot be assembled, the single-column
+/// prediction is returned with the reason in `wind_field_error`.
+pub async fn predict(
+  cache: &ForecastCache,
+  dem: &DemProvider,
+  params: LaunchParams,
+  weather_data: Option<WeatherData>,
+  grib_files: Option<Vec<String>>,
+  ground_elevation: Option<f64>,
+) -> Result<PredictionResult, PredictionError> {
+  let single_column = weather_data.is_some();
+  let grid = resolve_weather(cache, &params, weather_data, grib_files.clone()).await?;
+  let terrain = DemTerrain {
+    dem: dem.clone(),
+    fallback: ground_elevation.unwrap_or(0.0),
+  };
+
+  let first_pass = {
+    let (params, terrain) = (params.clone(), terrain.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+      run_prediction_simulation(&params, &grid, &terrain)
+    })
+    .await
+    .map_err(|e| PredictionError::Simulation(e.to_string()))??
+  };
+  if single_column {
+    return Ok(first_pass);
+  }
+
+  let field = match resolve_wind_field(cache, &params, &first_pass.path, grib_files).await {
+    Ok(field) => field,
+    Err(error) => {
+      log::warn!("Using the launch-site forecast only: {}", error);
+      return Ok(PredictionResult {
+        wind_field_error: Some(error.to_string()),
+        ..first_pass
+      });
+    }
+  };
+  let result = tauri::async_runtime::spawn_blocking(move || {
+    run_prediction_simulation(&params, &field, &terrain)
+  })
+  .await
+  .map_err(|e| PredictionError::Simulation(e.to_string()))??;
+  Ok(PredictionResult {
+    wind_field: true,
+    ..result
+  })
+}
+
+/// Runs a prediction; see [`predict`].
+#[tauri::command]
+pub async fn run_prediction(
+  cache: State<'_, ForecastCache>,
+  dem: State<'_, DemProvider>,
+  params: LaunchParams,
+  weather_data: Option<WeatherData>,
+  grib_files: Option<Vec<String>>,
+  ground_elevation: Option<f64>,
+) -> Result<PredictionResult, PredictionError> {
+  predict(
+    &cache,
+    dem.inner(),
+    params,
+    weather_data,
+    grib_files,
+    ground_elevation,
+  )
+  .await
+}
+
+#[cfg(test)]
+impl LaunchParams {
+  /// A 5 m/s ascent to 30 km and 5 m/s descent from 40°N 105°W, 1600 m, at
+  /// noon UTC on 2025-07-07: the launch tests start from.
+  pub fn test_default() -> Self {
+    LaunchParams {
+      lat: 40.0,
+      lon: -105.0,
+      launch_time: "2025-07-07T12:00:00Z".into(),
+      launch_altitude: 1600.0,
+      ascent_rate: 5.0,
+      burst_altitude: 30000.0,
+      balloon: None,
+      descent_rate: 5.0,
+      parachute: None,
+      tracking_callsign: None,
+      integration: IntegrationSettings::default(),
+      float: None,
+      profile: None,
+    }
+  }
+}
+
+#[cfg(test)]
+impl WeatherGrid {
+  /// A forecast of `hours` hourly steps from 2025-07-07T00:00Z with the same
+  /// wind at every level and hour.
+  pub fn uniform(speed: f64, direction: f64, hours: usize) -> Self {
+    Self::strengthening(speed, 0.0, direction, hours)
+  }
+
+  /// Like [`WeatherGrid::uniform`], but the wind at every level starts at
+  /// `speed` and changes by `per_hour` m/s each hour.
+  pub fn strengthening(speed: f64, per_hour: f64, direction: f64, hours: usize) -> Self {
+    use crate::weather::{LevelSample, SurfaceSample, PRESSURE_LEVELS};
+
+    let start = parse_time("2025-07-07T00:00").unwrap().timestamp();
+    let times: Vec<i64> = (0..hours as i64).map(|h| start + h * 3600).collect();
+    let levels = PRESSURE_LEVELS.to_vec();
+    let samples = (0..hours)
+      .flat_map(|hour| {
+        let sample = LevelSample {
+          wind_speed: speed + per_hour * hour as f64,
+          wind_direction: direction,
+          geopotential_height: None,
+          temperature: None,
+        };
+        vec![Some(sample); levels.len()]
+      })
+      .collect();
+    WeatherGrid {
+      samples,
+      surface: vec![SurfaceSample::default(); hours],
+      times,
+      levels,
+      ..Default::default()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ascent::{LiftGas, BALLOON_DRAG_COEFFICIENT};
+  use crate::weather::open_meteo;
+
+  #[test]
+  fn calm_flight_lands_at_launch_site() {
+    let result = run_prediction_simulation(
+      &LaunchParams::test_default(),
+      &WeatherGrid::uniform(0.0, 0.0, 48),
+      &0.0,
+    )
+    .unwrap();
+
+    assert_eq!(result.burst_point.altitude, 30000.0);
+    assert_eq!(result.landing_point.altitude, 0.0);
+    assert!((result.landing_point.lat - 40.0).abs() < 1e-9);
+    assert!((result.landing_point.lon + 105.0).abs() < 1e-9);
+    // 28.4 km up at 5 m/s, the last step cut short at the burst.
+    assert_eq!(result.burst_point.time, 28400.0 / 5.0);
+    assert!(result.path.contains(&result.burst_point));
+    // Falling fast through thin air, the descent takes well under the
+    // 100 minutes 5 m/s all the way down would.
+    let descent = result.total_time - result.burst_point.time;
+    assert!(
+      descent > 30.0 * 60.0 && descent < 60.0 * 60.0,
+      "{}",
+      descent
+    );
+    // ...and slows to about the sea-level rate near the ground.
+    let last = result.path[result.path.len() - 2];
+    let final_rate = last.altitude / (result.total_time - last.time);
+    assert!((final_rate - 5.0).abs() < 0.3, "{}", final_rate);
+    assert_eq!(result.forecast_exceeded_at, None);
+  }
+
+  #[test]
+  fn blends_between_forecast_hours() {
+    let mut grid = WeatherGrid::uniform(10.0, 270.0, 48);
+    let levels = grid.levels.len();
+    for sample in grid.samples[levels..2 * levels].iter_mut().flatten() {
+      sample.wind_direction = 0.0;
+    }
+    let start = grid.times[0] as f64;
+
+    let on_the_hour = grid.wind_at(40.0, -105.0, 10000.0, start);
+    assert_eq!(on_the_hour.direction, 270.0);
+    // Halfway from a westerly to a northerly: a weaker northwesterly.
+    let between = grid.wind_at(40.0, -105.0, 10000.0, start + 1800.0);
+    assert!((between.speed - 50.0_f64.sqrt()).abs() < 1e-9);
+    assert!((between.direction - 315.0).abs() < 1e-9);
+    let quarter = grid.wind_at(40.0, -105.0, 10000.0, start + 2700.0);
+    assert!(quarter.direction < 360.0 && quarter.direction > 315.0);
+  }
+
+  #[test]
+  fn reports_running_past_the_forecast() {
+    // The forecast ends at 13:00, an hour after launch.
+    let mut grid = WeatherGrid::uniform(10.0, 270.0, 48);
+    let hours = 14;
+    grid.times.truncate(hours);
+    grid.samples.truncate(hours * grid.levels.len());
+    grid.surface.truncate(hours);
+
+    let result = run_prediction_simulation(&LaunchParams::test_default(), &grid, &0.0).unwrap();
+    assert_eq!(result.forecast_exceeded_at, Some(3600.0));
+  }
+
+  #[test]
+  fn westerly_wind_drifts_east() {
+    let result = run_prediction_simulation(
+      &LaunchParams::test_default(),
+      &WeatherGrid::uniform(10.0, 270.0, 48),
+      &0.0,
+    )
+    .unwrap();
+
+    // 10 m/s for the whole flight.
+    let expected = 10.0 * result.total_time;
+    assert!((result.distance - expected).abs() / expected < 0.01);
+    assert!(result.landing_point.lon > -105.0);
+    assert!((result.landing_point.lat - 40.0).abs() < 0.05);
+  }
+
+  #[test]
+  fn runs_on_recorded_forecast() {
+    let grid =
+      open_meteo::parse_response(200, include_str!("../tests/fixtures/open_meteo_gfs.json"))
+        .unwrap();
+    let result = run_prediction_simulation(&LaunchParams::test_default(), &grid, &1600.0).unwrap();
+
+    assert_eq!(result.landing_point.altitude, 1600.0);
+    // Westerlies aloft carry the payload east.
+    assert!(result.landing_point.lon > result.launch_point.lon);
+    assert!(result.distance > 50_000.0);
+  }
+
+  #[test]
+  fn parachute_sets_the_descent_rate() {
+    let mut heavy_params = LaunchParams::test_default();
+    heavy_params.parachute = Some(Parachute {
+      diameter: 0.9,
+      drag_coefficient: 1.5,
+      payload_mass: 2.0,
+    });
+    let light_chute = Parachute {
+      payload_mass: 0.5,
+      ..heavy_params.parachute.unwrap()
+    };
+    let mut light_params = LaunchParams::test_default();
+    light_params.parachute = Some(light_chute);
+
+    let calm = WeatherGrid::uniform(0.0, 0.0, 48);
+    let heavy = run_prediction_simulation(&heavy_params, &calm, &0.0).unwrap();
+    let light = run_prediction_simulation(&light_params, &calm, &0.0).unwrap();
+    assert!(heavy.total_time < light.total_time);
+
+    light_params.parachute = Some(Parachute {
+      drag_coefficient: -1.0,
+      ..light_chute
+    });
+    assert!(matches!(
+      run_prediction_simulation(&light_params, &calm, &0.0),
+      Err(PredictionError::InvalidParams(_))
+    ));
+  }
+
+  #[test]
+  fn balloon_model_drives_the_ascent() {
+    let mut with_balloon = LaunchParams::test_default();
+    with_balloon.balloon = Some(Balloon {
+      balloon_mass: 1.2,
+      payload_mass: 1.0,
+      free_lift: 1.5,
+      gas: LiftGas::Helium,
+      gas_purity: 1.0,
+      drag_coefficient: BALLOON_DRAG_COEFFICIENT,
+      burst_diameter: Some(8.63),
+    });
+    let result =
+      run_prediction_simulation(&with_balloon, &WeatherGrid::uniform(0.0, 0.0, 48), &0.0).unwrap();
+
+    // Bursts at the rated diameter rather than at `burst_altitude`.
+    let curve = result.expansion_curve.unwrap();
+    let top = curve.last().unwrap();
+    assert_eq!(top.altitude, result.burst_point.altitude);
+    assert!((top.radius * 2.0 - 8.63).abs() < 0.01);
+    assert_ne!(result.burst_point.altitude, 30000.0);
+    // The climb speeds up: later steps gain more height than the first.
+    let gain = |i: usize| result.path[i + 1].altitude - result.path[i].altitude;
+    assert!(gain(60) > gain(0));
+  }
+
+  /// Winds that strengthen and veer with height.
+  fn veering_weather() -> WeatherGrid {
+    let mut grid = WeatherGrid::uniform(0.0, 0.0, 48);
+    let levels = grid.levels.clone();
+    for (index, sample) in grid.samples.iter_mut().enumerate() {
+      let level = levels[index % levels.len()] as f64;
+      let sample = sample.as_mut().unwrap();
+      sample.wind_speed = 3.0 + 30.0 * (1.0 - level / 1000.0);
+      sample.wind_direction = 180.0 + 120.0 * (1.0 - level / 1000.0);
+    }
+    grid
+  }
+
+  fn landing_with(integrator: Integrator, time_step: f64) -> FlightPoint {
+    let mut params = LaunchParams::test_default();
+    params.integration = IntegrationSettings {
+      integrator,
+      time_step,
+      ..Default::default()
+    };
+    run_prediction_simulation(&params, &veering_weather(), &0.0)
+      .unwrap()
+      .landing_point
+  }
+
+  #[test]
+  fn landing_converges_as_the_step_shrinks() {
+    let reference = landing_with(Integrator::Rk4, 1.0);
+    let miss = |p: FlightPoint| geodesy::distance(p.lat, p.lon, reference.lat, reference.lon);
+
+    let euler: Vec<f64> = [120.0, 60.0, 30.0, 15.0]
+      .into_iter()
+      .map(|dt| miss(landing_with(Integrator::Euler, dt)))
+      .collect();
+    // First order: each halving of the step roughly halves the miss.
+    for pair in euler.windows(2) {
+      assert!(pair[1] < pair[0] * 0.7, "{:?}", euler);
+    }
+    assert!(euler[1] > 100.0, "{:?}", euler);
+
+    // RK4 at the default 60 s beats Euler at 15 s, and RK45 picks its own
+    // steps to stay within a few meters, closer as the tolerance tightens.
+    let rk4 = miss(landing_with(Integrator::Rk4, 60.0));
+    assert!(rk4 < euler[3] / 10.0, "{} vs {:?}", rk4, euler);
+    let rk45 = |tolerance| {
+      let mut params = LaunchParams::test_default();
+      params.integration = IntegrationSettings {
+        integrator: Integrator::Rk45,
+        tolerance,
+        ..Default::default()
+      };
+      let result = run_prediction_simulation(&params, &veering_weather(), &0.0).unwrap();
+      miss(result.landing_point)
+    };
+    assert!(rk45(0.1) < 10.0, "{}", rk45(0.1));
+    assert!(rk45(0.01) < rk45(0.1));
+  }
+
+  #[test]
+  fn resamples_path_to_output_interval() {
+    let mut params = LaunchParams::test_default();
+    params.integration = IntegrationSettings {
+      integrator: Integrator::Rk45,
+      output_interval: 300.0,
+      ..Default::default()
+    };
+    let result = run_prediction_simulation(&params, &veering_weather(), &0.0).unwrap();
+
+    let path = &result.path;
+    assert!(path.contains(&result.burst_point));
+    assert_eq!(path.last(), Some(&result.landing_point));
+    assert!(path.windows(2).all(|w| w[1].time > w[0].time));
+    let on_grid = path.iter().filter(|p| p.time % 300.0 == 0.0).count();
+    assert_eq!(on_grid, path.len() - 2);
+  }
+
+  #[test]
+  fn crosses_the_date_line() {
+    let mut params = LaunchParams::test_default();
+    params.lon = 179.9;
+    let result =
+      run_prediction_simulation(&params, &WeatherGrid::uniform(10.0, 270.0, 48), &0.0).unwrap();
+
+    // Drifts ~90 km east into the western hemisphere, without a 360° jump.
+    assert!(
+      result.landing_point.lon < -178.0,
+      "{}",
+      result.landing_point.lon
+    );
+    assert!(result.path.iter().all(|p| (-180.0..180.0).contains(&p.lon)));
+    let expected = 10.0 * result.total_time;
+    assert!((result.distance - expected).abs() / expected < 0.01);
+    assert!(result
+      .path
+      .windows(2)
+      .all(|w| geodesy::distance(w[0].lat, w[0].lon, w[1].lat, w[1].lon) < 1000.0));
+  }
+
+  #[test]
+  fn circles_near_the_pole() {
+    // 11 km from the pole a westerly carries the flight around it more than
+    // once, at a steady latitude.
+    let mut params = LaunchParams::test_default();
+    params.lat = 89.9;
+    let result =
+      run_prediction_simulation(&params, &WeatherGrid::uniform(10.0, 270.0, 48), &0.0).unwrap();
+
+    assert!(result
+      .path
+      .iter()
+      .all(|p| (p.lat - 89.9).abs() < 0.001 && (-180.0..180.0).contains(&p.lon)));
+    let expected = 10.0 * result.total_time;
+    assert!((result.distance - expected).abs() / expected < 0.01);
+
+    // A southerly carries it over the pole, where the same wind turns it
+    // back: it stays near the pole instead of blowing up.
+    params.lat = 89.95;
+    let result =
+      run_prediction_simulation(&params, &WeatherGrid::uniform(10.0, 180.0, 48), &0.0).unwrap();
+    assert!(result.path.iter().all(|p| p.lat > 89.9 && p.lat <= 90.0));
+    assert!(result.path.iter().any(|p| p.lon.abs() > 90.0));
+  }
+
+  #[test]
+  fn floats_until_cutdown() {
+    let mut params = LaunchParams::test_default();
+    params.float = Some(FloatProfile {
+      altitude: 20000.0,
+      duration: Some(3.0 * 3600.0),
+      end_time: None,
+      leak_rate: 0.0,
+    });
+    let result =
+      run_prediction_simulation(&params, &WeatherGrid::uniform(10.0, 270.0, 48), &0.0).unwrap();
+
+    let float = result.float_point.unwrap();
+    let cutdown = result.cutdown_point.unwrap();
+    assert_eq!(float.altitude, 20000.0);
+    assert_eq!(float.time, 18400.0 / 5.0);
+    assert_eq!(cutdown.time, float.time + 3.0 * 3600.0);
+    assert_eq!(cutdown.altitude, 20000.0);
+    assert_eq!(result.burst_point, cutdown);
+    assert_eq!(result.max_altitude, 20000.0);
+    // Three hours on a 10 m/s westerly at float.
+    let drift = geodesy::distance(float.lat, float.lon, cutdown.lat, cutdown.lon);
+    assert!((drift - 108_000.0).abs() < 100.0, "{}", drift);
+    assert!(cutdown.lon > float.lon);
+    assert!(result.landing_point.time > cutdown.time);
+
+    // An end time gives the same cutdown.
+    params.float = Some(FloatProfile {
+      duration: None,
+      end_time: Some("2025-07-07T16:01:20Z".into()),
+      ..params.float.unwrap()
+    });
+    let by_time =
+      run_prediction_simulation(&params, &WeatherGrid::uniform(10.0, 270.0, 48), &0.0).unwrap();
+    assert_eq!(by_time.cutdown_point.unwrap().time, 4.0 * 3600.0 + 80.0);
+  }
+
+  #[test]
+  fn leaking_float_sinks() {
+    let mut params = LaunchParams::test_default();
+    params.float = Some(FloatProfile {
+      altitude: 20000.0,
+      duration: Some(3600.0),
+      end_time: None,
+      leak_rate: -0.5,
+    });
+    let result =
+      run_prediction_simulation(&params, &WeatherGrid::uniform(0.0, 0.0, 48), &0.0).unwrap();
+    let cutdown = result.cutdown_point.unwrap();
+    assert!((cutdown.altitude - 18200.0).abs() < 1e-6);
+
+    // A leak that reaches the ground lands before the cutdown.
+    params.float = Some(FloatProfile {
+      duration: Some(24.0 * 3600.0),
+      ..params.float.unwrap()
+    });
+    let result =
+      run_prediction_simulation(&params, &WeatherGrid::uniform(0.0, 0.0, 48), &0.0).unwrap();
+    assert_eq!(result.cutdown_point, None);
+    assert_eq!(result.landing_point.altitude, 0.0);
+    assert!((result.landing_point.time - result.float_point.unwrap().time - 40000.0).abs() < 1.0);
+
+    params.float = Some(FloatProfile {
+      altitude: 35000.0,
+      ..params.float.unwrap()
+    });
+    assert!(matches!(
+      run_prediction_simulation(&params, &WeatherGrid::uniform(0.0, 0.0, 48), &0.0),
+      Err(PredictionError::InvalidParams(_))
+    ));
+  }
+
+  #[test]
+  fn flies_a_multi_segment_profile() {
+    let mut params = LaunchParams::test_default();
+    params.profile = Some(
+      serde_json::from_str(
+        r#"{"segments": [
+        {"type": "constantRate", "altitude": 18000},
+        {"type": "floatUntilTime", "duration": 7200},
+        {"type": "constantRate", "altitude": 12000, "rate": 1.0},
+        {"type": "floatUntilDistance", "distance": 200000},
+        {"type": "terminalDescent"}
+      ]}"#,
+      )
+      .unwrap(),
+    );
+    let result =
+      run_prediction_simulation(&params, &WeatherGrid::uniform(10.0, 270.0, 48), &0.0).unwrap();
+
+    assert_eq!(result.max_altitude, 18000.0);
+    let float = result.float_point.unwrap();
+    assert_eq!(float.time, 16400.0 / 5.0);
+    // Two hours at 18 km, then down at 1 m/s: 3080 s into the valving.
+    let valved = result.path.iter().find(|p| p.time == 13560.0).unwrap();
+    assert_eq!(float.time + 7200.0 + 3080.0, 13560.0);
+    assert!((valved.altitude - 14920.0).abs() < 1e-6);
+
+    let cutdown = result.cutdown_point.unwrap();
+    assert_eq!(result.burst_point, cutdown);
+    assert_eq!(cutdown.altitude, 12000.0);
+    let from_launch = geodesy::distance(40.0, -105.0, cutdown.lat, cutdown.lon);
+    assert!((from_launch - 200_000.0).abs() < 1.0, "{}", from_launch);
+    assert_eq!(result.landing_point.altitude, 0.0);
+
+    // A profile may not climb past the burst altitude.
+    let mut too_high = params.clone();
+    too_high.profile = Some(FlightProfile::standard(31000.0));
+    assert!(matches!(
+      run_prediction_simulation(&too_high, &WeatherGrid::uniform(0.0, 0.0, 48), &0.0),
+      Err(PredictionError::InvalidParams(_))
+    ));
+    too_high.profile = None;
+    too_high.float = Some(FloatProfile {
+      altitude: 35000.0,
+      duration: Some(60.0),
+      end_time: None,
+      leak_rate: 0.0,
+    });
+    assert!(matches!(
+      run_prediction_simulation(&too_high, &WeatherGrid::uniform(0.0, 0.0, 48), &0.0),
+      Err(PredictionError::InvalidParams(_))
+    ));
+  }
+
+  #[test]
+  fn rejects_non_positive_rates() {
+    let mut bad = LaunchParams::test_default();
+    bad.descent_rate = 0.0;
+    assert!(matches!(
+      run_prediction_simulation(&bad, &WeatherGrid::uniform(0.0, 0.0, 48), &0.0),
+      Err(PredictionError::InvalidParams(_))
+    ));
+  }
+
+  /// A ridge rising 1 m per 10 m east of 105°W.
+  struct Slope;
+
+  impl Terrain for Slope {
+    fn elevation(&self, _lat: f64, lon: f64) -> f64 {
+      ((lon + 105.0) * 85_000.0 / 10.0).max(0.0)
+    }
+  }
+
+  /// Calm at 106°W, a 10 m/s westerly at 104°W.
+  fn sheared_field() -> WeatherField {
+    let layout = FieldLayout {
+      lats: vec![39.0, 41.0],
+      lons: vec![-106.0, -104.0],
+    };
+    let columns = layout
+      .points()
+      .into_iter()
+      .map(|(lat, lon)| {
+        let speed = if lon < -105.0 { 0.0 } else { 10.0 };
+        WeatherGrid {
+          latitude: lat,
+          longitude: lon,
+          ..WeatherGrid::uniform(speed, 270.0, 48)
+        }
+      })
+      .collect();
+    WeatherField::new(layout, columns).unwrap()
+  }
+
+  #[test]
+  fn winds_vary_across_the_field() {
+    let field = sheared_field();
+    let result = run_prediction_simulation(&LaunchParams::test_default(), &field, &0.0).unwrap();
+
+    // Halfway between the columns the westerly is at half strength...
+    let first = geodesy::distance(
+      result.path[0].lat,
+      result.path[0].lon,
+      result.path[1].lat,
+      result.path[1].lon,
+    );
+    assert!((first - 5.0 * TIME_STEP_S).abs() < 1.0);
+    // ...and it strengthens as the balloon drifts east.
+    let half = run_prediction_simulation(
+      &LaunchParams::test_default(),
+      &WeatherGrid::uniform(5.0, 270.0, 48),
+      &0.0,
+    )
+    .unwrap();
+    assert!(result.distance > half.distance * 1.2);
+    assert!((result.landing_point.lat - 40.0).abs() < 0.05);
+
+    // One column behaves exactly like the bare grid.
+    let grid = WeatherGrid::uniform(10.0, 270.0, 48);
+    let layout = FieldLayout {
+      lats: vec![40.0],
+      lons: vec![-105.0],
+    };
+    let single = WeatherField::new(layout, vec![grid.clone()]).unwrap();
+    let a = run_prediction_simulation(&LaunchParams::test_default(), &single, &0.0).unwrap();
+    let b = run_prediction_simulation(&LaunchParams::test_default(), &grid, &0.0).unwrap();
+    assert!((a.landing_point.lon - b.landing_point.lon).abs() < 1e-9);
+  }
+
+  #[test]
+  fn lands_where_descent_meets_terrain() {
+    let result = run_prediction_simulation(
+      &LaunchParams::test_default(),
+      &WeatherGrid::uniform(10.0, 270.0, 48),
+      &Slope,
+    )
+    .unwrap();
+    let landing = result.landing_point;
+
+    assert!(
+      (landing.altitude - Slope.elevation(landing.lat, landing.lon)).abs() < TERRAIN_TOLERANCE_M
+    );
+    // Lands on the slope well above sea level, earlier than flat ground would.
+    assert!(landing.altitude > 1000.0);
+    let flat = run_prediction_simulation(
+      &LaunchParams::test_default(),
+      &WeatherGrid::uniform(10.0, 270.0, 48),
+      &0.0,
+    )
+    .unwrap();
+    assert!(result.total_time < flat.total_time);
+    assert_eq!(result.total_time, landing.time);
+    // The landing lies between the last two steps, not on the step grid.
+    let previous = result.path[result.path.len() - 2];
+    assert!(landing.time > previous.time && landing.time < previous.time + TIME_STEP_S);
+  }
+}
@@ -0,0 +1,200 @@
+//! Multi-segment flight profiles.
+//!
+//! A profile is an ordered list of segments that the prediction engine runs
+//! one after the other, e.g. "ascend to 18 km, float 2 h, valve down at
+//! 1 m/s to 12 km, float, then cut down". Standard burst flights and the
+//! float shorthand of `LaunchParams` are run as the profiles built by
+//! [`FlightProfile::standard`] and [`FlightProfile::float`].
+
+use serde::{Deserialize, Serialize};
+
+use crate::prediction::FloatProfile;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+  tag = "type",
+  rename_all = "camelCase",
+  rename_all_fields = "camelCase"
+)]
+pub enum Segment {
+  /// Climb or sink to `altitude` (m). At `rate` m/s when given; otherwise
+  /// up at the launch ascent rate (or balloon model) and down at the
+  /// descent rate.
+  ConstantRate {
+    altitude: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rate: Option<f64>,
+  },
+  /// Float for `duration` seconds, or until the ISO `end_time`.
+  FloatUntilTime {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    end_time: Option<String>,
+    /// Vertical speed while floating, m/s; negative for a slow leak
+    #[serde(default)]
+    leak_rate: f64,
+  },
+  /// Float until the payload is `distance` meters from the launch site.
+  FloatUntilDistance {
+    distance: f64,
+    #[serde(default)]
+    leak_rate: f64,
+  },
+  /// Burst or cutdown, and the descent to the ground.
+  TerminalDescent,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlightProfile {
+  pub segments: Vec<Segment>,
+}
+
+impl FlightProfile {
+  /// Ascent to `burst_altitude` and descent.
+  pub fn standard(burst_altitude: f64) -> Self {
+    FlightProfile {
+      segments: vec![
+        Segment::ConstantRate {
+          altitude: burst_altitude,
+          rate: None,
+        },
+        Segment::TerminalDescent,
+      ],
+    }
+  }
+
+  /// Ascent to the float altitude, the float and a cutdown.
+  pub fn float(float: &FloatProfile) -> Self {
+    FlightProfile {
+      segments: vec![
+        Segment::ConstantRate {
+          altitude: float.altitude,
+          rate: None,
+        },
+        Segment::FloatUntilTime {
+          duration: float.duration,
+          end_time: float.end_time.clone(),
+          leak_rate: float.leak_rate,
+        },
+        Segment::TerminalDescent,
+      ],
+    }
+  }
+
+  /// Highest altitude any segment climbs to, m.
+  pub fn ceiling(&self) -> Option<f64> {
+    self
+      .segments
+      .iter()
+      .filter_map(|segment| match segment {
+        Segment::ConstantRate { altitude, .. } => Some(*altitude),
+        _ => None,
+      })
+      .reduce(f64::max)
+  }
+
+  /// Checks the segments, leaving end times to be parsed with the launch
+  /// time.
+  pub fn validate(&self) -> Result<(), &'static str> {
+    let Some((Segment::TerminalDescent, rest)) = self.segments.split_last() else {
+      return Err("a flight profile must end with a terminal descent");
+    };
+    for segment in rest {
+      match segment {
+        Segment::ConstantRate { altitude, rate } => {
+          if !altitude.is_finite() || rate.is_some_and(|r| !r.is_finite() || r <= 0.0) {
+            return Err("profile altitudes must be finite and rates positive");
+          }
+        }
+        Segment::FloatUntilTime {
+          duration,
+          end_time,
+          leak_rate,
+        } => {
+          let ends = match (duration, end_time) {
+            (Some(duration), None) => duration.is_finite() && *duration >= 0.0,
+            (None, Some(_)) => true,
+            _ => false,
+          };
+          if !ends || !leak_rate.is_finite() {
+            return Err("a float needs either a non-negative duration or an end time");
+          }
+        }
+        Segment::FloatUntilDistance {
+          distance,
+          leak_rate,
+        } => {
+          if !distance.is_finite() || *distance < 0.0 || !leak_rate.is_finite() {
+            return Err("float distances must be non-negative");
+          }
+        }
+        Segment::TerminalDescent => {
+          return Err("a flight profile must end with its only terminal descent");
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_tagged_segments() {
+    let profile: FlightProfile = serde_json::from_str(
+      r#"{"segments": [
+        {"type": "constantRate", "altitude": 18000},
+        {"type": "floatUntilTime", "duration": 7200},
+        {"type": "constantRate", "altitude": 12000, "rate": 1.0},
+        {"type": "floatUntilDistance", "distance": 150000, "leakRate": -0.1},
+        {"type": "terminalDescent"}
+      ]}"#,
+    )
+    .unwrap();
+    assert_eq!(profile.segments.len(), 5);
+    assert_eq!(
+      profile.segments[2],
+      Segment::ConstantRate {
+        altitude: 12000.0,
+        rate: Some(1.0)
+      }
+    );
+    assert_eq!(profile.ceiling(), Some(18000.0));
+    assert_eq!(profile.validate(), Ok(()));
+
+    let json = serde_json::to_string(&profile).unwrap();
+    assert!(json.contains(r#""leakRate":-0.1"#));
+    assert_eq!(
+      serde_json::from_str::<FlightProfile>(&json).unwrap(),
+      profile
+    );
+  }
+
+  #[test]
+  fn needs_one_terminal_descent_at_the_end() {
+    let mut profile = FlightProfile::standard(30000.0);
+    assert!(profile.validate().is_ok());
+
+    profile.segments.pop();
+    assert!(profile.validate().is_err());
+    profile.segments.insert(0, Segment::TerminalDescent);
+    profile.segments.push(Segment::TerminalDescent);
+    assert!(profile.validate().is_err());
+
+    let float = FlightProfile {
+      segments: vec![
+        Segment::FloatUntilTime {
+          duration: Some(60.0),
+          end_time: Some("2025-07-07T12:00:00Z".into()),
+          leak_rate: 0.0,
+        },
+        Segment::TerminalDescent,
+      ],
+    };
+    assert!(float.validate().is_err());
+  }
+}
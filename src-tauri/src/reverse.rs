@@ -0,0 +1,545 @@
+//! Reverse prediction: launch sites for a landing target.
+//!
+//! For every launch time in a window, a forward prediction from the target
+//! gives the flight's altitude over time. The drift is then integrated
+//! backwards from the target through the winds at those altitudes, which
+//! lands close to the launch site. Each candidate is checked with a forward
+//! prediction, and shifted by the landing miss until it lands within
+//! `LANDING_TOLERANCE_M` of the target. The launch altitude follows the
+//! terrain as the site moves. Sites are ranked by landing error, after every
+//! site whose flight stays within the forecast.
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::elevation::dem::DemProvider;
+use crate::elevation::{DemTerrain, Terrain};
+use crate::geodesy;
+use crate::integrator::{Integrator, StateVector};
+use crate::prediction::{
+  displaced, launch_times, resolve_weather, resolve_wind_field, run_prediction_simulation,
+  velocity, FlightPoint, LaunchParams, PredictionError, PredictionResult, WindSource,
+  MAX_FLIGHT_TIME_S,
+};
+use crate::weather::cache::ForecastCache;
+use crate::weather::{self, parse_time};
+
+/// Landing error at which a candidate is accepted, m.
+const LANDING_TOLERANCE_M: f64 = 100.0;
+/// Forward checks per launch time, including the first.
+const MAX_CHECKS: usize = 6;
+
+fn default_window_step() -> f64 {
+  3600.0
+}
+
+/// Landing target and the launch times to search.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReverseSettings {
+  pub target_lat: f64,
+  pub target_lon: f64,
+  /// ISO format strings, inclusive
+  pub window_start: String,
+  pub window_end: String,
+  /// Seconds between launch times
+  #[serde(default = "default_window_step")]
+  pub window_step: f64,
+}
+
+/// A launch site found for one launch time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchSite {
+  /// ISO format string
+  pub launch_time: String,
+  pub lat: f64,
+  pub lon: f64,
+  /// Distance from the forward-predicted landing to the target, m
+  pub landing_error: f64,
+  /// Forward predictions run to check and refine the site
+  pub iterations: usize,
+  /// Whether the checked flight outlasted the forecast; such sites rest on
+  /// the last forecast hour's winds and rank after all others
+  pub forecast_exceeded: bool,
+  pub prediction: PredictionResult,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReverseResult {
+  /// Best site first
+  pub sites: Vec<LaunchSite>,
+  /// Launch times whose search failed
+  pub failed_launch_times: usize,
+}
+
+/// First and last launch time of the window.
+fn window_bounds(
+  settings: &ReverseSettings,
+) -> Result<(DateTime<Utc>, DateTime<Utc>), PredictionError> {
+  let start = parse_time(&settings.window_start)
+    .ok_or_else(|| PredictionError::InvalidLaunchTime(settings.window_start.clone()))?;
+  let end = parse_time(&settings.window_end)
+    .ok_or_else(|| PredictionError::InvalidLaunchTime(settings.window_end.clone()))?;
+  Ok((start, end))
+}
+
+/// Launch times from the window start to its end.
+fn window_times(settings: &ReverseSettings) -> Result<Vec<DateTime<Utc>>, PredictionError> {
+  let (start, end) = window_bounds(settings)?;
+  launch_times(start, end, settings.window_step)
+}
+
+/// Sites within the forecast first, each group by landing error.
+fn rank(sites: &mut [LaunchSite]) {
+  sites.sort_by(|a, b| {
+    a.forecast_exceeded
+      .cmp(&b.forecast_exceeded)
+      .then(a.landing_error.total_cmp(&b.landing_error))
+  });
+}
+
+/// Moves the launch to (`lat`, `lon`), on the ground there.
+fn move_launch(params: &mut LaunchParams, lat: f64, lon: f64, terrain: &dyn Terrain) {
+  (params.lat, params.lon) = (lat, lon);
+  params.launch_altitude = terrain.elevation(lat, lon);
+}
+
+/// Altitude of `path` at `time` seconds from launch, interpolated linearly.
+fn altitude_at(path: &[FlightPoint], time: f64) -> f64 {
+  let next = path.partition_point(|p| p.time < time);
+  match (
+    next.checked_sub(1).map(|i| path[i]),
+    path.get(next).copied(),
+  ) {
+    (Some(a), Some(b)) if b.time > a.time => {
+      a.altitude + (b.altitude - a.altitude) * (time - a.time) / (b.time - a.time)
+    }
+    (_, Some(p)) | (Some(p), None) => p.altitude,
+    (None, None) => 0.0,
+  }
+}
+
+/// Integrates the drift of `flight` backwards from `target`, at the
+/// altitude `flight` had at each moment, to where it would have launched.
+fn trace_back(
+  target: (f64, f64),
+  flight: &PredictionResult,
+  launch_timestamp: f64,
+  weather: &(impl WindSource + ?Sized),
+  time_step: f64,
+) -> (f64, f64) {
+  let mut position = target;
+  let mut t = flight.total_time;
+  while t > 0.0 {
+    let dt = time_step.min(t);
+    let origin = position;
+    // `s` counts seconds back from `t`.
+    let drift = |s: f64, y: &StateVector| {
+      let time = t - s;
+      let altitude = altitude_at(&flight.path, time);
+      let (lat, lon, rotation) = displaced(origin, y);
+      let wind = weather.wind_at(lat, lon, altitude, launch_timestamp + time);
+      let [east, north, _] = velocity(wind, rotation, 0.0);
+      [-east, -north, 0.0]
+    };
+    let step = Integrator::Rk4.step(drift, 0.0, &[0.0; 3], dt, 0.0, |_, _| 0.0);
+    let (lat, lon, _) = displaced(origin, &step.state);
+    position = (lat, lon);
+    t -= dt;
+  }
+  position
+}
+
+/// Finds the launch site for one launch time.
+fn solve(
+  params: &LaunchParams,
+  target: (f64, f64),
+  launch_time: DateTime<Utc>,
+  weather: &(impl WindSource + ?Sized),
+  terrain: &dyn Terrain,
+) -> Result<LaunchSite, PredictionError> {
+  let mut params = LaunchParams {
+    launch_time: launch_time.to_rfc3339_opts(SecondsFormat::Secs, true),
+    ..params.clone()
+  };
+  move_launch(&mut params, target.0, target.1, terrain);
+  let from_target = run_prediction_simulation(&params, weather, terrain)?;
+  let (lat, lon) = trace_back(
+    target,
+    &from_target,
+    launch_time.timestamp() as f64,
+    weather,
+    params.integration.time_step,
+  );
+  move_launch(&mut params, lat, lon, terrain);
+
+  let mut best: Option<LaunchSite> = None;
+  for iterations in 1..=MAX_CHECKS {
+    let prediction = run_prediction_simulation(&params, weather, terrain)?;
+    let landing = prediction.landing_point;
+    let miss = geodesy::inverse(landing.lat, landing.lon, target.0, target.1);
+    if best
+      .as_ref()
+      .is_some_and(|best| best.landing_error <= miss.distance)
+    {
+      break;
+    }
+    best = Some(LaunchSite {
+      launch_time: params.launch_time.clone(),
+      lat: params.lat,
+      lon: params.lon,
+      landing_error: miss.distance,
+      iterations,
+      forecast_exceeded: prediction.forecast_exceeded_at.is_some(),
+      prediction,
+    });
+    if miss.distance <= LANDING_TOLERANCE_M {
+      break;
+    }
+    // Nearby launches drift alike: move the launch by the miss.
+    let shifted = geodesy::direct(params.lat, params.lon, miss.initial_bearing, miss.distance);
+    move_launch(&mut params, shifted.lat, shifted.lon, terrain);
+  }
+  Ok(best.expect("at least one forward check runs"))
+}
+
+/// Solves every launch time in the window in parallel and ranks the sites.
+/// `params` supplies the balloon, rates and profile; its site, altitude and
+/// launch time are ignored. Launch times whose search fails are counted and
+/// left out, unless every one fails.
+pub fn run_reverse_simulation(
+  params: &LaunchParams,
+  settings: &ReverseSettings,
+  weather: &(impl WindSource + Sync + ?Sized),
+  terrain: &(dyn Terrain + Sync),
+) -> Result<ReverseResult, PredictionError> {
+  if !(-90.0..=90.0).contains(&settings.target_lat)
+    || !(-180.0..=180.0).contains(&settings.target_lon)
+  {
+    return Err(PredictionError::InvalidParams(
+      "landing target is out of range",
+    ));
+  }
+  let target = (settings.target_lat, settings.target_lon);
+  let results: Vec<Result<LaunchSite, PredictionError>> = window_times(settings)?
+    .into_par_iter()
+    .map(|launch_time| solve(params, target, launch_time, weather, terrain))
+    .collect();
+  let failed_launch_times = results.iter().filter(|r| r.is_err()).count();
+  let mut sites = Vec::with_capacity(results.len());
+  let mut first_error = None;
+  for result in results {
+    match result {
+      Ok(site) => sites.push(site),
+      Err(error) => {
+        first_error.get_or_insert(error);
+      }
+    }
+  }
+  if let (true, Some(error)) = (sites.is_empty(), first_error) {
+    return Err(error);
+  }
+  rank(&mut sites);
+  Ok(ReverseResult {
+    sites,
+    failed_launch_times,
+  })
+}
+
+/// Ranks launch sites for landing at a target. The forecast at the target
+/// is fetched to cover every flight in the window, and the search is
+/// repeated on a wind field covering the candidate flights. The field is
+/// forecast from the start of the window, so a launch time whose flight
+/// outlasts it keeps its site from the target forecast.
+#[tauri::command]
+pub async fn reverse_prediction(
+  cache: State<'_, ForecastCache>,
+  dem: State<'_, DemProvider>,
+  params: LaunchParams,
+  settings: ReverseSettings,
+  grib_files: Option<Vec<String>>,
+  ground_elevation: Option<f64>,
+) -> Result<ReverseResult, PredictionError> {
+  let at_target = LaunchParams {
+    lat: settings.target_lat,
+    lon: settings.target_lon,
+    launch_time: settings.window_start.clone(),
+    ..params.clone()
+  };
+  let grid = match grib_files.clone() {
+    Some(paths) => resolve_weather(&cache, &at_target, None, Some(paths)).await?,
+    None => {
+      let (start, end) = window_bounds(&settings)?;
+      let last_landing = end + Duration::seconds(MAX_FLIGHT_TIME_S as i64);
+      weather::fetch_gfs_range_cached(
+        &cache,
+        settings.target_lat,
+        settings.target_lon,
+        start,
+        last_landing,
+      )
+      .await?
+    }
+  };
+  let terrain = DemTerrain {
+    dem: dem.inner().clone(),
+    fallback: ground_elevation.unwrap_or(0.0),
+  };
+
+  let first_pass = {
+    let (params, settings, terrain) = (params.clone(), settings.clone(), terrain.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+      run_reverse_simulation(&params, &settings, &grid, &terrain)
+    })
+    .await
+    .map_err(|e| PredictionError::Simulation(e.to_string()))??
+  };
+
+  let tracks: Vec<FlightPoint> = first_pass
+    .sites
+    .iter()
+    .flat_map(|site| site.prediction.path.iter().copied())
+    .collect();
+  let field = match resolve_wind_field(&cache, &at_target, &tracks, grib_files).await {
+    Ok(field) => field,
+    Err(error) => {
+      log::warn!(
+        "Searching launch sites on the target forecast only: {}",
+        error
+      );
+      let mut result = first_pass;
+      for site in &mut result.sites {
+        site.prediction.wind_field_error = Some(error.to_string());
+      }
+      return Ok(result);
+    }
+  };
+  let mut result = tauri::async_runtime::spawn_blocking(move || {
+    run_reverse_simulation(&params, &settings, &field, &terrain)
+  })
+  .await
+  .map_err(|e| PredictionError::Simulation(e.to_string()))??;
+  for site in &mut result.sites {
+    let within_target_forecast = first_pass
+      .sites
+      .iter()
+      .find(|first| first.launch_time == site.launch_time && !first.forecast_exceeded);
+    match within_target_forecast {
+      Some(first) if site.forecast_exceeded => {
+        *site = first.clone();
+        site.prediction.wind_field_error =
+          Some("the wind field forecast ends before this flight does".into());
+      }
+      _ => site.prediction.wind_field = true,
+    }
+  }
+  rank(&mut result.sites);
+  Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::weather::field::{FieldLayout, WeatherField};
+  use crate::weather::WeatherGrid;
+
+  fn settings(window_end: &str) -> ReverseSettings {
+    ReverseSettings {
+      target_lat: 40.0,
+      target_lon: -104.0,
+      window_start: "2025-07-07T12:00:00Z".into(),
+      window_end: window_end.into(),
+      window_step: 3600.0,
+    }
+  }
+
+  /// The same wind at every level, strengthening by 2 m/s an hour.
+  fn strengthening_weather(lat: f64, lon: f64, direction: f64) -> WeatherGrid {
+    WeatherGrid {
+      latitude: lat,
+      longitude: lon,
+      ..WeatherGrid::strengthening(-16.0, 2.0, direction, 48)
+    }
+  }
+
+  fn params() -> LaunchParams {
+    LaunchParams {
+      lat: 0.0,
+      lon: 0.0,
+      launch_altitude: 0.0,
+      ..LaunchParams::test_default()
+    }
+  }
+
+  #[test]
+  fn finds_upwind_launch_sites() {
+    let weather = strengthening_weather(40.0, -104.0, 270.0);
+    let result =
+      run_reverse_simulation(&params(), &settings("2025-07-07T14:00:00Z"), &weather, &0.0).unwrap();
+    let sites = result.sites;
+
+    assert_eq!(sites.len(), 3);
+    assert_eq!(result.failed_launch_times, 0);
+    assert!(sites
+      .windows(2)
+      .all(|w| w[0].landing_error <= w[1].landing_error));
+    for site in &sites {
+      assert!(
+        site.landing_error < LANDING_TOLERANCE_M,
+        "{:?}",
+        site.landing_error
+      );
+      let landing = site.prediction.landing_point;
+      assert!(geodesy::distance(landing.lat, landing.lon, 40.0, -104.0) < LANDING_TOLERANCE_M);
+      // A westerly: launch due west of the target.
+      assert!(site.lon < -104.5 && (site.lat - 40.0).abs() < 0.01);
+    }
+    // Later launches meet stronger winds and start further away.
+    let launch_at = |hour: &str| {
+      sites
+        .iter()
+        .find(|s| s.launch_time == format!("2025-07-07T{}:00:00Z", hour))
+        .unwrap()
+        .lon
+    };
+    assert!(launch_at("14") < launch_at("13") && launch_at("13") < launch_at("12"));
+  }
+
+  #[test]
+  fn traces_back_through_a_sheared_field() {
+    // A westerly that strengthens eastwards, so the drift depends on where
+    // the balloon flies and differs from that of a launch at the target.
+    let layout = FieldLayout {
+      lats: vec![39.0, 41.0],
+      lons: vec![-107.0, -103.0],
+    };
+    let columns = layout
+      .points()
+      .into_iter()
+      .map(|(lat, lon)| {
+        let mut grid = strengthening_weather(lat, lon, 270.0);
+        for sample in grid.samples.iter_mut().flatten() {
+          sample.wind_speed = if lon < -105.0 { 4.0 } else { 12.0 };
+        }
+        grid
+      })
+      .collect();
+    let field = WeatherField::new(layout, columns).unwrap();
+    let result =
+      run_reverse_simulation(&params(), &settings("2025-07-07T12:00:00Z"), &field, &0.0).unwrap();
+
+    let site = &result.sites[0];
+    assert!(
+      site.landing_error < LANDING_TOLERANCE_M,
+      "{:?}",
+      site.landing_error
+    );
+    assert_eq!(site.prediction.launch_point.lat, site.lat);
+  }
+
+  #[test]
+  fn rejects_bad_windows() {
+    let weather = strengthening_weather(40.0, -104.0, 270.0);
+    let backwards = settings("2025-07-07T11:00:00Z");
+    assert!(matches!(
+      run_reverse_simulation(&params(), &backwards, &weather, &0.0),
+      Err(PredictionError::InvalidParams(_))
+    ));
+    let too_long = ReverseSettings {
+      window_step: 60.0,
+      ..settings("2025-07-08T12:00:00Z")
+    };
+    assert!(matches!(
+      run_reverse_simulation(&params(), &too_long, &weather, &0.0),
+      Err(PredictionError::InvalidParams(_))
+    ));
+  }
+
+  /// Terrain varying with longitude only.
+  struct Ground(fn(f64) -> f64);
+
+  impl Terrain for Ground {
+    fn elevation(&self, _lat: f64, lon: f64) -> f64 {
+      (self.0)(lon)
+    }
+  }
+
+  #[test]
+  fn launches_from_the_ground_at_each_site() {
+    // Rising westwards from 1500 m at the target.
+    let ground = Ground(|lon| 1500.0 + (-104.0 - lon) * 1000.0);
+    let weather = strengthening_weather(40.0, -104.0, 270.0);
+    let result = run_reverse_simulation(
+      &params(),
+      &settings("2025-07-07T13:00:00Z"),
+      &weather,
+      &ground,
+    )
+    .unwrap();
+
+    for site in &result.sites {
+      let launch = site.prediction.launch_point;
+      assert_eq!(launch.altitude, ground.elevation(site.lat, site.lon));
+      assert!(launch.altitude > 2000.0, "{}", launch.altitude);
+      assert!(site.landing_error < LANDING_TOLERANCE_M);
+    }
+  }
+
+  #[test]
+  fn ranks_sites_past_the_forecast_last() {
+    // The forecast ends at 15:00, before the later flights land.
+    let weather = WeatherGrid {
+      latitude: 40.0,
+      longitude: -104.0,
+      ..WeatherGrid::strengthening(-16.0, 2.0, 270.0, 16)
+    };
+    let sites =
+      run_reverse_simulation(&params(), &settings("2025-07-07T14:00:00Z"), &weather, &0.0)
+        .unwrap()
+        .sites;
+
+    let exceeded: Vec<bool> = sites.iter().map(|s| s.forecast_exceeded).collect();
+    assert_eq!(exceeded, [false, true, true]);
+    assert_eq!(sites[0].launch_time, "2025-07-07T12:00:00Z");
+    assert!(sites[1..]
+      .iter()
+      .all(|s| s.prediction.forecast_exceeded_at.is_some()));
+  }
+
+  #[test]
+  fn skips_launch_times_that_fail() {
+    // No terrain data west of 105.4°W: the 14:00 site, furthest upwind,
+    // cannot be launched from.
+    let ground = Ground(|lon| if lon < -105.4 { f64::NAN } else { 0.0 });
+    let weather = strengthening_weather(40.0, -104.0, 270.0);
+    let result = run_reverse_simulation(
+      &params(),
+      &settings("2025-07-07T14:00:00Z"),
+      &weather,
+      &ground,
+    )
+    .unwrap();
+    assert_eq!(result.failed_launch_times, 1);
+    assert_eq!(result.sites.len(), 2);
+    assert!(result
+      .sites
+      .iter()
+      .all(|s| s.launch_time != "2025-07-07T14:00:00Z"));
+
+    // With every launch time failing, the reason is reported.
+    let ground = Ground(|lon| if lon < -104.5 { f64::NAN } else { 0.0 });
+    assert!(matches!(
+      run_reverse_simulation(
+        &params(),
+        &settings("2025-07-07T14:00:00Z"),
+        &weather,
+        &ground
+      ),
+      Err(PredictionError::InvalidParams(_))
+    ));
+  }
+}
@@ -0,0 +1,839 @@
+//! Tawhiri-compatible prediction API.
+//!
+//! Scripts and chase-car dashboards written against the CUSF Tawhiri API can
+//! point at BLiPS instead: when enabled, an HTTP server on localhost answers
+//! `GET /api/v1/?launch_latitude=…&profile=standard_profile` with the native
+//! prediction engine, in Tawhiri's JSON format. The standard profile (ascent,
+//! burst, descent) and the float profile (ascent, then a float until
+//! `stop_datetime`) are supported. As in Tawhiri, longitudes are reported in
+//! 0-360° and a launch without an altitude starts from the ground.
+//!
+//! Requests are answered by a small fixed pool of worker threads, so a
+//! burst of requests queues rather than spawning a thread each. Browsers on
+//! other origins are only let in when the origin is on the allowlist given
+//! at start; other origins are refused before any prediction runs, as is any
+//! `Host` other than the loopback address, so a DNS-rebinding page cannot
+//! reach the API either.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::ascent::MAX_BURST_ALTITUDE_M;
+use crate::elevation::dem::DemProvider;
+use crate::elevation::{DemTerrain, Terrain};
+use crate::prediction::{
+  predict, FloatProfile, IntegrationSettings, LaunchParams, PredictionError, PredictionResult,
+};
+use crate::weather::cache::ForecastCache;
+use crate::weather::{parse_time, WeatherError};
+
+/// Port the API listens on unless another is asked for.
+pub const DEFAULT_PORT: u16 = 8000;
+const API_PATH: &str = "/api/v1/";
+/// Threads answering requests; further requests wait for one to be free.
+const WORKERS: usize = 2;
+/// Sink rate passed to the engine for float flights, whose descent after the
+/// cutdown is not reported, m/s.
+const FLOAT_DESCENT_RATE: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Profile {
+  StandardProfile,
+  FloatProfile,
+}
+
+/// A prediction request, echoed back in the response as Tawhiri does.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TawhiriRequest {
+  pub profile: Profile,
+  pub launch_latitude: f64,
+  /// 0-360°
+  pub launch_longitude: f64,
+  pub launch_datetime: String,
+  /// m; the ground at the launch site when not given
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub launch_altitude: Option<f64>,
+  /// m/s
+  pub ascent_rate: f64,
+  /// m; standard profile
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub burst_altitude: Option<f64>,
+  /// Sea-level sink rate, m/s; standard profile
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub descent_rate: Option<f64>,
+  /// m; float profile
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub float_altitude: Option<f64>,
+  /// End of the float; float profile
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub stop_datetime: Option<String>,
+  /// Accepted for compatibility; BLiPS picks the forecast itself
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub dataset: Option<String>,
+  pub version: u32,
+}
+
+impl TawhiriRequest {
+  /// Parses a Tawhiri query string.
+  pub fn parse(query: &str) -> Result<Self, ApiError> {
+    let query: HashMap<String, String> = form_urlencoded::parse(query.as_bytes())
+      .into_owned()
+      .collect();
+    let text = |name: &str| query.get(name).map(|v| v.trim()).filter(|v| !v.is_empty());
+    let required = |name: &str| {
+      text(name)
+        .ok_or_else(|| ApiError::Request(format!("Parameter '{}' not provided in request.", name)))
+    };
+    let number = |name: &str| {
+      text(name)
+        .map(|v| {
+          v.parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .ok_or_else(|| ApiError::Request(format!("Parameter '{}' is not a number.", name)))
+        })
+        .transpose()
+    };
+    let required_number = |name: &str| {
+      required(name)?;
+      number(name).map(|v| v.unwrap_or_default())
+    };
+    let datetime = |name: &str| -> Result<String, ApiError> {
+      let time = parse_time(required(name)?).ok_or_else(|| {
+        ApiError::Request(format!("Parameter '{}' is not an RFC3339 datetime.", name))
+      })?;
+      Ok(format_time(time))
+    };
+
+    if text("version").is_some_and(|v| v != "1") {
+      return Err(ApiError::Request(
+        "Only version 1 of the API is supported.".into(),
+      ));
+    }
+    if text("format").is_some_and(|v| v != "json") {
+      return Err(ApiError::Request(
+        "Only the json format is supported.".into(),
+      ));
+    }
+    let profile = match text("profile").unwrap_or("standard_profile") {
+      "standard_profile" => Profile::StandardProfile,
+      "float_profile" => Profile::FloatProfile,
+      other => {
+        return Err(ApiError::Request(format!("Unknown profile '{}'.", other)));
+      }
+    };
+    let launch_latitude = required_number("launch_latitude")?;
+    let launch_longitude = required_number("launch_longitude")?;
+    if !(-90.0..=90.0).contains(&launch_latitude) || !(-180.0..=360.0).contains(&launch_longitude) {
+      return Err(ApiError::Request(
+        "Launch coordinates are out of range.".into(),
+      ));
+    }
+    let (burst_altitude, descent_rate, float_altitude, stop_datetime) = match profile {
+      Profile::StandardProfile => (
+        Some(required_number("burst_altitude")?),
+        Some(required_number("descent_rate")?),
+        None,
+        None,
+      ),
+      Profile::FloatProfile => (
+        None,
+        None,
+        Some(required_number("float_altitude")?),
+        Some(datetime("stop_datetime")?),
+      ),
+    };
+    Ok(TawhiriRequest {
+      profile,
+      launch_latitude,
+      launch_longitude: launch_longitude.rem_euclid(360.0),
+      launch_datetime: datetime("launch_datetime")?,
+      launch_altitude: number("launch_altitude")?,
+      ascent_rate: required_number("ascent_rate")?,
+      burst_altitude,
+      descent_rate,
+      float_altitude,
+      stop_datetime,
+      dataset: text("dataset").map(String::from),
+      version: 1,
+    })
+  }
+
+  /// The engine's launch parameters, from `launch_altitude` (m).
+  pub fn launch_params(&self, launch_altitude: f64) -> LaunchParams {
+    let float = self.float_altitude.map(|altitude| FloatProfile {
+      altitude,
+      duration: None,
+      end_time: self.stop_datetime.clone(),
+      leak_rate: 0.0,
+    });
+    LaunchParams {
+      lat: self.launch_latitude,
+      lon: wrap_longitude(self.launch_longitude),
+      launch_time: self.launch_datetime.clone(),
+      launch_altitude,
+      ascent_rate: self.ascent_rate,
+      burst_altitude: self.burst_altitude.unwrap_or(MAX_BURST_ALTITUDE_M),
+      balloon: None,
+      descent_rate: self.descent_rate.unwrap_or(FLOAT_DESCENT_RATE),
+      parachute: None,
+      tracking_callsign: None,
+      integration: IntegrationSettings::default(),
+      float,
+      profile: None,
+    }
+  }
+}
+
+/// -180-180°
+fn wrap_longitude(lon: f64) -> f64 {
+  (lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+  time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrajectoryPoint {
+  /// m
+  pub altitude: f64,
+  pub datetime: String,
+  pub latitude: f64,
+  /// 0-360°
+  pub longitude: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Stage {
+  /// `ascent`, `descent` or `float`
+  pub stage: &'static str,
+  pub trajectory: Vec<TrajectoryPoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Metadata {
+  pub start_datetime: String,
+  pub complete_datetime: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Warning {
+  pub count: usize,
+  pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TawhiriResponse {
+  pub metadata: Metadata,
+  pub prediction: Vec<Stage>,
+  pub request: TawhiriRequest,
+  pub warnings: BTreeMap<&'static str, Warning>,
+}
+
+impl TawhiriResponse {
+  /// Splits `result` into Tawhiri's stages: ascent and descent at the burst,
+  /// or ascent and float at the float altitude, ending at the cutdown.
+  pub fn new(
+    request: TawhiriRequest,
+    result: &PredictionResult,
+    started: DateTime<Utc>,
+  ) -> Result<Self, ApiError> {
+    let launch_time = parse_time(&request.launch_datetime)
+      .ok_or_else(|| ApiError::Request("Invalid launch datetime.".into()))?;
+    let trajectory = |from: f64, to: f64| {
+      result
+        .path
+        .iter()
+        .filter(|point| point.time >= from && point.time <= to)
+        .map(|point| TrajectoryPoint {
+          altitude: point.altitude,
+          datetime: format_time(
+            launch_time + chrono::Duration::milliseconds((point.time * 1000.0) as i64),
+          ),
+          latitude: point.lat,
+          longitude: point.lon.rem_euclid(360.0),
+        })
+        .collect()
+    };
+    let prediction = match request.profile {
+      Profile::StandardProfile => {
+        let burst = result.burst_point.time;
+        vec![
+          Stage {
+            stage: "ascent",
+            trajectory: trajectory(0.0, burst),
+          },
+          Stage {
+            stage: "descent",
+            trajectory: trajectory(burst, result.total_time),
+          },
+        ]
+      }
+      Profile::FloatProfile => {
+        let float = result
+          .float_point
+          .map_or(result.total_time, |point| point.time);
+        let stop = result
+          .cutdown_point
+          .map_or(result.total_time, |point| point.time);
+        vec![
+          Stage {
+            stage: "ascent",
+            trajectory: trajectory(0.0, float),
+          },
+          Stage {
+            stage: "float",
+            trajectory: trajectory(float, stop),
+          },
+        ]
+      }
+    };
+    let mut warnings = BTreeMap::new();
+    if let Some(time) = result.forecast_exceeded_at {
+      warnings.insert(
+        "forecast_exceeded",
+        Warning {
+          count: 1,
+          description: format!(
+            "The flight ran past the end of the forecast {:.0} minutes after launch; \
+             from then on it drifts on the last forecast hour's winds.",
+            time / 60.0
+          ),
+        },
+      );
+    }
+    Ok(TawhiriResponse {
+      metadata: Metadata {
+        start_datetime: format_time(started),
+        complete_datetime: format_time(Utc::now()),
+      },
+      prediction,
+      request: TawhiriRequest {
+        launch_altitude: Some(result.launch_point.altitude),
+        ..request
+      },
+      warnings,
+    })
+  }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+  #[error("{0}")]
+  Request(String),
+  #[error("Unknown endpoint '{0}'.")]
+  NotFound(String),
+  #[error("{0}")]
+  Forbidden(String),
+  #[error(transparent)]
+  Prediction(#[from] PredictionError),
+  #[error("could not start the prediction API on port {0}: {1}")]
+  Start(u16, String),
+}
+
+impl ApiError {
+  /// Tawhiri's exception type and HTTP status for the error.
+  fn kind(&self) -> (&'static str, u16) {
+    match self {
+      ApiError::Request(_)
+      | ApiError::Prediction(PredictionError::InvalidParams(_))
+      | ApiError::Prediction(PredictionError::InvalidLaunchTime(_)) => ("RequestException", 400),
+      ApiError::NotFound(_) => ("RequestException", 404),
+      ApiError::Forbidden(_) => ("RequestException", 403),
+      ApiError::Prediction(PredictionError::Weather(
+        WeatherError::NoForecast | WeatherError::NoWindData,
+      )) => ("InvalidDatasetException", 404),
+      ApiError::Prediction(_) => ("PredictionException", 500),
+      ApiError::Start(..) => ("InternalException", 500),
+    }
+  }
+}
+
+impl Serialize for ApiError {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.to_string())
+  }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+  error: ErrorDetail,
+  metadata: Metadata,
+}
+
+#[derive(Serialize)]
+struct ErrorDetail {
+  #[serde(rename = "type")]
+  kind: &'static str,
+  description: String,
+}
+
+/// Answers a request for `url` (path and query), running `predict` on a
+/// valid prediction request. Returns the HTTP status and JSON body.
+pub fn respond(
+  url: &str,
+  predict: impl FnOnce(&TawhiriRequest) -> Result<PredictionResult, ApiError>,
+) -> (u16, String) {
+  let started = Utc::now();
+  let (path, query) = url.split_once('?').unwrap_or((url, ""));
+  let response = if path.trim_end_matches('/') == API_PATH.trim_end_matches('/') {
+    TawhiriRequest::parse(query).and_then(|request| {
+      let result = predict(&request)?;
+      TawhiriResponse::new(request, &result, started)
+    })
+  } else {
+    Err(ApiError::NotFound(path.into()))
+  };
+  match response {
+    Ok(response) => serde_json::to_string(&response)
+      .map(|json| (200, json))
+      .unwrap_or_else(|error| (500, format!("{{\"error\":\"{}\"}}", error))),
+    Err(error) => error_response(&error, started),
+  }
+}
+
+/// The HTTP status and Tawhiri JSON body reporting `error`.
+fn error_response(error: &ApiError, started: DateTime<Utc>) -> (u16, String) {
+  let (kind, status) = error.kind();
+  let body = ErrorBody {
+    error: ErrorDetail {
+      kind,
+      description: error.to_string(),
+    },
+    metadata: Metadata {
+      start_datetime: format_time(started),
+      complete_datetime: format_time(Utc::now()),
+    },
+  };
+  serde_json::to_string(&body)
+    .map(|json| (status, json))
+    .unwrap_or_else(|error| (500, format!("{{\"error\":\"{}\"}}", error)))
+}
+
+/// Checks a request's `Host` and `Origin` headers for the server on `port`.
+/// The host must name the loopback address and port, and a browser origin
+/// must be on `allowed_origins`. Returns the origin to echo in
+/// `Access-Control-Allow-Origin`, if any.
+fn admit(
+  host: Option<&str>,
+  origin: Option<&str>,
+  port: u16,
+  allowed_origins: &[String],
+) -> Result<Option<String>, ApiError> {
+  let local = host.is_some_and(|host| {
+    ["127.0.0.1", "localhost"]
+      .iter()
+      .any(|name| host.eq_ignore_ascii_case(&format!("{}:{}", name, port)))
+  });
+  if !local {
+    return Err(ApiError::Forbidden(format!(
+      "Host '{}' is not this machine.",
+      host.unwrap_or_default()
+    )));
+  }
+  match origin {
+    None => Ok(None),
+    Some(origin) if allowed_origins.iter().any(|allowed| allowed == origin) => {
+      Ok(Some(origin.to_string()))
+    }
+    Some(origin) => Err(ApiError::Forbidden(format!(
+      "Origin '{}' is not allowed to use the prediction API.",
+      origin
+    ))),
+  }
+}
+
+/// Runs `request` through the app's forecast cache and DEM, as
+/// [`run_prediction`](crate::prediction::run_prediction) would.
+fn predict_in_app(app: &AppHandle, request: &TawhiriRequest) -> Result<PredictionResult, ApiError> {
+  let dem = app.state::<DemProvider>().inner().clone();
+  let params = request.launch_params(0.0);
+  let launch_altitude = request.launch_altitude.unwrap_or_else(|| {
+    DemTerrain {
+      dem: dem.clone(),
+      fallback: 0.0,
+    }
+    .elevation(params.lat, params.lon)
+  });
+  let params = LaunchParams {
+    launch_altitude,
+    ..params
+  };
+  let cache = app.state::<ForecastCache>();
+  Ok(tauri::async_runtime::block_on(predict(
+    cache.inner(),
+    &dem,
+    params,
+    None,
+    None,
+    None,
+  ))?)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiStatus {
+  pub running: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub port: Option<u16>,
+  /// Endpoint to point Tawhiri clients at
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub url: Option<String>,
+}
+
+struct Running {
+  server: Arc<tiny_http::Server>,
+  workers: Vec<JoinHandle<()>>,
+  port: u16,
+}
+
+/// The embedded server, stopped until asked to start.
+#[derive(Default)]
+pub struct PredictionApi {
+  running: Mutex<Option<Running>>,
+}
+
+impl PredictionApi {
+  fn running(&self) -> std::sync::MutexGuard<'_, Option<Running>> {
+    self
+      .running
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  /// Listens on localhost `port` (any free port for 0), answering requests
+  /// on `WORKERS` threads with `handler`, which returns the HTTP status and
+  /// JSON body for a URL. Requests that do not address the loopback host, or
+  /// come from a browser origin not in `allowed_origins`, are refused with
+  /// 403 without reaching `handler`. A running server is stopped first.
+  pub fn start(
+    &self,
+    port: u16,
+    allowed_origins: Vec<String>,
+    handler: impl Fn(&str) -> (u16, String) + Send + Sync + 'static,
+  ) -> Result<ApiStatus, ApiError> {
+    let mut running = self.running();
+    if let Some(previous) = running.take() {
+      Self::shut_down(previous);
+    }
+    let server = tiny_http::Server::http(("127.0.0.1", port))
+      .map_err(|error| ApiError::Start(port, error.to_string()))?;
+    let port = server
+      .server_addr()
+      .to_ip()
+      .map_or(port, |address| address.port());
+    let server = Arc::new(server);
+    let handler = Arc::new(handler);
+    let allowed_origins = Arc::new(allowed_origins);
+    let workers = (0..WORKERS)
+      .map(|_| {
+        let (server, handler, allowed_origins) =
+          (server.clone(), handler.clone(), allowed_origins.clone());
+        std::thread::spawn(move || {
+          for request in server.incoming_requests() {
+            let field = |name: &'static str| {
+              request
+                .headers()
+                .iter()
+                .find(|h| h.field.equiv(name))
+                .map(|h| h.value.as_str())
+            };
+            let admitted = admit(field("Host"), field("Origin"), port, &allowed_origins);
+            let (origin, (status, body)) = match admitted {
+              Ok(origin) => (origin, handler(request.url())),
+              Err(error) => (None, error_response(&error, Utc::now())),
+            };
+            let mut response = tiny_http::Response::from_string(body)
+              .with_status_code(status)
+              .with_header(header("Content-Type", "application/json"))
+              .with_header(header("Vary", "Origin"));
+            if let Some(origin) = origin {
+              response.add_header(header("Access-Control-Allow-Origin", &origin));
+            }
+            if let Err(error) = request.respond(response) {
+              log::warn!("Prediction API response failed: {}", error);
+            }
+          }
+        })
+      })
+      .collect();
+    *running = Some(Running {
+      server,
+      workers,
+      port,
+    });
+    Ok(Self::status_of(running.as_ref()))
+  }
+
+  pub fn stop(&self) -> ApiStatus {
+    if let Some(running) = self.running().take() {
+      Self::shut_down(running);
+    }
+    Self::status_of(None)
+  }
+
+  pub fn status(&self) -> ApiStatus {
+    Self::status_of(self.running().as_ref())
+  }
+
+  fn shut_down(running: Running) {
+    // Each unblock releases one waiting worker.
+    for _ in &running.workers {
+      running.server.unblock();
+    }
+    for worker in running.workers {
+      if worker.join().is_err() {
+        log::warn!("Prediction API worker thread panicked");
+      }
+    }
+  }
+
+  fn status_of(running: Option<&Running>) -> ApiStatus {
+    ApiStatus {
+      running: running.is_some(),
+      port: running.map(|r| r.port),
+      url: running.map(|r| format!("http://127.0.0.1:{}{}", r.port, API_PATH)),
+    }
+  }
+}
+
+fn header(name: &str, value: &str) -> tiny_http::Header {
+  tiny_http::Header::from_bytes(name.as_bytes(), value.as_bytes())
+    .expect("header names are static and values come from a parsed header")
+}
+
+/// Starts the Tawhiri-compatible API on localhost `port` (`DEFAULT_PORT`
+/// when not given). Web pages may call it only from `allowed_origins`, e.g.
+/// `http://localhost:3000` for a local dashboard; none are allowed by
+/// default.
+#[tauri::command]
+pub fn start_prediction_api(
+  app: AppHandle,
+  api: State<'_, PredictionApi>,
+  port: Option<u16>,
+  allowed_origins: Option<Vec<String>>,
+) -> Result<ApiStatus, ApiError> {
+  api.start(
+    port.unwrap_or(DEFAULT_PORT),
+    allowed_origins.unwrap_or_default(),
+    move |url| respond(url, |request| predict_in_app(&app, request)),
+  )
+}
+
+#[tauri::command]
+pub fn stop_prediction_api(api: State<'_, PredictionApi>) -> ApiStatus {
+  api.stop()
+}
+
+#[tauri::command]
+pub fn prediction_api_status(api: State<'_, PredictionApi>) -> ApiStatus {
+  api.status()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::{Read, Write};
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  use super::*;
+  use crate::prediction::run_prediction_simulation;
+  use crate::weather::WeatherGrid;
+
+  const STANDARD: &str = "launch_latitude=52.2&launch_longitude=359.9\
+    &launch_datetime=2025-07-07T12%3A00%3A00Z&launch_altitude=100&ascent_rate=5\
+    &burst_altitude=30000&descent_rate=6&profile=standard_profile";
+  const FLOAT: &str = "launch_latitude=52.2&launch_longitude=0.1\
+    &launch_datetime=2025-07-07T12:00:00Z&ascent_rate=4&float_altitude=15000\
+    &stop_datetime=2025-07-07T20:00:00Z&profile=float_profile";
+
+  fn simulate(request: &TawhiriRequest) -> Result<PredictionResult, ApiError> {
+    let params = request.launch_params(request.launch_altitude.unwrap_or(0.0));
+    Ok(run_prediction_simulation(
+      &params,
+      &WeatherGrid::uniform(10.0, 270.0, 48),
+      &0.0,
+    )?)
+  }
+
+  #[test]
+  fn parses_standard_and_float_requests() {
+    let standard = TawhiriRequest::parse(STANDARD).unwrap();
+    assert_eq!(standard.profile, Profile::StandardProfile);
+    assert_eq!(standard.launch_datetime, "2025-07-07T12:00:00Z");
+    assert_eq!(standard.burst_altitude, Some(30000.0));
+    let params = standard.launch_params(100.0);
+    assert!((params.lon + 0.1).abs() < 1e-9);
+    assert_eq!(params.descent_rate, 6.0);
+    assert!(params.float.is_none());
+
+    let float = TawhiriRequest::parse(FLOAT).unwrap();
+    assert_eq!(float.launch_altitude, None);
+    let params = float.launch_params(0.0);
+    let profile = params.float.unwrap();
+    assert_eq!(profile.altitude, 15000.0);
+    assert_eq!(profile.end_time.as_deref(), Some("2025-07-07T20:00:00Z"));
+
+    for (query, missing) in [
+      (
+        STANDARD.replace("&burst_altitude=30000", ""),
+        "burst_altitude",
+      ),
+      (
+        FLOAT.replace("&stop_datetime=2025-07-07T20:00:00Z", ""),
+        "stop_datetime",
+      ),
+    ] {
+      let error = TawhiriRequest::parse(&query).unwrap_err();
+      assert!(error.to_string().contains(missing), "{}", error);
+    }
+    assert!(
+      TawhiriRequest::parse(&STANDARD.replace("standard_profile", "reverse_profile")).is_err()
+    );
+    assert!(TawhiriRequest::parse(&STANDARD.replace("52.2", "95")).is_err());
+  }
+
+  #[test]
+  fn answers_in_tawhiri_stages() {
+    let (status, body) = respond(&format!("/api/v1/?{}", STANDARD), simulate);
+    assert_eq!(status, 200, "{}", body);
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let stages = json["prediction"].as_array().unwrap();
+    assert_eq!(stages[0]["stage"], "ascent");
+    assert_eq!(stages[1]["stage"], "descent");
+    let ascent = stages[0]["trajectory"].as_array().unwrap();
+    let descent = stages[1]["trajectory"].as_array().unwrap();
+    assert_eq!(ascent[0]["datetime"], "2025-07-07T12:00:00Z");
+    assert_eq!(ascent.last().unwrap()["altitude"], 30000.0);
+    assert_eq!(descent[0], *ascent.last().unwrap());
+    assert_eq!(descent.last().unwrap()["altitude"], 0.0);
+    // Blown east across the prime meridian, in 0-360°.
+    let landing = descent.last().unwrap()["longitude"].as_f64().unwrap();
+    assert!(landing > 0.0 && landing < 10.0, "{}", landing);
+    assert_eq!(json["request"]["launch_longitude"], 359.9);
+
+    let (status, body) = respond(&format!("/api/v1?{}", FLOAT), simulate);
+    assert_eq!(status, 200, "{}", body);
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let stages = json["prediction"].as_array().unwrap();
+    assert_eq!(stages[1]["stage"], "float");
+    let float = stages[1]["trajectory"].as_array().unwrap();
+    assert_eq!(float[0]["altitude"], 15000.0);
+    assert_eq!(float.last().unwrap()["datetime"], "2025-07-07T20:00:00Z");
+    assert_eq!(json["request"]["launch_altitude"], 0.0);
+  }
+
+  #[test]
+  fn reports_errors_as_tawhiri_exceptions() {
+    let (status, body) = respond("/api/v1/?launch_latitude=52", simulate);
+    assert_eq!(status, 400);
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["error"]["type"], "RequestException");
+
+    let (status, body) = respond(&format!("/api/v1/?{}", STANDARD), |_| {
+      Err(PredictionError::from(WeatherError::NoForecast).into())
+    });
+    assert_eq!(status, 404);
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["error"]["type"], "InvalidDatasetException");
+
+    let (status, _) = respond("/api/v2/", simulate);
+    assert_eq!(status, 404);
+  }
+
+  /// Sends a GET for `/api/v1/?a=1` to the API on `port` with the given
+  /// `Host` and `Origin` headers, returning the raw response.
+  fn get(port: u16, host: &str, origin: Option<&str>) -> String {
+    let mut stream = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let origin = origin.map_or(String::new(), |origin| format!("Origin: {}\r\n", origin));
+    write!(
+      stream,
+      "GET /api/v1/?a=1 HTTP/1.1\r\nHost: {}\r\n{}Connection: close\r\n\r\n",
+      host, origin
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+  }
+
+  #[test]
+  fn serves_on_localhost_until_stopped() {
+    let api = PredictionApi::default();
+    let status = api
+      .start(0, vec!["http://localhost:3000".into()], |url| {
+        (200, format!("{{\"url\":\"{}\"}}", url))
+      })
+      .unwrap();
+    let port = status.port.unwrap();
+    assert!(status.running);
+    assert_eq!(
+      status.url.as_deref(),
+      Some(format!("http://127.0.0.1:{}/api/v1/", port).as_str())
+    );
+
+    let response = get(
+      port,
+      &format!("localhost:{}", port),
+      Some("http://localhost:3000"),
+    );
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(response.contains("Access-Control-Allow-Origin: http://localhost:3000"));
+    assert!(response.contains("application/json"));
+    assert!(
+      response.ends_with("{\"url\":\"/api/v1/?a=1\"}"),
+      "{}",
+      response
+    );
+    // Scripts and curl send no origin and need no CORS header.
+    let response = get(port, &format!("127.0.0.1:{}", port), None);
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(!response.contains("Access-Control-Allow-Origin"));
+
+    assert!(!api.stop().running);
+    assert_eq!(api.status().port, None);
+  }
+
+  #[test]
+  fn refuses_foreign_origins_and_hosts() {
+    let api = PredictionApi::default();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let port = api
+      .start(0, vec!["http://localhost:3000".into()], move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        (200, "{}".into())
+      })
+      .unwrap()
+      .port
+      .unwrap();
+
+    let host = format!("localhost:{}", port);
+    let rebound = format!("evil.example:{}", port);
+    for (host, origin) in [
+      // A page on another site calling the API directly.
+      (host.as_str(), Some("http://evil.example")),
+      // A DNS-rebinding page: its own name resolving to 127.0.0.1.
+      (rebound.as_str(), Some("http://evil.example:8000")),
+      (rebound.as_str(), None),
+      // The right name on the wrong port.
+      ("localhost:1", None),
+    ] {
+      let response = get(port, host, origin);
+      assert!(response.starts_with("HTTP/1.1 403"), "{}", response);
+      assert!(!response.contains("Access-Control-Allow-Origin"));
+      assert!(response.contains("RequestException"), "{}", response);
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    assert!(get(port, &host, None).starts_with("HTTP/1.1 200"));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    api.stop();
+  }
+}
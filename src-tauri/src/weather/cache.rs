@@ -0,0 +1,387 @@
+//! On-disk forecast cache.
+//!
+//! Forecasts are stored in an SQLite database in the app data directory,
+//! keyed by model, model run, 0.25° lat/lon tile and forecast start date. A
+//! re-run for the same site and GFS cycle is served from disk instead of
+//! hitting Open-Meteo again. An entry expires once the next GFS run is
+//! expected to be available, but stays on disk as the fallback for when that
+//! run cannot be fetched.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, DurationRound, NaiveDate, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use super::{WeatherError, WeatherGrid};
+
+pub const GFS_MODEL: &str = "gfs";
+/// Wind-only GFS columns fetched for a wind field
+pub const GFS_WINDS_MODEL: &str = "gfs_winds";
+/// GFS columns running past the launch day, fetched to sweep a launch window
+pub const GFS_WINDOW_MODEL: &str = "gfs_window";
+pub const GEFS_MODEL: &str = "gefs";
+
+/// Hours between GFS cycles.
+const GFS_CYCLE_HOURS: i64 = 6;
+/// Typical delay between a GFS cycle time and Open-Meteo serving it.
+const GFS_AVAILABILITY_DELAY_HOURS: i64 = 5;
+/// Cache tiles match the 0.25° GFS grid.
+const TILE_DEGREES: f64 = 0.25;
+/// Entries fetched longer ago than this are removed by
+/// [`ForecastCache::purge`], even the newest run for their tile.
+const MAX_AGE_HOURS: i64 = 48;
+
+/// Identifies one cached forecast.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheKey {
+  pub model: String,
+  /// Model cycle time
+  pub run_time: DateTime<Utc>,
+  /// Tile indices (latitude and longitude divided by the tile size)
+  pub tile_lat: i32,
+  pub tile_lon: i32,
+  /// First forecast day requested
+  pub start_date: NaiveDate,
+}
+
+impl CacheKey {
+  /// Key for the newest GFS run available at `now` for a launch.
+  pub fn gfs(lat: f64, lon: f64, launch_time: DateTime<Utc>, now: DateTime<Utc>) -> Self {
+    Self::for_model(GFS_MODEL.to_string(), lat, lon, launch_time, now)
+  }
+
+  /// Key for a wind-only column of the run matching [`CacheKey::gfs`].
+  pub fn gfs_winds(lat: f64, lon: f64, launch_time: DateTime<Utc>, now: DateTime<Utc>) -> Self {
+    Self::for_model(GFS_WINDS_MODEL.to_string(), lat, lon, launch_time, now)
+  }
+
+  /// Key for a launch window forecast from the run matching
+  /// [`CacheKey::gfs`], starting on the day of `start`.
+  pub fn gfs_window(lat: f64, lon: f64, start: DateTime<Utc>, now: DateTime<Utc>) -> Self {
+    Self::for_model(GFS_WINDOW_MODEL.to_string(), lat, lon, start, now)
+  }
+
+  /// Key for one member of the GEFS run matching [`CacheKey::gfs`]; member 0
+  /// is the control run.
+  pub fn gefs(
+    member: usize,
+    lat: f64,
+    lon: f64,
+    launch_time: DateTime<Utc>,
+    now: DateTime<Utc>,
+  ) -> Self {
+    let model = format!("{}_m{:02}", GEFS_MODEL, member);
+    Self::for_model(model, lat, lon, launch_time, now)
+  }
+
+  fn for_model(
+    model: String,
+    lat: f64,
+    lon: f64,
+    launch_time: DateTime<Utc>,
+    now: DateTime<Utc>,
+  ) -> Self {
+    CacheKey {
+      model,
+      run_time: latest_gfs_run(now),
+      tile_lat: (lat / TILE_DEGREES).round() as i32,
+      tile_lon: (lon / TILE_DEGREES).round() as i32,
+      start_date: launch_time.date_naive(),
+    }
+  }
+
+  /// When the next model run supersedes this one.
+  pub fn expires_at(&self) -> DateTime<Utc> {
+    self.run_time + Duration::hours(GFS_CYCLE_HOURS + GFS_AVAILABILITY_DELAY_HOURS)
+  }
+}
+
+/// Metadata for one cached forecast.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedForecast {
+  #[serde(flatten)]
+  pub key: CacheKey,
+  pub latitude: f64,
+  pub longitude: f64,
+  pub fetched_at: DateTime<Utc>,
+  pub expires_at: DateTime<Utc>,
+  pub size_bytes: usize,
+}
+
+/// The most recent GFS cycle expected to be available at `now`.
+pub fn latest_gfs_run(now: DateTime<Utc>) -> DateTime<Utc> {
+  (now - Duration::hours(GFS_AVAILABILITY_DELAY_HOURS))
+    .duration_trunc(Duration::hours(GFS_CYCLE_HOURS))
+    .expect("six hours divides a day")
+}
+
+fn cache_error(error: impl std::fmt::Display) -> WeatherError {
+  WeatherError::Cache(error.to_string())
+}
+
+fn from_timestamp(seconds: i64) -> DateTime<Utc> {
+  Utc.timestamp_opt(seconds, 0).single().unwrap_or_default()
+}
+
+pub struct ForecastCache {
+  conn: Mutex<Connection>,
+}
+
+impl ForecastCache {
+  /// Opens (or creates) the cache database in `dir`.
+  pub fn open(dir: &Path) -> Result<Self, WeatherError> {
+    std::fs::create_dir_all(dir).map_err(cache_error)?;
+    Self::with_connection(Connection::open(dir.join("forecast_cache.sqlite")).map_err(cache_error)?)
+  }
+
+  #[cfg(test)]
+  pub fn open_in_memory() -> Result<Self, WeatherError> {
+    Self::with_connection(Connection::open_in_memory().map_err(cache_error)?)
+  }
+
+  fn with_connection(conn: Connection) -> Result<Self, WeatherError> {
+    conn
+      .execute_batch(
+        "CREATE TABLE IF NOT EXISTS forecasts (
+          model TEXT NOT NULL,
+          run_time INTEGER NOT NULL,
+          tile_lat INTEGER NOT NULL,
+          tile_lon INTEGER NOT NULL,
+          start_date TEXT NOT NULL,
+          latitude REAL NOT NULL,
+          longitude REAL NOT NULL,
+          fetched_at INTEGER NOT NULL,
+          expires_at INTEGER NOT NULL,
+          grid TEXT NOT NULL,
+          PRIMARY KEY (model, run_time, tile_lat, tile_lon, start_date)
+        );",
+      )
+      .map_err(cache_error)?;
+    Ok(ForecastCache {
+      conn: Mutex::new(conn),
+    })
+  }
+
+  fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+    self
+      .conn
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+
+  /// Returns the cached grid for `key` unless it has expired.
+  pub fn get(
+    &self,
+    key: &CacheKey,
+    now: DateTime<Utc>,
+  ) -> Result<Option<WeatherGrid>, WeatherError> {
+    let grid: Option<String> = self
+      .conn()
+      .query_row(
+        "SELECT grid FROM forecasts
+         WHERE model = ?1 AND run_time = ?2 AND tile_lat = ?3 AND tile_lon = ?4
+           AND start_date = ?5 AND expires_at > ?6",
+        params![
+          key.model,
+          key.run_time.timestamp(),
+          key.tile_lat,
+          key.tile_lon,
+          key.start_date.to_string(),
+          now.timestamp()
+        ],
+        |row| row.get(0),
+      )
+      .optional()
+      .map_err(cache_error)?;
+    grid
+      .map(|g| serde_json::from_str(&g).map_err(cache_error))
+      .transpose()
+  }
+
+  /// Returns the newest cached run for `key`'s model, tile and start date,
+  /// expired or not, with its key.
+  pub fn latest(&self, key: &CacheKey) -> Result<Option<(CacheKey, WeatherGrid)>, WeatherError> {
+    let row: Option<(i64, String)> = self
+      .conn()
+      .query_row(
+        "SELECT run_time, grid FROM forecasts
+         WHERE model = ?1 AND tile_lat = ?2 AND tile_lon = ?3 AND start_date = ?4
+         ORDER BY run_time DESC LIMIT 1",
+        params![
+          key.model,
+          key.tile_lat,
+          key.tile_lon,
+          key.start_date.to_string()
+        ],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+      )
+      .optional()
+      .map_err(cache_error)?;
+    row
+      .map(|(run_time, grid)| {
+        let key = CacheKey {
+          run_time: from_timestamp(run_time),
+          ..key.clone()
+        };
+        Ok((key, serde_json::from_str(&grid).map_err(cache_error)?))
+      })
+      .transpose()
+  }
+
+  pub fn put(
+    &self,
+    key: &CacheKey,
+    grid: &WeatherGrid,
+    now: DateTime<Utc>,
+  ) -> Result<CachedForecast, WeatherError> {
+    let json = serde_json::to_string(grid).map_err(cache_error)?;
+    let expires_at = key.expires_at();
+    self
+      .conn()
+      .execute(
+        "INSERT OR REPLACE INTO forecasts
+         (model, run_time, tile_lat, tile_lon, start_date, latitude, longitude, fetched_at, expires_at, grid)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+          key.model,
+          key.run_time.timestamp(),
+          key.tile_lat,
+          key.tile_lon,
+          key.start_date.to_string(),
+          grid.latitude,
+          grid.longitude,
+          now.timestamp(),
+          expires_at.timestamp(),
+          json
+        ],
+      )
+      .map_err(cache_error)?;
+    Ok(CachedForecast {
+      key: key.clone(),
+      latitude: grid.latitude,
+      longitude: grid.longitude,
+      fetched_at: now,
+      expires_at,
+      size_bytes: json.len(),
+    })
+  }
+
+  /// Lists cached forecasts, newest run first.
+  pub fn list(&self) -> Result<Vec<CachedForecast>, WeatherError> {
+    let conn = self.conn();
+    let mut statement = conn
+      .prepare(
+        "SELECT model, run_time, tile_lat, tile_lon, start_date, latitude, longitude,
+                fetched_at, expires_at, length(grid)
+         FROM forecasts ORDER BY run_time DESC, fetched_at DESC",
+      )
+      .map_err(cache_error)?;
+    let rows = statement
+      .query_map([], |row| {
+        let start_date: String = row.get(4)?;
+        Ok(CachedForecast {
+          key: CacheKey {
+            model: row.get(0)?,
+            run_time: from_timestamp(row.get(1)?),
+            tile_lat: row.get(2)?,
+            tile_lon: row.get(3)?,
+            start_date: start_date.parse().unwrap_or_default(),
+          },
+          latitude: row.get(5)?,
+          longitude: row.get(6)?,
+          fetched_at: from_timestamp(row.get(7)?),
+          expires_at: from_timestamp(row.get(8)?),
+          size_bytes: row.get::<_, i64>(9)? as usize,
+        })
+      })
+      .map_err(cache_error)?;
+    rows.collect::<Result<_, _>>().map_err(cache_error)
+  }
+
+  /// Removes runs superseded by a newer cached run of the same forecast and
+  /// anything older than the maximum age, or every entry when `all` is set.
+  /// The newest run for each tile is kept after it expires, as the offline
+  /// fallback. Returns the number removed.
+  pub fn purge(&self, all: bool, now: DateTime<Utc>) -> Result<usize, WeatherError> {
+    let conn = self.conn();
+    let removed = if all {
+      conn.execute("DELETE FROM forecasts", [])
+    } else {
+      conn.execute(
+        "DELETE FROM forecasts
+         WHERE fetched_at <= ?1
+           OR EXISTS (
+             SELECT 1 FROM forecasts AS newer
+             WHERE newer.model = forecasts.model AND newer.tile_lat = forecasts.tile_lat
+               AND newer.tile_lon = forecasts.tile_lon
+               AND newer.start_date = forecasts.start_date
+               AND newer.run_time > forecasts.run_time
+           )",
+        params![(now - Duration::hours(MAX_AGE_HOURS)).timestamp()],
+      )
+    };
+    removed.map_err(cache_error)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::weather::{open_meteo, parse_time};
+
+  fn grid() -> WeatherGrid {
+    open_meteo::parse_response(
+      200,
+      include_str!("../../tests/fixtures/open_meteo_gfs.json"),
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn picks_latest_available_gfs_run() {
+    let now = parse_time("2025-07-07T16:30:00Z").unwrap();
+    assert_eq!(
+      latest_gfs_run(now),
+      parse_time("2025-07-07T06:00:00Z").unwrap()
+    );
+
+    let now = parse_time("2025-07-07T04:59:00Z").unwrap();
+    assert_eq!(
+      latest_gfs_run(now),
+      parse_time("2025-07-06T18:00:00Z").unwrap()
+    );
+  }
+
+  #[test]
+  fn serves_entries_until_the_next_run() {
+    let cache = ForecastCache::open_in_memory().unwrap();
+    let launch = parse_time("2025-07-07T12:00:00Z").unwrap();
+    let now = parse_time("2025-07-07T08:00:00Z").unwrap();
+
+    let key = CacheKey::gfs(40.01, -105.27, launch, now);
+    // Nearby sites share a tile.
+    assert_eq!(key, CacheKey::gfs(40.08, -105.2, launch, now));
+
+    cache.put(&key, &grid(), now).unwrap();
+    assert_eq!(cache.get(&key, now).unwrap(), Some(grid()));
+    assert_eq!(cache.list().unwrap().len(), 1);
+
+    // The 06Z run is out by 11Z, so the 00Z entry is stale.
+    let later = parse_time("2025-07-07T11:00:00Z").unwrap();
+    assert_eq!(cache.get(&key, later).unwrap(), None);
+    assert_ne!(CacheKey::gfs(40.01, -105.27, launch, later), key);
+
+    // A newer run supersedes it; the maximum age removes the last one.
+    let newer = CacheKey::gfs(40.01, -105.27, launch, later);
+    cache.put(&newer, &grid(), later).unwrap();
+    assert_eq!(cache.purge(false, later).unwrap(), 1);
+    assert_eq!(cache.list().unwrap()[0].key, newer);
+    let much_later = later + Duration::hours(MAX_AGE_HOURS);
+    assert_eq!(cache.purge(false, much_later).unwrap(), 1);
+    assert!(cache.list().unwrap().is_empty());
+  }
+}
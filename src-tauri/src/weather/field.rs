@@ -0,0 +1,222 @@
+//! Horizontally varying forecasts.
+//!
+//! A [`WeatherField`] is a regular lat/lon lattice of forecast columns laid
+//! out around a flight corridor, so winds can change along the track
+//! instead of coming from the launch site's column alone.
+
+use super::{WeatherError, WeatherGrid};
+
+/// GFS 0.25° grid spacing the lattice snaps to, degrees.
+const GRID_DEGREES: f64 = 0.25;
+/// Distance kept between the trajectory and the edge of the lattice, degrees.
+const CORRIDOR_MARGIN_DEGREES: f64 = 0.5;
+/// Upper bound on columns along each axis, to keep the forecast request small.
+const MAX_COLUMNS_PER_AXIS: usize = 8;
+
+/// Positions of the forecast columns in a [`WeatherField`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldLayout {
+  /// Ascending latitudes, degrees
+  pub lats: Vec<f64>,
+  /// Ascending longitudes, degrees. Continuous across the antimeridian, so
+  /// values may run past 180.
+  pub lons: Vec<f64>,
+}
+
+impl FieldLayout {
+  /// Covers the bounding box of `track` (latitude, longitude) plus a margin,
+  /// on GFS grid points at most `MAX_COLUMNS_PER_AXIS` to a side.
+  pub fn around(track: impl IntoIterator<Item = (f64, f64)>) -> Option<Self> {
+    let mut track = track.into_iter();
+    let (first_lat, first_lon) = track.next()?;
+    let (mut south, mut north) = (first_lat, first_lat);
+    let (mut west, mut east) = (first_lon, first_lon);
+    for (lat, lon) in track {
+      // Unwrap longitudes relative to the first point of the track.
+      let lon = first_lon + (lon - first_lon + 180.0).rem_euclid(360.0) - 180.0;
+      south = south.min(lat);
+      north = north.max(lat);
+      west = west.min(lon);
+      east = east.max(lon);
+    }
+
+    let mut lats: Vec<f64> = axis(
+      south - CORRIDOR_MARGIN_DEGREES,
+      north + CORRIDOR_MARGIN_DEGREES,
+    )
+    .into_iter()
+    .map(|lat| lat.clamp(-90.0, 90.0))
+    .collect();
+    lats.dedup();
+    let lons = axis(
+      west - CORRIDOR_MARGIN_DEGREES,
+      east + CORRIDOR_MARGIN_DEGREES,
+    );
+    Some(FieldLayout { lats, lons })
+  }
+
+  /// Column positions in row-major `[lat][lon]` order, with longitudes in
+  /// [-180, 180).
+  pub fn points(&self) -> Vec<(f64, f64)> {
+    self
+      .lats
+      .iter()
+      .flat_map(|&lat| {
+        self
+          .lons
+          .iter()
+          .map(move |&lon| (lat, (lon + 180.0).rem_euclid(360.0) - 180.0))
+      })
+      .collect()
+  }
+}
+
+/// Grid-aligned positions from `start` to `end`, spaced a whole number of
+/// grid steps apart.
+fn axis(start: f64, end: f64) -> Vec<f64> {
+  let first = (start / GRID_DEGREES).floor() as i64;
+  let last = (end / GRID_DEGREES).ceil() as i64;
+  let steps = (last - first).max(1) as usize;
+  let stride = steps.div_ceil(MAX_COLUMNS_PER_AXIS - 1);
+  (0..=steps.div_ceil(stride))
+    .map(|i| (first + (i * stride) as i64) as f64 * GRID_DEGREES)
+    .collect()
+}
+
+/// Forecast columns on a lat/lon lattice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeatherField {
+  pub layout: FieldLayout,
+  /// Row-major `[lat][lon]`, matching [`FieldLayout::points`]
+  pub columns: Vec<WeatherGrid>,
+}
+
+impl WeatherField {
+  pub fn new(layout: FieldLayout, columns: Vec<WeatherGrid>) -> Result<Self, WeatherError> {
+    if columns.len() != layout.lats.len() * layout.lons.len() || columns.is_empty() {
+      return Err(WeatherError::Parse(format!(
+        "expected {} forecast columns, got {}",
+        layout.lats.len() * layout.lons.len(),
+        columns.len()
+      )));
+    }
+    Ok(WeatherField { layout, columns })
+  }
+
+  pub fn column(&self, lat_index: usize, lon_index: usize) -> &WeatherGrid {
+    &self.columns[lat_index * self.layout.lons.len() + lon_index]
+  }
+
+  /// The up to four columns surrounding (`lat`, `lon`) and their bilinear
+  /// weights. Points outside the lattice use its nearest edge.
+  pub fn neighbours(&self, lat: f64, lon: f64) -> Vec<(&WeatherGrid, f64)> {
+    let lons = &self.layout.lons;
+    // Bring `lon` into the lattice's unwrapped range.
+    let lon = lons[0] + (lon - lons[0] + 180.0).rem_euclid(360.0) - 180.0;
+    let (row, lat_weight) = bracket(&self.layout.lats, lat);
+    let (col, lon_weight) = bracket(lons, lon);
+
+    let mut corners = Vec::with_capacity(4);
+    for (r, wr) in [(row, 1.0 - lat_weight), (row + 1, lat_weight)] {
+      for (c, wc) in [(col, 1.0 - lon_weight), (col + 1, lon_weight)] {
+        let weight = wr * wc;
+        if weight > 0.0 {
+          corners.push((self.column(r, c), weight));
+        }
+      }
+    }
+    corners
+  }
+}
+
+/// Index of the axis value at or below `value` and the weight of the next
+/// one, clamped to the ends of the axis.
+fn bracket(axis: &[f64], value: f64) -> (usize, f64) {
+  if axis.len() < 2 || value <= axis[0] {
+    return (0, 0.0);
+  }
+  let upper = axis.iter().position(|&a| a > value).unwrap_or(axis.len());
+  if upper == axis.len() {
+    return (axis.len() - 1, 0.0);
+  }
+  let lower = upper - 1;
+  (lower, (value - axis[lower]) / (axis[upper] - axis[lower]))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn grid_at(lat: f64, lon: f64) -> WeatherGrid {
+    WeatherGrid {
+      latitude: lat,
+      longitude: lon,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn lays_out_corridor_on_grid_points() {
+    let layout = FieldLayout::around([(40.0, -105.0), (40.3, -103.9)]).unwrap();
+
+    assert_eq!(
+      layout.lats,
+      vec![39.5, 39.75, 40.0, 40.25, 40.5, 40.75, 41.0]
+    );
+    assert_eq!(layout.lons.first(), Some(&-105.5));
+    assert!(*layout.lons.last().unwrap() >= -103.4);
+    assert!(layout.lons.len() <= MAX_COLUMNS_PER_AXIS);
+    assert!(layout
+      .lons
+      .iter()
+      .all(|l| (l / GRID_DEGREES).fract() == 0.0));
+
+    // A long flight is thinned to a wider spacing.
+    let long = FieldLayout::around([(40.0, -105.0), (41.0, -95.0)]).unwrap();
+    assert!(long.lons.len() <= MAX_COLUMNS_PER_AXIS);
+    assert!(*long.lons.last().unwrap() >= -94.5);
+  }
+
+  #[test]
+  fn layout_crosses_the_antimeridian() {
+    let layout = FieldLayout::around([(-45.0, 179.8), (-45.0, -179.6)]).unwrap();
+
+    assert_eq!(layout.lons.first(), Some(&179.25));
+    assert_eq!(layout.lons.last(), Some(&181.0));
+    let points = layout.points();
+    assert!(points.iter().all(|p| (-180.0..180.0).contains(&p.1)));
+    assert!(points.contains(&(-45.0, -179.0)));
+  }
+
+  #[test]
+  fn weights_surrounding_columns() {
+    let layout = FieldLayout {
+      lats: vec![40.0, 41.0],
+      lons: vec![179.0, 181.0],
+    };
+    let columns = layout
+      .points()
+      .into_iter()
+      .map(|(lat, lon)| grid_at(lat, lon))
+      .collect();
+    let field = WeatherField::new(layout, columns).unwrap();
+
+    let corners = field.neighbours(40.25, -179.5);
+    let total: f64 = corners.iter().map(|c| c.1).sum();
+    assert!((total - 1.0).abs() < 1e-12);
+    let weight = |lat: f64, lon: f64| {
+      corners
+        .iter()
+        .find(|(g, _)| g.latitude == lat && g.longitude == lon)
+        .map(|c| c.1)
+    };
+    assert_eq!(weight(40.0, 179.0), Some(0.75 * 0.25));
+    assert_eq!(weight(41.0, -179.0), Some(0.25 * 0.75));
+
+    // Outside the lattice, the nearest edge column.
+    let corners = field.neighbours(45.0, 170.0);
+    assert_eq!(corners.len(), 1);
+    assert_eq!(corners[0].0.latitude, 41.0);
+    assert_eq!(corners[0].0.longitude, 179.0);
+  }
+}
@@ -0,0 +1,460 @@
+//! Offline GFS winds from GRIB2 files.
+//!
+//! Reads the UGRD, VGRD, HGT and TMP isobaric messages of a locally
+//! downloaded GFS `pgrb2.0p25` / `pgrb2.0p50` file (one file per forecast
+//! hour, as NOMADS serves them) and interpolates them bilinearly to the
+//! launch site (or to every column of a wind field), producing the same
+//! [`WeatherGrid`] the Open-Meteo client returns.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use super::{wind_from_uv, LevelSample, SurfaceSample, WeatherError, WeatherGrid};
+
+/// Fixed surface type for isobaric levels (GRIB2 code table 4.5).
+const ISOBARIC_SURFACE: u8 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+  U,
+  V,
+  Height,
+  Temperature,
+}
+
+impl Field {
+  /// Maps a meteorological (discipline 0) parameter to a field we use.
+  fn from_parameter(category: u8, number: u8) -> Option<Field> {
+    match (category, number) {
+      (0, 0) => Some(Field::Temperature),
+      (2, 2) => Some(Field::U),
+      (2, 3) => Some(Field::V),
+      (3, 5) => Some(Field::Height),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct LevelFields {
+  u: Option<f64>,
+  v: Option<f64>,
+  height: Option<f64>,
+  temperature: Option<f64>,
+}
+
+/// Grid points surrounding the launch site and their bilinear weights.
+#[derive(Debug, Clone, PartialEq)]
+struct Stencil {
+  shape: (usize, usize),
+  corners: Vec<(usize, f64)>,
+}
+
+fn grib_error(error: impl std::fmt::Display) -> WeatherError {
+  WeatherError::Grib(error.to_string())
+}
+
+fn normalize_lon(lon: f64) -> f64 {
+  lon.rem_euclid(360.0)
+}
+
+/// The grid's point coordinates in storage order, longitudes in 0-360°.
+fn grid_points(latlons: impl Iterator<Item = (f32, f32)>) -> Vec<(f64, f64)> {
+  latlons
+    .map(|(la, lo)| (la as f64, normalize_lon(lo as f64)))
+    .collect()
+}
+
+/// Finds the four grid points around (`lat`, `lon`) among the grid's
+/// `points`, as listed by [`grid_points`].
+fn build_stencil(
+  shape: (usize, usize),
+  points: &[(f64, f64)],
+  lat: f64,
+  lon: f64,
+) -> Result<Stencil, WeatherError> {
+  let lon = normalize_lon(lon);
+
+  let below_lat = points
+    .iter()
+    .map(|p| p.0)
+    .filter(|&la| la <= lat)
+    .fold(f64::NAN, f64::max);
+  let above_lat = points
+    .iter()
+    .map(|p| p.0)
+    .filter(|&la| la >= lat)
+    .fold(f64::NAN, f64::min);
+  let west_lon = points
+    .iter()
+    .map(|p| p.1)
+    .filter(|&lo| lo <= lon)
+    .fold(f64::NAN, f64::max);
+  let east_lon = points
+    .iter()
+    .map(|p| p.1)
+    .filter(|&lo| lo >= lon)
+    .fold(f64::NAN, f64::min);
+  if below_lat.is_nan() || above_lat.is_nan() {
+    return Err(WeatherError::Grib(
+      "launch site is outside the GRIB2 grid".into(),
+    ));
+  }
+  // A global grid wraps at the prime meridian.
+  let west_lon = if west_lon.is_nan() {
+    points.iter().map(|p| p.1).fold(f64::NAN, f64::max)
+  } else {
+    west_lon
+  };
+  let east_lon = if east_lon.is_nan() {
+    points.iter().map(|p| p.1).fold(f64::NAN, f64::min)
+  } else {
+    east_lon
+  };
+
+  let lat_weight = if above_lat > below_lat {
+    (lat - below_lat) / (above_lat - below_lat)
+  } else {
+    0.0
+  };
+  let lon_span = (east_lon - west_lon).rem_euclid(360.0);
+  let lon_weight = if lon_span > 0.0 {
+    (lon - west_lon).rem_euclid(360.0) / lon_span
+  } else {
+    0.0
+  };
+
+  let targets = [
+    (below_lat, west_lon, (1.0 - lat_weight) * (1.0 - lon_weight)),
+    (below_lat, east_lon, (1.0 - lat_weight) * lon_weight),
+    (above_lat, west_lon, lat_weight * (1.0 - lon_weight)),
+    (above_lat, east_lon, lat_weight * lon_weight),
+  ];
+  let mut corners = Vec::with_capacity(4);
+  for (target_lat, target_lon, weight) in targets {
+    let index = points
+      .iter()
+      .position(|&(la, lo)| la == target_lat && lo == target_lon)
+      .ok_or_else(|| WeatherError::Grib("GRIB2 grid is not a regular lat/lon grid".into()))?;
+    corners.push((index, weight));
+  }
+  Ok(Stencil { shape, corners })
+}
+
+/// Decodes the wind fields of one or more GFS GRIB2 files into a forecast
+/// column at (`lat`, `lon`).
+pub fn load_gfs(
+  paths: &[impl AsRef<Path>],
+  lat: f64,
+  lon: f64,
+) -> Result<WeatherGrid, WeatherError> {
+  let mut columns = load_gfs_columns(paths, &[(lat, lon)])?;
+  Ok(columns.remove(0))
+}
+
+/// Decodes the wind fields of one or more GFS GRIB2 files into a forecast
+/// column at each of `points` (latitude, longitude), decoding every message
+/// only once.
+pub fn load_gfs_columns(
+  paths: &[impl AsRef<Path>],
+  points: &[(f64, f64)],
+) -> Result<Vec<WeatherGrid>, WeatherError> {
+  // Per point: time -> pressure (hPa) -> fields
+  let mut columns: Vec<BTreeMap<i64, BTreeMap<u32, LevelFields>>> =
+    vec![BTreeMap::new(); points.len()];
+  let mut stencils: Vec<Stencil> = Vec::new();
+
+  for path in paths {
+    let file = File::open(path.as_ref()).map_err(grib_error)?;
+    let grib2 = grib::from_reader(BufReader::new(file)).map_err(grib_error)?;
+
+    for (_, submessage) in grib2.iter() {
+      if submessage.indicator().discipline != 0 {
+        continue;
+      }
+      let prod_def = submessage.prod_def();
+      let (Some(category), Some(number)) =
+        (prod_def.parameter_category(), prod_def.parameter_number())
+      else {
+        continue;
+      };
+      let Some(field) = Field::from_parameter(category, number) else {
+        continue;
+      };
+      let Some((surface, _)) = prod_def.fixed_surfaces() else {
+        continue;
+      };
+      if surface.surface_type != ISOBARIC_SURFACE {
+        continue;
+      }
+      // Levels are stored in Pa; the sub-hPa levels of the 0.25° files are skipped.
+      let pressure_hpa = surface.value() / 100.0;
+      if pressure_hpa < 1.0 || pressure_hpa.fract() != 0.0 {
+        continue;
+      }
+      let Some(valid_time) = submessage.temporal_info().forecast_time_target else {
+        continue;
+      };
+
+      let shape = submessage.grid_shape().map_err(grib_error)?;
+      if stencils.first().is_none_or(|s| s.shape != shape) {
+        let grid = grid_points(submessage.latlons().map_err(grib_error)?);
+        stencils = points
+          .iter()
+          .map(|&(lat, lon)| build_stencil(shape, &grid, lat, lon))
+          .collect::<Result<_, _>>()?;
+      }
+
+      let values: Vec<f32> = grib::Grib2SubmessageDecoder::from(submessage)
+        .and_then(|decoder| decoder.dispatch().map(|values| values.collect()))
+        .map_err(grib_error)?;
+
+      for (stencil, column) in stencils.iter().zip(columns.iter_mut()) {
+        let value = stencil
+          .corners
+          .iter()
+          .map(|&(index, weight)| values.get(index).map(|&v| v as f64 * weight))
+          .sum::<Option<f64>>()
+          .filter(|v| v.is_finite());
+
+        let fields = column
+          .entry(valid_time.timestamp())
+          .or_default()
+          .entry(pressure_hpa as u32)
+          .or_default();
+        match field {
+          Field::U => fields.u = value,
+          Field::V => fields.v = value,
+          Field::Height => fields.height = value,
+          Field::Temperature => fields.temperature = value.map(|k| k - 273.15),
+        }
+      }
+    }
+  }
+
+  columns
+    .into_iter()
+    .zip(points)
+    .map(|(column, &(lat, lon))| column_grid(column, lat, lon))
+    .collect()
+}
+
+/// Lays out one point's decoded fields as a [`WeatherGrid`].
+fn column_grid(
+  column: BTreeMap<i64, BTreeMap<u32, LevelFields>>,
+  lat: f64,
+  lon: f64,
+) -> Result<WeatherGrid, WeatherError> {
+  let mut levels: Vec<u32> = column.values().flat_map(|c| c.keys().copied()).collect();
+  levels.sort_unstable_by(|a, b| b.cmp(a));
+  levels.dedup();
+  if levels.is_empty() {
+    return Err(WeatherError::NoWindData);
+  }
+
+  let times: Vec<i64> = column.keys().copied().collect();
+  let mut samples = Vec::with_capacity(times.len() * levels.len());
+  for hour in column.values() {
+    for level in &levels {
+      let fields = hour.get(level).copied().unwrap_or_default();
+      samples.push(match (fields.u, fields.v) {
+        (Some(u), Some(v)) => {
+          let (wind_speed, wind_direction) = wind_from_uv(u, v);
+          Some(LevelSample {
+            wind_speed,
+            wind_direction,
+            geopotential_height: fields.height,
+            temperature: fields.temperature,
+          })
+        }
+        _ => None,
+      });
+    }
+  }
+
+  let grid = WeatherGrid {
+    latitude: lat,
+    longitude: lon,
+    elevation: 0.0,
+    surface: vec![SurfaceSample::default(); times.len()],
+    times,
+    levels,
+    samples,
+    stale: false,
+  };
+  if !grid.has_wind() {
+    return Err(WeatherError::NoWindData);
+  }
+  Ok(grid)
+}
+
+/// Loads a forecast column from local GFS GRIB2 files, for launches
+/// without connectivity.
+#[tauri::command]
+pub async fn load_grib_weather(
+  paths: Vec<String>,
+  lat: f64,
+  lon: f64,
+) -> Result<WeatherGrid, WeatherError> {
+  tauri::async_runtime::spawn_blocking(move || load_gfs(&paths, lat, lon))
+    .await
+    .map_err(grib_error)?
+}
+
+/// Loads one forecast column per GEFS member from local GRIB2 files, one
+/// list of files (e.g. `gep01.t00z.pgrb2a.0p50.f*`) per member.
+#[tauri::command]
+pub async fn load_grib_ensemble(
+  members: Vec<Vec<String>>,
+  lat: f64,
+  lon: f64,
+) -> Result<Vec<WeatherGrid>, WeatherError> {
+  tauri::async_runtime::spawn_blocking(move || {
+    members
+      .iter()
+      .map(|paths| load_gfs(paths, lat, lon))
+      .collect()
+  })
+  .await
+  .map_err(grib_error)?
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::weather::parse_time;
+
+  /// GFS-like files for the 2025-07-07 00Z run at hours 0 and 3, written by
+  /// `tests/fixtures/make_gfs_grib2.py`; its docstring gives the fields.
+  const F000: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/gfs_f000.grib2");
+  const F003: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/gfs_f003.grib2");
+
+  /// The fixture winds, as (speed, direction), at `lat`, `lon` (°E).
+  fn fixture_wind(level_u: f64, hour: f64, lat: f64, lon: f64) -> (f64, f64) {
+    wind_from_uv(level_u + 0.5 * (lon - 253.0) + hour, -2.0 * (lat - 39.0))
+  }
+
+  fn assert_close(actual: f64, expected: f64) {
+    assert!(
+      (actual - expected).abs() < 1e-3,
+      "{} != {}",
+      actual,
+      expected
+    );
+  }
+
+  #[test]
+  fn stencil_weights_surrounding_points() {
+    // 0.5° global grid rows scanned north to south, like GFS.
+    let lats: Vec<f32> = (0..361).map(|j| 90.0 - 0.5 * j as f32).collect();
+    let lons: Vec<f32> = (0..720).map(|i| 0.5 * i as f32).collect();
+    let points = grid_points(
+      lats
+        .iter()
+        .flat_map(|&la| lons.iter().map(move |&lo| (la, lo))),
+    );
+
+    let stencil = build_stencil((720, 361), &points, 40.1, -105.1).unwrap();
+    let total: f64 = stencil.corners.iter().map(|c| c.1).sum();
+    assert!((total - 1.0).abs() < 1e-9);
+
+    // Row for 40.0°N is j = 100, column for 254.5°E is i = 509.
+    let index = |j: usize, i: usize| j * 720 + i;
+    let weights = &stencil.corners;
+    assert_eq!(weights[0].0, index(100, 509));
+    assert_eq!(weights[3].0, index(99, 510));
+    assert!((weights[0].1 - 0.2 * 0.8).abs() < 1e-6);
+  }
+
+  #[test]
+  fn decodes_isobaric_winds_heights_and_temperatures() {
+    let (lat, lon) = (40.25, -104.5);
+    let grid =
+      tauri::async_runtime::block_on(load_grib_weather(vec![F003.into(), F000.into()], lat, lon))
+        .unwrap();
+
+    // Hours in time order whatever the file order; 0.4 hPa and 2 m skipped.
+    let run = parse_time("2025-07-07T00:00:00Z").unwrap().timestamp();
+    assert_eq!(grid.times, [run, run + 3 * 3600]);
+    assert_eq!(grid.levels, [850, 500, 250]);
+    assert_eq!((grid.latitude, grid.longitude), (lat, lon));
+    assert_eq!(grid.surface.len(), 2);
+
+    for (hour_index, hour) in [0.0, 3.0].into_iter().enumerate() {
+      for (level_index, (u, height, kelvin)) in [
+        (5.0, 1500.0, 288.0),
+        (15.0, 5800.0, 258.0),
+        (30.0, 10400.0, 222.0),
+      ]
+      .into_iter()
+      .enumerate()
+      {
+        let sample = grid.samples[hour_index * 3 + level_index].unwrap();
+        let (speed, direction) = fixture_wind(u, hour, lat, 255.5);
+        assert_close(sample.wind_speed, speed);
+        assert_close(sample.wind_direction, direction);
+        assert_close(sample.geopotential_height.unwrap(), height + 10.0 * hour);
+        assert_close(sample.temperature.unwrap(), kelvin - 0.5 * hour - 273.15);
+      }
+    }
+  }
+
+  #[test]
+  fn decodes_columns_and_members() {
+    // Across the grid, including a grid point and the western edge.
+    let points = [(40.0, -106.0), (39.5, 254.75), (41.0, -104.0)];
+    let columns = load_gfs_columns(&[F000], &points).unwrap();
+    assert_eq!(columns.len(), 3);
+    for (column, &(lat, lon)) in columns.iter().zip(&points) {
+      let sample = column.samples[1].unwrap();
+      let (speed, direction) = fixture_wind(15.0, 0.0, lat, normalize_lon(lon));
+      assert_close(sample.wind_speed, speed);
+      assert_close(sample.wind_direction, direction);
+    }
+
+    // One column per member, each from its own files.
+    let members = tauri::async_runtime::block_on(load_grib_ensemble(
+      vec![vec![F000.into(), F003.into()], vec![F000.into()]],
+      40.0,
+      -105.0,
+    ))
+    .unwrap();
+    assert_eq!(members.len(), 2);
+    assert_eq!(members[0].times.len(), 2);
+    assert_eq!(members[1].times.len(), 1);
+
+    assert!(matches!(
+      load_gfs(&[F000], 45.0, -105.0),
+      Err(WeatherError::Grib(_))
+    ));
+  }
+
+  #[test]
+  fn columns_need_winds() {
+    let fields = |u, v| LevelFields {
+      u,
+      v,
+      height: Some(1500.0),
+      temperature: None,
+    };
+    let column = |fields: LevelFields| BTreeMap::from([(0, BTreeMap::from([(850, fields)]))]);
+
+    let grid = column_grid(column(fields(Some(0.0), Some(-10.0))), 40.0, -105.0).unwrap();
+    let sample = grid.samples[0].unwrap();
+    assert_close(sample.wind_speed, 10.0);
+    assert_close(sample.wind_direction, 0.0);
+    assert_eq!(sample.geopotential_height, Some(1500.0));
+
+    // Heights alone, or no levels at all, are no forecast.
+    assert!(matches!(
+      column_grid(column(fields(Some(1.0), None)), 40.0, -105.0),
+      Err(WeatherError::NoWindData)
+    ));
+    assert!(matches!(
+      column_grid(BTreeMap::new(), 40.0, -105.0),
+      Err(WeatherError::NoWindData)
+    ));
+  }
+}
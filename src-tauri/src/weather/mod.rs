@@ -0,0 +1,563 @@
+//! Forecast data for the prediction engine.
+//!
+//! Every source (the Open-Meteo GFS and GEFS endpoints or local GRIB2 files)
+//! is decoded into a [`WeatherGrid`]: a single forecast column of hourly
+//! samples on the `PRESSURE_LEVELS` set, with wind speed in m/s and
+//! meteorological wind direction in degrees. Ensembles are one grid per
+//! member, and a [`field::WeatherField`] is a lattice of grids around the
+//! flight corridor.
+
+pub mod cache;
+pub mod field;
+pub mod grib;
+pub mod open_meteo;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use cache::{CacheKey, CachedForecast, ForecastCache};
+
+/// Pressure levels requested from the forecast, hPa. Same set as
+/// `PRESSURE_LEVELS` in `src/constants/index.ts`.
+pub const PRESSURE_LEVELS: [u32; 31] = [
+  1000, 975, 950, 925, 900, 850, 800, 750, 700, 650, 600, 550, 500, 450, 400, 350, 300, 250, 200,
+  150, 100, 70, 50, 30, 20, 10, 7, 5, 3, 2, 1,
+];
+
+#[derive(Debug, thiserror::Error)]
+pub enum WeatherError {
+  #[error("Invalid location or time parameters: {0}")]
+  InvalidRequest(String),
+  #[error("Weather service not found. The API endpoint may have changed.")]
+  NotFound,
+  #[error("Too many requests. Please wait a moment and try again.")]
+  RateLimited,
+  #[error("Weather service temporarily unavailable ({0}). Please try again later.")]
+  ServerError(u16),
+  #[error("Weather API error ({0}): {1}")]
+  Http(u16, String),
+  #[error("Network error while fetching weather data: {0}")]
+  Network(String),
+  #[error("Malformed weather data: {0}")]
+  Parse(String),
+  #[error("Could not read GRIB2 data: {0}")]
+  Grib(String),
+  #[error("Forecast cache error: {0}")]
+  Cache(String),
+  #[error("No weather forecast available for the selected time and location.")]
+  NoForecast,
+  #[error("Wind data not available for this location or time.")]
+  NoWindData,
+}
+
+impl Serialize for WeatherError {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.to_string())
+  }
+}
+
+/// Forecast values on one pressure level at one hour.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LevelSample {
+  /// m/s
+  pub wind_speed: f64,
+  /// Direction the wind blows from, degrees
+  pub wind_direction: f64,
+  /// Geopotential height of the level, m
+  pub geopotential_height: Option<f64>,
+  /// °C
+  pub temperature: Option<f64>,
+}
+
+/// Near-surface forecast values at one hour.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SurfaceSample {
+  /// °C
+  pub temperature_2m: Option<f64>,
+  /// %
+  pub relative_humidity_2m: Option<f64>,
+  /// °C
+  pub dewpoint_2m: Option<f64>,
+  /// mm
+  pub precipitation: Option<f64>,
+  /// %
+  pub cloudcover: Option<f64>,
+  /// m
+  pub visibility: Option<f64>,
+  /// m/s
+  pub wind_speed_10m: Option<f64>,
+  /// degrees
+  pub wind_direction_10m: Option<f64>,
+  /// m/s
+  pub wind_gusts_10m: Option<f64>,
+  /// hPa
+  pub pressure_msl: Option<f64>,
+  /// hPa
+  pub surface_pressure: Option<f64>,
+  /// J/kg
+  pub cape: Option<f64>,
+}
+
+/// Hourly forecast column: time × pressure level.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeatherGrid {
+  pub latitude: f64,
+  pub longitude: f64,
+  /// Model ground elevation, m
+  pub elevation: f64,
+  /// Forecast hours as Unix timestamps (seconds, UTC)
+  pub times: Vec<i64>,
+  /// Pressure levels, hPa, ordered from the surface upward
+  pub levels: Vec<u32>,
+  /// Row-major `[time][level]`; `None` where the model has no value
+  pub samples: Vec<Option<LevelSample>>,
+  /// One entry per forecast hour
+  pub surface: Vec<SurfaceSample>,
+  /// Set when the current model run could not be fetched and an older
+  /// cached run was served instead
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub stale: bool,
+}
+
+impl WeatherGrid {
+  pub fn sample(&self, time_index: usize, level_index: usize) -> Option<&LevelSample> {
+    if level_index >= self.levels.len() {
+      return None;
+    }
+    self
+      .samples
+      .get(time_index * self.levels.len() + level_index)?
+      .as_ref()
+  }
+
+  /// Whether at least one level has wind at any hour.
+  pub fn has_wind(&self) -> bool {
+    self.samples.iter().any(Option::is_some)
+  }
+
+  /// Index of the forecast hour at or just before `timestamp` and the
+  /// weight (0..1) of the hour after it, clamped to the ends of the forecast.
+  pub fn time_bracket(&self, timestamp: f64) -> (usize, f64) {
+    match self.times.iter().position(|&t| t as f64 > timestamp) {
+      None => (self.times.len().saturating_sub(1), 0.0),
+      Some(0) => (0, 0.0),
+      Some(next) => {
+        let (t0, t1) = (self.times[next - 1] as f64, self.times[next] as f64);
+        (next - 1, (timestamp - t0) / (t1 - t0))
+      }
+    }
+  }
+
+  /// First and last forecast hours as Unix timestamps, if there are any.
+  pub fn time_span(&self) -> Option<(f64, f64)> {
+    Some((*self.times.first()? as f64, *self.times.last()? as f64))
+  }
+}
+
+/// Converts wind components to speed (m/s) and the direction the wind
+/// blows from (degrees).
+pub fn wind_from_uv(u: f64, v: f64) -> (f64, f64) {
+  let speed = u.hypot(v);
+  let direction = (-u).atan2(-v).to_degrees().rem_euclid(360.0);
+  (speed, direction)
+}
+
+/// Converts speed and the direction the wind blows from to eastward and
+/// northward components. Inverse of [`wind_from_uv`].
+pub fn wind_to_uv(speed: f64, direction: f64) -> (f64, f64) {
+  let (sin, cos) = direction.to_radians().sin_cos();
+  (-speed * sin, -speed * cos)
+}
+
+/// Parses the ISO strings used by the frontend and by Open-Meteo. Strings
+/// without an offset (`2025-07-07T12:00`) are taken as UTC.
+pub fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+  if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+    return Some(time.with_timezone(&Utc));
+  }
+  ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .map(|time| time.and_utc())
+}
+
+fn parse_launch_time(launch_time: &str) -> Result<DateTime<Utc>, WeatherError> {
+  parse_time(launch_time)
+    .ok_or_else(|| WeatherError::InvalidRequest(format!("invalid launch time {}", launch_time)))
+}
+
+/// Caches a freshly `fetched` grid under `key`. When the fetch failed, the
+/// newest cached run of the same forecast is served instead, marked stale, so
+/// a site prefetched before losing coverage can still be predicted.
+fn store_or_fallback(
+  cache: &ForecastCache,
+  key: &CacheKey,
+  fetched: Result<WeatherGrid, WeatherError>,
+  now: DateTime<Utc>,
+) -> Result<WeatherGrid, WeatherError> {
+  match fetched {
+    Ok(grid) => {
+      cache.put(key, &grid, now)?;
+      Ok(grid)
+    }
+    Err(error) => stale_fallback(cache, key, &error)?.ok_or(error),
+  }
+}
+
+/// The newest cached run for `key`'s site and date, marked stale.
+fn stale_fallback(
+  cache: &ForecastCache,
+  key: &CacheKey,
+  error: &WeatherError,
+) -> Result<Option<WeatherGrid>, WeatherError> {
+  let Some((run, grid)) = cache.latest(key)? else {
+    return Ok(None);
+  };
+  log::warn!(
+    "Serving the cached {} {} run for tile {}, {}: {}",
+    key.model,
+    run.run_time,
+    key.tile_lat,
+    key.tile_lon,
+    error
+  );
+  Ok(Some(WeatherGrid {
+    stale: true,
+    ..grid
+  }))
+}
+
+/// Fetches the GFS forecast column for a launch site, served from `cache`
+/// when the current model run is already on disk, or from an older cached
+/// run when it cannot be fetched.
+pub async fn fetch_gfs_cached(
+  cache: &ForecastCache,
+  lat: f64,
+  lon: f64,
+  launch_time: DateTime<Utc>,
+) -> Result<WeatherGrid, WeatherError> {
+  let now = Utc::now();
+  let key = CacheKey::gfs(lat, lon, launch_time, now);
+  if let Some(grid) = cache.get(&key, now)? {
+    return Ok(grid);
+  }
+  let fetched = open_meteo::Client::default()
+    .fetch_gfs(lat, lon, launch_time)
+    .await;
+  store_or_fallback(cache, &key, fetched, now)
+}
+
+/// Fetches the GFS forecast column for a launch site from the day of `start`
+/// through the day of `end`, or through the last day of the forecast if that
+/// is sooner, as sweeping a launch window needs. Cached like
+/// [`fetch_gfs_cached`]; a cached forecast ending short of that is fetched
+/// again.
+pub async fn fetch_gfs_range_cached(
+  cache: &ForecastCache,
+  lat: f64,
+  lon: f64,
+  start: DateTime<Utc>,
+  end: DateTime<Utc>,
+) -> Result<WeatherGrid, WeatherError> {
+  let now = Utc::now();
+  let last_day = end.date_naive().min(open_meteo::forecast_horizon(now));
+  if start.date_naive() > last_day {
+    return Err(WeatherError::InvalidRequest(format!(
+      "{} is past the end of the forecast",
+      start
+    )));
+  }
+  let key = CacheKey::gfs_window(lat, lon, start, now);
+  let covered = |grid: &WeatherGrid| {
+    grid
+      .times
+      .last()
+      .and_then(|&last| DateTime::from_timestamp(last, 0))
+      .is_some_and(|last| last.date_naive() >= last_day)
+  };
+  if let Some(grid) = cache.get(&key, now)?.filter(covered) {
+    return Ok(grid);
+  }
+  let fetched = open_meteo::Client::default()
+    .fetch_gfs_range(lat, lon, start.date_naive(), last_day)
+    .await;
+  store_or_fallback(cache, &key, fetched, now)
+}
+
+/// Fetches the GFS winds at each of `points` (latitude, longitude), taking
+/// what the cache has and requesting the rest in batches small enough for
+/// Open-Meteo's rate limit. Only the winds are requested, and a full column
+/// cached for a launch on the same tile serves as well. When a batch cannot
+/// be fetched, each of its columns must have an older cached run to fall
+/// back on.
+pub async fn fetch_gfs_field_cached(
+  cache: &ForecastCache,
+  points: &[(f64, f64)],
+  launch_time: DateTime<Utc>,
+) -> Result<Vec<WeatherGrid>, WeatherError> {
+  let now = Utc::now();
+  let full_keys: Vec<CacheKey> = points
+    .iter()
+    .map(|&(lat, lon)| CacheKey::gfs(lat, lon, launch_time, now))
+    .collect();
+  let keys: Vec<CacheKey> = points
+    .iter()
+    .map(|&(lat, lon)| CacheKey::gfs_winds(lat, lon, launch_time, now))
+    .collect();
+  let mut columns = Vec::with_capacity(points.len());
+  for (full_key, key) in full_keys.iter().zip(&keys) {
+    columns.push(match cache.get(full_key, now)? {
+      Some(grid) => Some(grid),
+      None => cache.get(key, now)?,
+    });
+  }
+
+  let missing: Vec<usize> = (0..points.len())
+    .filter(|&i| columns[i].is_none())
+    .collect();
+  let client = open_meteo::Client::default();
+  for batch in missing.chunks(open_meteo::wind_field_batch_size()) {
+    let wanted: Vec<(f64, f64)> = batch.iter().map(|&i| points[i]).collect();
+    match client.fetch_gfs_winds(&wanted, launch_time).await {
+      Ok(fetched) => {
+        for (&i, grid) in batch.iter().zip(fetched) {
+          cache.put(&keys[i], &grid, now)?;
+          columns[i] = Some(grid);
+        }
+      }
+      Err(error) => {
+        for &i in batch {
+          let fallback = match stale_fallback(cache, &keys[i], &error)? {
+            Some(grid) => Some(grid),
+            None => stale_fallback(cache, &full_keys[i], &error)?,
+          };
+          match fallback {
+            Some(grid) => columns[i] = Some(grid),
+            None => return Err(error),
+          }
+        }
+      }
+    }
+  }
+  Ok(columns.into_iter().flatten().collect())
+}
+
+/// Fetches every GEFS member's forecast column for a launch site, control
+/// first, served from `cache` when the current run is already on disk, or
+/// from an older cached run when it cannot be fetched.
+pub async fn fetch_gefs_cached(
+  cache: &ForecastCache,
+  lat: f64,
+  lon: f64,
+  launch_time: DateTime<Utc>,
+) -> Result<Vec<WeatherGrid>, WeatherError> {
+  let now = Utc::now();
+  let key = |member| CacheKey::gefs(member, lat, lon, launch_time, now);
+  let mut members = Vec::new();
+  while let Some(grid) = cache.get(&key(members.len()), now)? {
+    members.push(grid);
+  }
+  if !members.is_empty() {
+    return Ok(members);
+  }
+  let error = match open_meteo::Client::default()
+    .fetch_gefs(lat, lon, launch_time)
+    .await
+  {
+    Ok(members) => {
+      for (member, grid) in members.iter().enumerate() {
+        cache.put(&key(member), grid, now)?;
+      }
+      return Ok(members);
+    }
+    Err(error) => error,
+  };
+  while let Some(grid) = stale_fallback(cache, &key(members.len()), &error)? {
+    members.push(grid);
+  }
+  if members.is_empty() {
+    return Err(error);
+  }
+  Ok(members)
+}
+
+/// Fetches the GFS forecast column for a launch site.
+#[tauri::command]
+pub async fn fetch_weather(
+  cache: State<'_, ForecastCache>,
+  lat: f64,
+  lon: f64,
+  launch_time: String,
+) -> Result<WeatherGrid, WeatherError> {
+  fetch_gfs_cached(&cache, lat, lon, parse_launch_time(&launch_time)?).await
+}
+
+/// Fetches the GEFS ensemble members for a launch site, control first.
+#[tauri::command]
+pub async fn fetch_ensemble_weather(
+  cache: State<'_, ForecastCache>,
+  lat: f64,
+  lon: f64,
+  launch_time: String,
+) -> Result<Vec<WeatherGrid>, WeatherError> {
+  fetch_gefs_cached(&cache, lat, lon, parse_launch_time(&launch_time)?).await
+}
+
+#[tauri::command]
+pub fn list_cached_forecasts(
+  cache: State<'_, ForecastCache>,
+) -> Result<Vec<CachedForecast>, WeatherError> {
+  cache.list()
+}
+
+/// Removes superseded and overly old forecasts, keeping the newest run of
+/// each for offline use, or all of them when `all` is set. Returns the
+/// number of entries removed.
+#[tauri::command]
+pub fn purge_forecast_cache(
+  cache: State<'_, ForecastCache>,
+  all: Option<bool>,
+) -> Result<usize, WeatherError> {
+  cache.purge(all.unwrap_or(false), Utc::now())
+}
+
+/// Makes sure the current GFS run for a launch is cached, e.g. before
+/// driving out to a site without coverage. Without a connection, the newest
+/// cached run is returned, its `expiresAt` in the past when it is stale.
+#[tauri::command]
+pub async fn prefetch_forecast(
+  cache: State<'_, ForecastCache>,
+  lat: f64,
+  lon: f64,
+  launch_time: String,
+) -> Result<CachedForecast, WeatherError> {
+  let launch_time = parse_launch_time(&launch_time)?;
+  let now = Utc::now();
+  let key = CacheKey::gfs(lat, lon, launch_time, now);
+  if cache.get(&key, now)?.is_some() {
+    if let Some(entry) = cache.list()?.into_iter().find(|entry| entry.key == key) {
+      return Ok(entry);
+    }
+  }
+  match open_meteo::Client::default()
+    .fetch_gfs(lat, lon, launch_time)
+    .await
+  {
+    Ok(grid) => cache.put(&key, &grid, now),
+    Err(error) => {
+      let Some((run, _)) = cache.latest(&key)? else {
+        return Err(error);
+      };
+      log::warn!("Keeping the cached {} run: {}", run.run_time, error);
+      cache
+        .list()?
+        .into_iter()
+        .find(|entry| entry.key == run)
+        .ok_or(error)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn converts_components_to_meteorological_direction() {
+    let cases = [
+      ((0.0, -10.0), 0.0),  // from the north
+      ((-10.0, 0.0), 90.0), // from the east
+      ((0.0, 10.0), 180.0), // from the south
+      ((10.0, 0.0), 270.0), // from the west
+    ];
+    for ((u, v), direction) in cases {
+      let (speed, dir) = wind_from_uv(u, v);
+      assert!((speed - 10.0).abs() < 1e-9);
+      assert!((dir - direction).abs() < 1e-9, "{} {} -> {}", u, v, dir);
+
+      let (u2, v2) = wind_to_uv(speed, dir);
+      assert!((u2 - u).abs() < 1e-9 && (v2 - v).abs() < 1e-9);
+    }
+  }
+
+  #[test]
+  fn brackets_forecast_hours() {
+    let grid = WeatherGrid {
+      times: vec![0, 3600, 7200],
+      ..Default::default()
+    };
+    assert_eq!(grid.time_bracket(-60.0), (0, 0.0));
+    assert_eq!(grid.time_bracket(0.0), (0, 0.0));
+    assert_eq!(grid.time_bracket(900.0), (0, 0.25));
+    assert_eq!(grid.time_bracket(3600.0), (1, 0.0));
+    assert_eq!(grid.time_bracket(9000.0), (2, 0.0));
+    assert_eq!(grid.time_span(), Some((0.0, 7200.0)));
+    assert_eq!(WeatherGrid::default().time_span(), None);
+  }
+
+  #[test]
+  fn serves_an_older_cached_run_when_offline() {
+    let cache = ForecastCache::open_in_memory().unwrap();
+    let grid = open_meteo::parse_response(
+      200,
+      include_str!("../../tests/fixtures/open_meteo_gfs.json"),
+    )
+    .unwrap();
+    let launch = parse_time("2025-07-07T18:00:00Z").unwrap();
+    let fetched_at = parse_time("2025-07-07T08:00:00Z").unwrap();
+    let key = CacheKey::gfs(40.0, -105.0, launch, fetched_at);
+    store_or_fallback(&cache, &key, Ok(grid.clone()), fetched_at).unwrap();
+
+    // Seven hours on, a newer run is due and nothing can fetch it.
+    let later = parse_time("2025-07-07T15:00:00Z").unwrap();
+    let current = CacheKey::gfs(40.0, -105.0, launch, later);
+    assert_ne!(current, key);
+    assert_eq!(cache.get(&current, later).unwrap(), None);
+    let offline = || Err(WeatherError::Network("no route to host".into()));
+    let served = store_or_fallback(&cache, &current, offline(), later).unwrap();
+    assert!(served.stale);
+    assert_eq!(
+      WeatherGrid {
+        stale: false,
+        ..served
+      },
+      grid
+    );
+
+    // Another site or day has nothing to fall back on.
+    let elsewhere = CacheKey::gfs(45.0, -105.0, launch, later);
+    assert!(matches!(
+      store_or_fallback(&cache, &elsewhere, offline(), later),
+      Err(WeatherError::Network(_))
+    ));
+  }
+
+  #[test]
+  fn routine_purge_keeps_the_offline_fallback() {
+    let cache = ForecastCache::open_in_memory().unwrap();
+    let grid = open_meteo::parse_response(
+      200,
+      include_str!("../../tests/fixtures/open_meteo_gfs.json"),
+    )
+    .unwrap();
+    let launch = parse_time("2025-07-07T18:00:00Z").unwrap();
+    let fetched_at = parse_time("2025-07-07T08:00:00Z").unwrap();
+    let key = CacheKey::gfs(40.0, -105.0, launch, fetched_at);
+    cache.put(&key, &grid, fetched_at).unwrap();
+
+    // The run has expired, but is the only one on disk for its tile.
+    let later = parse_time("2025-07-07T15:00:00Z").unwrap();
+    assert_eq!(cache.purge(false, later).unwrap(), 0);
+    let current = CacheKey::gfs(40.0, -105.0, launch, later);
+    let offline = Err(WeatherError::Network("no route to host".into()));
+    assert!(
+      store_or_fallback(&cache, &current, offline, later)
+        .unwrap()
+        .stale
+    );
+  }
+}
@@ -0,0 +1,729 @@
+//! Open-Meteo GFS client.
+//!
+//! Requests the same hourly variables as `fetchWeatherData` in
+//! `src/services/weatherService.ts`, plus geopotential height and
+//! temperature on every pressure level, and decodes the reply into a
+//! [`WeatherGrid`]. Several locations can share one request, as the columns
+//! of a wind field do. GEFS members come from the ensemble endpoint, which
+//! returns every member's series side by side with a `_memberNN` suffix.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tauri_plugin_http::reqwest;
+
+use super::{parse_time, LevelSample, SurfaceSample, WeatherError, WeatherGrid, PRESSURE_LEVELS};
+
+pub const GFS_URL: &str = "https://api.open-meteo.com/v1/gfs";
+pub const ENSEMBLE_URL: &str = "https://ensemble-api.open-meteo.com/v1/ensemble";
+/// Open-Meteo model name for the 0.5° GEFS (control plus 30 members).
+pub const GEFS_MODEL: &str = "gfs05";
+/// Pressure levels GEFS publishes, hPa.
+pub const GEFS_PRESSURE_LEVELS: [u32; 12] =
+  [1000, 925, 850, 700, 500, 400, 300, 250, 200, 100, 50, 10];
+
+const SURFACE_PARAMS: [&str; 18] = [
+  "temperature_2m",
+  "relativehumidity_2m",
+  "dewpoint_2m",
+  "apparent_temperature",
+  "precipitation",
+  "rain",
+  "snowfall",
+  "cloudcover",
+  "cloudcover_low",
+  "cloudcover_mid",
+  "cloudcover_high",
+  "visibility",
+  "windspeed_10m",
+  "winddirection_10m",
+  "windgusts_10m",
+  "pressure_msl",
+  "surface_pressure",
+  "cape",
+];
+
+/// Days of GFS forecast Open-Meteo serves, today included.
+pub const GFS_FORECAST_DAYS: i64 = 16;
+/// Open-Meteo bills each location of a request as one API call per ten
+/// hourly variables; the free tier allows 600 calls a minute.
+const VARIABLES_PER_CALL: usize = 10;
+/// Most API calls one wind-field request may cost, so that a whole field
+/// stays well inside the per-minute limit.
+const MAX_CALLS_PER_REQUEST: usize = 150;
+
+/// Raw Open-Meteo forecast response, the `WeatherData` type on the frontend.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WeatherData {
+  pub hourly: HourlyData,
+  #[serde(default)]
+  pub hourly_units: HashMap<String, String>,
+  #[serde(default)]
+  pub latitude: f64,
+  #[serde(default)]
+  pub longitude: f64,
+  #[serde(default)]
+  pub elevation: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HourlyData {
+  pub time: Vec<String>,
+  /// Every other hourly series (`windspeed_250hPa`, `temperature_2m`, ...).
+  #[serde(flatten)]
+  pub series: HashMap<String, Vec<Option<f64>>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+  reason: Option<String>,
+}
+
+/// Builds the GFS request URL for a launch site. The forecast covers the
+/// launch day and the next, so flights crossing midnight UTC have data.
+pub fn gfs_url(base_url: &str, lat: f64, lon: f64, launch_time: DateTime<Utc>) -> String {
+  gfs_url_many(base_url, &[(lat, lon)], launch_time)
+}
+
+/// Builds one GFS request for several (latitude, longitude) columns; Open-Meteo
+/// answers with one forecast per location, in order.
+pub fn gfs_url_many(base_url: &str, points: &[(f64, f64)], launch_time: DateTime<Utc>) -> String {
+  let (start, end) = launch_days(launch_time);
+  forecast_url(base_url, points, &gfs_variables(), None, start, end)
+}
+
+/// Builds the GFS request URL for a launch site covering the days from
+/// `start` through `end`, e.g. to sweep a launch window.
+pub fn gfs_range_url(
+  base_url: &str,
+  lat: f64,
+  lon: f64,
+  start: NaiveDate,
+  end: NaiveDate,
+) -> String {
+  forecast_url(base_url, &[(lat, lon)], &gfs_variables(), None, start, end)
+}
+
+/// Last day of the GFS forecast Open-Meteo serves at `now`.
+pub fn forecast_horizon(now: DateTime<Utc>) -> NaiveDate {
+  (now + Duration::days(GFS_FORECAST_DAYS - 1)).date_naive()
+}
+
+/// Builds a GFS request for the winds alone at several columns, as a wind
+/// field needs; see [`wind_field_batch_size`] for how many fit in one.
+pub fn gfs_winds_url(base_url: &str, points: &[(f64, f64)], launch_time: DateTime<Utc>) -> String {
+  let hourly = level_variables(&["windspeed", "winddirection"], &PRESSURE_LEVELS);
+  let (start, end) = launch_days(launch_time);
+  forecast_url(base_url, points, &hourly, None, start, end)
+}
+
+/// Number of wind-only columns one request can carry without costing more
+/// than `MAX_CALLS_PER_REQUEST` API calls.
+pub fn wind_field_batch_size() -> usize {
+  let calls_per_location = (2 * PRESSURE_LEVELS.len()).div_ceil(VARIABLES_PER_CALL);
+  (MAX_CALLS_PER_REQUEST / calls_per_location).max(1)
+}
+
+/// The launch day and the next.
+fn launch_days(launch_time: DateTime<Utc>) -> (NaiveDate, NaiveDate) {
+  (
+    launch_time.date_naive(),
+    (launch_time + Duration::days(1)).date_naive(),
+  )
+}
+
+/// Every pressure-level and surface variable of a full forecast column.
+fn gfs_variables() -> Vec<String> {
+  let mut hourly = level_variables(
+    &[
+      "windspeed",
+      "winddirection",
+      "geopotential_height",
+      "temperature",
+    ],
+    &PRESSURE_LEVELS,
+  );
+  hourly.extend(SURFACE_PARAMS.iter().map(|p| p.to_string()));
+  hourly
+}
+
+/// `name_{level}hPa` for each of `levels`, for each of `names`.
+fn level_variables(names: &[&str], levels: &[u32]) -> Vec<String> {
+  names
+    .iter()
+    .flat_map(|name| levels.iter().map(move |p| format!("{}_{}hPa", name, p)))
+    .collect()
+}
+
+/// Builds a forecast request for `hourly` at each of `points` over the days
+/// from `start` through `end`, from `models` when given.
+fn forecast_url(
+  base_url: &str,
+  points: &[(f64, f64)],
+  hourly: &[String],
+  models: Option<&str>,
+  start: NaiveDate,
+  end: NaiveDate,
+) -> String {
+  format!(
+    "{}?latitude={}&longitude={}{}&hourly={}&wind_speed_unit=ms&start_date={}&end_date={}",
+    base_url,
+    points
+      .iter()
+      .map(|p| format!("{:.4}", p.0))
+      .collect::<Vec<_>>()
+      .join(","),
+    points
+      .iter()
+      .map(|p| format!("{:.4}", p.1))
+      .collect::<Vec<_>>()
+      .join(","),
+    models.map_or(String::new(), |models| format!("&models={}", models)),
+    hourly.join(","),
+    start.format("%Y-%m-%d"),
+    end.format("%Y-%m-%d")
+  )
+}
+
+/// Builds the GEFS request URL for a launch site, covering the same two
+/// days as [`gfs_url`].
+pub fn ensemble_url(base_url: &str, lat: f64, lon: f64, launch_time: DateTime<Utc>) -> String {
+  let mut hourly = level_variables(
+    &[
+      "wind_speed",
+      "wind_direction",
+      "geopotential_height",
+      "temperature",
+    ],
+    &GEFS_PRESSURE_LEVELS,
+  );
+  hourly.extend(["wind_speed_10m", "wind_direction_10m"].map(String::from));
+  let (start, end) = launch_days(launch_time);
+  forecast_url(
+    base_url,
+    &[(lat, lon)],
+    &hourly,
+    Some(GEFS_MODEL),
+    start,
+    end,
+  )
+}
+
+/// Maps a non-success HTTP status to the matching [`WeatherError`].
+fn check_status(status: u16, body: &str) -> Result<(), WeatherError> {
+  if !(200..300).contains(&status) {
+    let reason = serde_json::from_str::<ErrorBody>(body)
+      .ok()
+      .and_then(|e| e.reason);
+    return Err(match status {
+      400 => WeatherError::InvalidRequest(reason.unwrap_or_else(|| "bad request".into())),
+      404 => WeatherError::NotFound,
+      429 => WeatherError::RateLimited,
+      500..=599 => WeatherError::ServerError(status),
+      _ => WeatherError::Http(
+        status,
+        reason.unwrap_or_else(|| "unexpected response".into()),
+      ),
+    });
+  }
+  Ok(())
+}
+
+/// Maps an HTTP status and body to a grid or the matching [`WeatherError`].
+pub fn parse_response(status: u16, body: &str) -> Result<WeatherGrid, WeatherError> {
+  check_status(status, body)?;
+  let data: WeatherData =
+    serde_json::from_str(body).map_err(|e| WeatherError::Parse(e.to_string()))?;
+  let grid = WeatherGrid::try_from(data)?;
+  if !grid.has_wind() {
+    return Err(WeatherError::NoWindData);
+  }
+  Ok(grid)
+}
+
+/// Open-Meteo replies with a bare object for one location and an array for
+/// several.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Locations {
+  Many(Vec<WeatherData>),
+  One(Box<WeatherData>),
+}
+
+/// Maps a multi-location response to one grid per requested location.
+pub fn parse_multi_response(status: u16, body: &str) -> Result<Vec<WeatherGrid>, WeatherError> {
+  check_status(status, body)?;
+  let locations: Locations =
+    serde_json::from_str(body).map_err(|e| WeatherError::Parse(e.to_string()))?;
+  let locations = match locations {
+    Locations::Many(locations) => locations,
+    Locations::One(location) => vec![*location],
+  };
+  let grids = locations
+    .into_iter()
+    .map(WeatherGrid::try_from)
+    .collect::<Result<Vec<_>, _>>()?;
+  if grids.is_empty() || !grids.iter().all(WeatherGrid::has_wind) {
+    return Err(WeatherError::NoWindData);
+  }
+  Ok(grids)
+}
+
+/// Splits an ensemble response into one grid per member, control first.
+pub fn parse_ensemble_response(status: u16, body: &str) -> Result<Vec<WeatherGrid>, WeatherError> {
+  check_status(status, body)?;
+  let data: WeatherData =
+    serde_json::from_str(body).map_err(|e| WeatherError::Parse(e.to_string()))?;
+
+  // Members are not always numbered contiguously: a member missing from the
+  // run is simply absent from the response.
+  let mut members: BTreeMap<usize, WeatherData> = BTreeMap::new();
+  for (key, values) in &data.hourly.series {
+    let (name, member) = split_member(key);
+    let target = members.entry(member).or_insert_with(|| WeatherData {
+      hourly: HourlyData {
+        time: data.hourly.time.clone(),
+        series: HashMap::new(),
+      },
+      hourly_units: HashMap::new(),
+      ..data.clone()
+    });
+    target
+      .hourly
+      .series
+      .insert(name.to_string(), values.clone());
+    if let Some(unit) = data.hourly_units.get(key) {
+      target.hourly_units.insert(name.to_string(), unit.clone());
+    }
+  }
+
+  let mut grids = Vec::with_capacity(members.len());
+  for (member, data) in members {
+    let grid = WeatherGrid::try_from(data)?;
+    if grid.has_wind() {
+      grids.push(grid);
+    } else {
+      log::warn!("GEFS member {} has no wind data; skipping it", member);
+    }
+  }
+  if grids.is_empty() {
+    return Err(WeatherError::NoWindData);
+  }
+  Ok(grids)
+}
+
+/// Splits `wind_speed_500hPa_member07` into the series name and member
+/// number; the control run has no suffix and is member 0.
+fn split_member(key: &str) -> (&str, usize) {
+  key
+    .rsplit_once("_member")
+    .and_then(|(name, member)| member.parse().ok().map(|m| (name, m)))
+    .unwrap_or((key, 0))
+}
+
+/// Factor converting a reported unit to SI. Open-Meteo reports wind in km/h
+/// unless `wind_speed_unit=ms` was requested, which the frontend does not do.
+fn speed_factor(unit: Option<&String>) -> f64 {
+  match unit.map(String::as_str) {
+    Some("km/h") => 1.0 / 3.6,
+    Some("mp/h") | Some("mph") => 0.44704,
+    Some("kn") => 0.514444,
+    _ => 1.0,
+  }
+}
+
+impl WeatherData {
+  /// Looks up a series under either of Open-Meteo's spellings
+  /// (`windspeed_10m` / `wind_speed_10m`).
+  fn series(&self, name: &str) -> Option<(&Vec<Option<f64>>, f64)> {
+    let alternate =
+      name
+        .replacen("windspeed", "wind_speed", 1)
+        .replacen("winddirection", "wind_direction", 1);
+    let key = if self.hourly.series.contains_key(name) {
+      name
+    } else {
+      alternate.as_str()
+    };
+    let values = self.hourly.series.get(key)?;
+    Some((values, speed_factor(self.hourly_units.get(key))))
+  }
+
+  fn value(&self, name: &str, index: usize) -> Option<f64> {
+    let (values, _) = self.series(name)?;
+    values.get(index).copied().flatten()
+  }
+
+  fn speed(&self, name: &str, index: usize) -> Option<f64> {
+    let (values, factor) = self.series(name)?;
+    values.get(index).copied().flatten().map(|v| v * factor)
+  }
+}
+
+impl TryFrom<WeatherData> for WeatherGrid {
+  type Error = WeatherError;
+
+  fn try_from(data: WeatherData) -> Result<Self, Self::Error> {
+    if data.hourly.time.is_empty() {
+      return Err(WeatherError::NoForecast);
+    }
+    let times = data
+      .hourly
+      .time
+      .iter()
+      .map(|t| parse_time(t).map(|t| t.timestamp()))
+      .collect::<Option<Vec<_>>>()
+      .ok_or_else(|| WeatherError::Parse("unrecognised forecast time".into()))?;
+
+    let levels: Vec<u32> = PRESSURE_LEVELS.to_vec();
+    let mut samples = Vec::with_capacity(times.len() * levels.len());
+    let mut surface = Vec::with_capacity(times.len());
+
+    for i in 0..times.len() {
+      for level in &levels {
+        let speed = data.speed(&format!("windspeed_{}hPa", level), i);
+        let direction = data.value(&format!("winddirection_{}hPa", level), i);
+        samples.push(match (speed, direction) {
+          (Some(wind_speed), Some(wind_direction)) => Some(LevelSample {
+            wind_speed,
+            wind_direction,
+            geopotential_height: data.value(&format!("geopotential_height_{}hPa", level), i),
+            temperature: data.value(&format!("temperature_{}hPa", level), i),
+          }),
+          _ => None,
+        });
+      }
+
+      surface.push(SurfaceSample {
+        temperature_2m: data.value("temperature_2m", i),
+        relative_humidity_2m: data.value("relativehumidity_2m", i),
+        dewpoint_2m: data.value("dewpoint_2m", i),
+        precipitation: data.value("precipitation", i),
+        cloudcover: data.value("cloudcover", i),
+        visibility: data.value("visibility", i),
+        wind_speed_10m: data.speed("windspeed_10m", i),
+        wind_direction_10m: data.value("winddirection_10m", i),
+        wind_gusts_10m: data.speed("windgusts_10m", i),
+        pressure_msl: data.value("pressure_msl", i),
+        surface_pressure: data.value("surface_pressure", i),
+        cape: data.value("cape", i),
+      });
+    }
+
+    Ok(WeatherGrid {
+      latitude: data.latitude,
+      longitude: data.longitude,
+      elevation: data.elevation,
+      times,
+      levels,
+      samples,
+      surface,
+      stale: false,
+    })
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct Client {
+  http: reqwest::Client,
+  base_url: String,
+  ensemble_base_url: String,
+}
+
+impl Default for Client {
+  fn default() -> Self {
+    Client {
+      http: reqwest::Client::new(),
+      base_url: GFS_URL.to_string(),
+      ensemble_base_url: ENSEMBLE_URL.to_string(),
+    }
+  }
+}
+
+impl Client {
+  /// GETs `url`, returning the HTTP status and body for the response
+  /// parsers, which map error statuses.
+  async fn get_json(&self, url: String) -> Result<(u16, String), WeatherError> {
+    let response = self
+      .http
+      .get(url)
+      .send()
+      .await
+      .map_err(|e| WeatherError::Network(e.to_string()))?;
+    let status = response.status().as_u16();
+    let body = response
+      .text()
+      .await
+      .map_err(|e| WeatherError::Network(e.to_string()))?;
+    Ok((status, body))
+  }
+
+  pub async fn fetch_gfs(
+    &self,
+    lat: f64,
+    lon: f64,
+    launch_time: DateTime<Utc>,
+  ) -> Result<WeatherGrid, WeatherError> {
+    let (status, body) = self
+      .get_json(gfs_url(&self.base_url, lat, lon, launch_time))
+      .await?;
+    parse_response(status, &body)
+  }
+
+  /// Fetches the GFS forecast column for a launch site covering the days
+  /// from `start` through `end`.
+  pub async fn fetch_gfs_range(
+    &self,
+    lat: f64,
+    lon: f64,
+    start: NaiveDate,
+    end: NaiveDate,
+  ) -> Result<WeatherGrid, WeatherError> {
+    let (status, body) = self
+      .get_json(gfs_range_url(&self.base_url, lat, lon, start, end))
+      .await?;
+    parse_response(status, &body)
+  }
+
+  /// Fetches the GFS winds at several (latitude, longitude) points in one
+  /// request, in the order given. Columns carry no temperatures, heights or
+  /// surface data.
+  pub async fn fetch_gfs_winds(
+    &self,
+    points: &[(f64, f64)],
+    launch_time: DateTime<Utc>,
+  ) -> Result<Vec<WeatherGrid>, WeatherError> {
+    let (status, body) = self
+      .get_json(gfs_winds_url(&self.base_url, points, launch_time))
+      .await?;
+    let grids = parse_multi_response(status, &body)?;
+    if grids.len() != points.len() {
+      return Err(WeatherError::Parse(format!(
+        "expected {} locations, got {}",
+        points.len(),
+        grids.len()
+      )));
+    }
+    Ok(grids)
+  }
+
+  /// Fetches every GEFS member, control first.
+  pub async fn fetch_gefs(
+    &self,
+    lat: f64,
+    lon: f64,
+    launch_time: DateTime<Utc>,
+  ) -> Result<Vec<WeatherGrid>, WeatherError> {
+    let (status, body) = self
+      .get_json(ensemble_url(&self.ensemble_base_url, lat, lon, launch_time))
+      .await?;
+    parse_ensemble_response(status, &body)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const GFS_FIXTURE: &str = include_str!("../../tests/fixtures/open_meteo_gfs.json");
+  const KMH_FIXTURE: &str = include_str!("../../tests/fixtures/open_meteo_gfs_kmh.json");
+  const ERROR_FIXTURE: &str = include_str!("../../tests/fixtures/open_meteo_error.json");
+  const GEFS_FIXTURE: &str = include_str!("../../tests/fixtures/open_meteo_gefs.json");
+  const GEFS_MISSING_MEMBER_FIXTURE: &str =
+    include_str!("../../tests/fixtures/open_meteo_gefs_missing_member.json");
+
+  #[test]
+  fn url_requests_every_pressure_level() {
+    let launch = parse_time("2025-07-07T23:30:00Z").unwrap();
+    let url = gfs_url(GFS_URL, 40.01499, -105.27055, launch);
+
+    assert!(
+      url.starts_with("https://api.open-meteo.com/v1/gfs?latitude=40.0150&longitude=-105.2706&")
+    );
+    assert!(url.contains("start_date=2025-07-07&end_date=2025-07-08"));
+    assert!(url.contains("wind_speed_unit=ms"));
+    for level in PRESSURE_LEVELS {
+      for name in [
+        "windspeed",
+        "winddirection",
+        "geopotential_height",
+        "temperature",
+      ] {
+        assert!(
+          url.contains(&format!("{}_{}hPa", name, level)),
+          "missing {} {}",
+          name,
+          level
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn range_url_stops_at_the_forecast_horizon() {
+    let launch = parse_time("2025-07-07T23:30:00Z").unwrap();
+    let now = parse_time("2025-07-07T06:00:00Z").unwrap();
+
+    // A launch window sweep asks for every day it needs, up to the horizon.
+    let end = (launch + Duration::days(9))
+      .date_naive()
+      .min(forecast_horizon(now));
+    let url = gfs_range_url(GFS_URL, 40.0, -105.0, launch.date_naive(), end);
+    assert!(url.contains("start_date=2025-07-07&end_date=2025-07-16"));
+    assert!(url.contains("windspeed_250hPa"));
+    let end = (launch + Duration::days(20))
+      .date_naive()
+      .min(forecast_horizon(now));
+    assert_eq!(end.to_string(), "2025-07-22");
+  }
+
+  #[test]
+  fn parses_recorded_forecast() {
+    let grid = parse_response(200, GFS_FIXTURE).unwrap();
+
+    assert_eq!(grid.times.len(), 6);
+    assert_eq!(grid.times[1] - grid.times[0], 3600);
+    assert_eq!(grid.levels, PRESSURE_LEVELS.to_vec());
+    assert_eq!(grid.samples.len(), 6 * PRESSURE_LEVELS.len());
+    assert_eq!(grid.elevation, 1624.0);
+
+    // 250 hPa at the second hour.
+    let level = grid.levels.iter().position(|&l| l == 250).unwrap();
+    let sample = grid.sample(1, level).unwrap();
+    assert_eq!(sample.wind_speed, 31.2);
+    assert_eq!(sample.wind_direction, 262.0);
+    assert_eq!(sample.geopotential_height, Some(10890.0));
+    assert_eq!(sample.temperature, Some(-47.3));
+
+    // GFS has no wind below ground at 1000 hPa over the Rockies.
+    assert!(grid.sample(0, 0).is_none());
+    assert_eq!(grid.surface[0].surface_pressure, Some(836.4));
+  }
+
+  #[test]
+  fn converts_kmh_wind_to_ms() {
+    let grid = parse_response(200, KMH_FIXTURE).unwrap();
+    let level = grid.levels.iter().position(|&l| l == 500).unwrap();
+    let sample = grid.sample(0, level).unwrap();
+
+    assert!((sample.wind_speed - 20.0).abs() < 1e-9);
+    assert!((grid.surface[0].wind_speed_10m.unwrap() - 5.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn maps_http_errors() {
+    assert!(matches!(
+      parse_response(400, ERROR_FIXTURE),
+      Err(WeatherError::InvalidRequest(reason)) if reason.starts_with("Latitude must be")
+    ));
+    assert!(matches!(
+      parse_response(404, ""),
+      Err(WeatherError::NotFound)
+    ));
+    assert!(matches!(
+      parse_response(429, ERROR_FIXTURE),
+      Err(WeatherError::RateLimited)
+    ));
+    assert!(matches!(
+      parse_response(503, "<html>"),
+      Err(WeatherError::ServerError(503))
+    ));
+    assert!(matches!(
+      parse_response(418, "{}"),
+      Err(WeatherError::Http(418, _))
+    ));
+  }
+
+  #[test]
+  fn rejects_empty_forecast() {
+    let body = r#"{"latitude":0,"longitude":0,"elevation":0,"hourly":{"time":[]}}"#;
+    assert!(matches!(
+      parse_response(200, body),
+      Err(WeatherError::NoForecast)
+    ));
+
+    let body = r#"{"hourly":{"time":["2025-07-07T00:00"],"temperature_2m":[20.1]}}"#;
+    assert!(matches!(
+      parse_response(200, body),
+      Err(WeatherError::NoWindData)
+    ));
+  }
+
+  #[test]
+  fn parses_multi_location_forecast() {
+    let launch = parse_time("2025-07-07T12:00:00Z").unwrap();
+    let url = gfs_url_many(GFS_URL, &[(40.0, -105.0), (40.25, -104.75)], launch);
+    assert!(url.contains("?latitude=40.0000,40.2500&longitude=-105.0000,-104.7500&"));
+
+    let body = format!("[{},{}]", GFS_FIXTURE.trim(), GFS_FIXTURE.trim());
+    let grids = parse_multi_response(200, &body).unwrap();
+    assert_eq!(grids.len(), 2);
+    assert_eq!(grids[1], parse_response(200, GFS_FIXTURE).unwrap());
+
+    // A full batch of wind-field columns stays within one request's budget.
+    let points = vec![(40.0, -105.0); wind_field_batch_size()];
+    let url = gfs_winds_url(GFS_URL, &points, launch);
+    assert!(!url.contains("temperature") && !url.contains("geopotential"));
+    let variables = url
+      .split("hourly=")
+      .nth(1)
+      .unwrap()
+      .split('&')
+      .next()
+      .unwrap();
+    let calls = variables.split(',').count().div_ceil(VARIABLES_PER_CALL) * points.len();
+    assert!(calls <= MAX_CALLS_PER_REQUEST, "{} calls", calls);
+    assert!(points.len() >= 16);
+
+    // A single location comes back as a bare object.
+    assert_eq!(parse_multi_response(200, GFS_FIXTURE).unwrap().len(), 1);
+  }
+
+  #[test]
+  fn splits_ensemble_members() {
+    let launch = parse_time("2025-07-07T12:00:00Z").unwrap();
+    let url = ensemble_url(ENSEMBLE_URL, 40.0, -105.0, launch);
+    assert!(url.contains("models=gfs05"));
+    assert!(url.starts_with("https://ensemble-api.open-meteo.com/v1/ensemble?latitude=40.0000&"));
+    assert!(url.contains("start_date=2025-07-07&end_date=2025-07-08"));
+    assert!(url.contains("wind_speed_250hPa"));
+
+    assert_eq!(
+      split_member("wind_speed_500hPa_member07"),
+      ("wind_speed_500hPa", 7)
+    );
+    assert_eq!(split_member("wind_speed_500hPa"), ("wind_speed_500hPa", 0));
+
+    let members = parse_ensemble_response(200, GEFS_FIXTURE).unwrap();
+    assert_eq!(members.len(), 3);
+    let level = members[0].levels.iter().position(|&l| l == 500).unwrap();
+    let speeds: Vec<f64> = members
+      .iter()
+      .map(|m| m.sample(0, level).unwrap().wind_speed)
+      .collect();
+    assert_eq!(speeds, vec![13.0, 14.5, 16.0]);
+    assert_eq!(members[2].surface[0].wind_direction_10m, Some(182.0));
+    // Levels GEFS does not publish stay empty.
+    let level = members[0].levels.iter().position(|&l| l == 975).unwrap();
+    assert!(members[0].sample(0, level).is_none());
+
+    assert!(matches!(
+      parse_ensemble_response(429, ""),
+      Err(WeatherError::RateLimited)
+    ));
+  }
+
+  #[test]
+  fn skips_missing_ensemble_members() {
+    // Member 01 is absent from this response.
+    let members = parse_ensemble_response(200, GEFS_MISSING_MEMBER_FIXTURE).unwrap();
+    assert_eq!(members.len(), 2);
+    let level = members[0].levels.iter().position(|&l| l == 500).unwrap();
+    let speeds: Vec<f64> = members
+      .iter()
+      .map(|m| m.sample(0, level).unwrap().wind_speed)
+      .collect();
+    assert_eq!(speeds, vec![13.0, 16.0]);
+  }
+}
@@ -0,0 +1,96 @@
+#!/usr/bin/env python3
+"""Writes the small GFS-like GRIB2 fixtures used by the weather::grib tests.
+
+Two files, gfs_f000.grib2 and gfs_f003.grib2, hold the 2025-07-07 00Z run at
+forecast hours 0 and 3 on a 1 degree grid from 41N to 39N and 253E to 256E,
+scanned north to south like GFS. Each has UGRD, VGRD, HGT and TMP at 850, 500
+and 250 hPa, plus two messages the reader must skip: UGRD at 0.4 hPa and TMP
+at 2 m above ground. Values are simple packed with one decimal.
+
+The fields are chosen so bilinear interpolation is exact:
+  UGRD = level_u + 0.5 * (lon - 253) + hour      (m/s)
+  VGRD = -2 * (lat - 39)                         (m/s)
+  HGT  = level_height + 10 * hour                (gpm)
+  TMP  = level_temperature - 0.5 * hour          (K)
+
+Run from this directory: python3 make_gfs_grib2.py
+"""
+
+import struct
+
+LATS = [41, 40, 39]
+LONS = [253, 254, 255, 256]
+# hPa: (u at 253E, height, temperature)
+LEVELS = {850: (5.0, 1500.0, 288.0), 500: (15.0, 5800.0, 258.0), 250: (30.0, 10400.0, 222.0)}
+
+ISOBARIC = 100
+HEIGHT_ABOVE_GROUND = 103
+
+
+def section(number, body):
+    return struct.pack(">IB", 5 + len(body), number) + body
+
+
+def signed32(value):
+    """GRIB2 sign-and-magnitude integer."""
+    return struct.pack(">I", (0x80000000 | -value) if value < 0 else value)
+
+
+def identification():
+    # Centre 7 (NCEP), master tables 2, reference time is the start of the
+    # forecast, 2025-07-07 00:00, operational forecast products.
+    return section(1, struct.pack(">HHBBBHBBBBBBB", 7, 0, 2, 1, 1, 2025, 7, 7, 0, 0, 0, 0, 1))
+
+
+def grid():
+    template = struct.pack(">BBIBIBIII", 6, 0, 0, 0, 0, 0, 0, len(LONS), len(LATS))
+    template += struct.pack(">II", 0, 0xFFFFFFFF)
+    template += signed32(LATS[0] * 10**6) + signed32(LONS[0] * 10**6) + bytes([48])
+    template += signed32(LATS[-1] * 10**6) + signed32(LONS[-1] * 10**6)
+    template += struct.pack(">IIB", 10**6, 10**6, 0)
+    return section(3, struct.pack(">BIBBH", 0, len(LATS) * len(LONS), 0, 0, 0) + template)
+
+
+def product(category, number, hour, surface, value):
+    template = struct.pack(">BBBBBHBBI", category, number, 2, 0, 96, 0, 0, 1, hour)
+    template += struct.pack(">BBI", surface, 0, value) + struct.pack(">BBI", 255, 0, 0)
+    return section(4, struct.pack(">HH", 0, 0) + template)
+
+
+def data(values):
+    scaled = [round(v * 10) for v in values]
+    reference = min(scaled)
+    bits = 24
+    representation = struct.pack(">IH", len(values), 0)
+    representation += struct.pack(">fHHBB", float(reference), 0, 1, bits, 0)
+    packed = 0
+    for v in scaled:
+        packed = (packed << bits) | (v - reference)
+    payload = packed.to_bytes(len(values) * bits // 8, "big")
+    return section(5, representation) + section(6, bytes([255])) + section(7, payload)
+
+
+def message(category, number, hour, surface, value, field):
+    values = [field(lat, lon) for lat in LATS for lon in LONS]
+    body = identification() + grid() + product(category, number, hour, surface, value)
+    body += data(values) + b"7777"
+    total = 16 + len(body)
+    return b"GRIB" + bytes([0, 0, 0, 2]) + struct.pack(">Q", total) + body
+
+
+def write(hour):
+    messages = []
+    for level, (u, height, temperature) in LEVELS.items():
+        pa = level * 100
+        messages.append(message(2, 2, hour, ISOBARIC, pa, lambda la, lo: u + 0.5 * (lo - 253) + hour))
+        messages.append(message(2, 3, hour, ISOBARIC, pa, lambda la, lo: -2.0 * (la - 39)))
+        messages.append(message(3, 5, hour, ISOBARIC, pa, lambda la, lo: height + 10.0 * hour))
+        messages.append(message(0, 0, hour, ISOBARIC, pa, lambda la, lo: temperature - 0.5 * hour))
+    messages.append(message(2, 2, hour, ISOBARIC, 40, lambda la, lo: 99.0))
+    messages.append(message(0, 0, hour, HEIGHT_ABOVE_GROUND, 2, lambda la, lo: 300.0))
+    with open("gfs_f{:03d}.grib2".format(hour), "wb") as f:
+        f.write(b"".join(messages))
+
+
+for hour in (0, 3):
+    write(hour)
@@ -7,6 +7,7 @@ import LiabilityModal from './components/LiabilityModal';
 import ErrorBoundary from './components/ErrorBoundary';
 import { getARTCC } from './services/atcService';
 import { initializeElevationCache } from './services/elevationService';
+import { initializePredictionApi } from './services/predictionApiService';
 
 const getDefaultLaunchTime = () => {
   const now = new Date();
@@ -65,6 +66,7 @@ const App: React.FC = () => {
 
   useEffect(() => {
     initializeElevationCache();
+    initializePredictionApi();
   }, []);
 
   const handlePredict = () => {
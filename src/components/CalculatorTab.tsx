@@ -1,6 +1,7 @@
-import React, { useState } from 'react';
-import { CalculatorParams, UnitSystem, LaunchParams, GoalCalculationResult } from '../types/index';
+import React, { useEffect, useState } from 'react';
+import { CalculatorParams, UnitSystem, LaunchParams, GoalCalculationResult, BalloonSpec, ParachuteSpec } from '../types/index';
 import { calculateFlightPerformance, calculateGoalOptions } from '../services/predictionService';
+import { listBalloons, listParachutes, solveNeckLift } from '../services/catalogService';
 import {
   metersToFeet, feetToMeters,
   msToFts,
@@ -29,6 +30,15 @@ const CalculatorTab: React.FC<CalculatorTabProps> = ({
   const [isGoalMode, setIsGoalMode] = useState(false);
   const [targetBurstAltitude, setTargetBurstAltitude] = useState(30000); // Default 30km
   const [goalResults, setGoalResults] = useState<GoalCalculationResult | null>(null);
+  const [balloonSpecs, setBalloonSpecs] = useState<BalloonSpec[]>([]);
+  const [parachuteSpecs, setParachuteSpecs] = useState<ParachuteSpec[]>([]);
+
+  useEffect(() => {
+    // The catalog lives in the native backend; without it the weights are
+    // entered by hand.
+    listBalloons().then(setBalloonSpecs).catch(() => setBalloonSpecs([]));
+    listParachutes().then(setParachuteSpecs).catch(() => setParachuteSpecs([]));
+  }, []);
 
   const isImperial = unitSystem === 'imperial';
   const wUnit = isImperial ? 'oz' : 'g';
@@ -42,12 +52,65 @@ const CalculatorTab: React.FC<CalculatorTabProps> = ({
     }
     const numericValue = parseFloat(value) || 0;
     const metricValue = isImperial ? ozToG(numericValue) : numericValue;
+    if (field === 'parachuteWeight') {
+      // Likewise for the catalog parachute, whose drag model goes with it.
+      setCalculatorParams({ ...calculatorParams, parachuteWeight: metricValue, parachuteModel: undefined });
+      setMissionParams({ ...missionParams, parachute: undefined });
+      return;
+    }
+    if (field === 'balloonWeight') {
+      // A hand-entered weight no longer matches the catalog balloon.
+      setCalculatorParams({ ...calculatorParams, balloonWeight: metricValue, balloonModel: undefined, burstDiameter: undefined });
+      return;
+    }
     setCalculatorParams({ ...calculatorParams, [field]: metricValue });
   };
 
+  const handleBalloonModelChange = (id: string) => {
+    const spec = balloonSpecs.find(b => b.id === id);
+    setCalculatorParams({
+      ...calculatorParams,
+      balloonModel: spec?.id,
+      burstDiameter: spec?.burstDiameter,
+      balloonWeight: spec ? spec.mass * 1000 : calculatorParams.balloonWeight,
+    });
+  };
+
+  // Everything hanging under the canopy after burst, kg.
+  const descentMass = (params: CalculatorParams) => (params.payloadWeight + params.parachuteWeight) / 1000;
+
+  const handleParachuteModelChange = (id: string) => {
+    const spec = parachuteSpecs.find(p => p.id === id);
+    const params = {
+      ...calculatorParams,
+      parachuteModel: spec?.id,
+      parachuteWeight: spec ? spec.mass * 1000 : calculatorParams.parachuteWeight,
+    };
+    setCalculatorParams(params);
+    // The native engine descends on the canopy's drag instead of the fixed descent rate.
+    setMissionParams({
+      ...missionParams,
+      parachute: spec
+        ? { diameter: spec.diameter, dragCoefficient: spec.dragCoefficient, payloadMass: descentMass(params) }
+        : undefined,
+    });
+  };
+
   const handleCalculate = () => {
     setCalcError(null);
     setCalculationDetails(null);
+    if (calculatorParams.balloonModel) {
+      // A catalog balloon goes through the native ascent model, which uses the
+      // drag and burst diameter calibrated from its recorded flights.
+      const payloadMass = (calculatorParams.payloadWeight + calculatorParams.parachuteWeight) / 1000;
+      solveNeckLift(calculatorParams.balloonModel, calculatorParams.gas.toLowerCase(), missionParams.launchAltitude, {
+        neckLift: payloadMass + calculatorParams.neckLift / 1000,
+        payloadMass,
+      })
+        .then(solution => setCalculationDetails({ ascentRate: solution.ascentRate, burstAltitude: solution.burstAltitude }))
+        .catch(error => setCalcError(String(error)));
+      return;
+    }
     const results = calculateFlightPerformance(calculatorParams, missionParams.launchAltitude);
     if (!results) {
       setCalcError('Invalid input. Please check balloon weight and other values.');
@@ -62,6 +125,7 @@ const CalculatorTab: React.FC<CalculatorTabProps> = ({
         ...missionParams,
         ascentRate: calculationDetails.ascentRate,
         burstAltitude: calculationDetails.burstAltitude,
+        parachute: missionParams.parachute && { ...missionParams.parachute, payloadMass: descentMass(calculatorParams) },
       });
       if (onApplyAndSwitch) onApplyAndSwitch();
     }
@@ -101,7 +165,11 @@ const CalculatorTab: React.FC<CalculatorTabProps> = ({
     setMissionParams({
       ...missionParams,
       ascentRate,
-      burstAltitude
+      burstAltitude,
+      parachute: missionParams.parachute && {
+        ...missionParams.parachute,
+        payloadMass: descentMass({ ...calculatorParams, payloadWeight }),
+      },
     });
     
     if (onApplyAndSwitch) onApplyAndSwitch();
@@ -228,6 +296,38 @@ const CalculatorTab: React.FC<CalculatorTabProps> = ({
       {/* Normal Mode Input Fields */}
       {!isGoalMode && (
         <>
+          {balloonSpecs.length > 0 && (
+            <div className="grid grid-cols-2 gap-4 mb-4">
+              <div>
+                <label className="block text-sm font-medium text-gray-400">Balloon Model</label>
+                <select
+                  value={calculatorParams.balloonModel ?? ''}
+                  onChange={e => handleBalloonModelChange(e.target.value)}
+                  className="mt-1 block w-full bg-gray-700 border-gray-600 rounded-md shadow-sm py-2 px-3 text-white focus:outline-none focus:ring-cyan-500 focus:border-cyan-500"
+                >
+                  <option value="">Custom</option>
+                  {balloonSpecs.map(b => (
+                    <option key={b.id} value={b.id}>{b.manufacturer} {b.model}</option>
+                  ))}
+                </select>
+                <p className="text-xs text-gray-400 mt-1">Runs the balloon's ascent model, calibrated from its recorded flights.</p>
+              </div>
+              <div>
+                <label className="block text-sm font-medium text-gray-400">Parachute Model</label>
+                <select
+                  value={calculatorParams.parachuteModel ?? ''}
+                  onChange={e => handleParachuteModelChange(e.target.value)}
+                  className="mt-1 block w-full bg-gray-700 border-gray-600 rounded-md shadow-sm py-2 px-3 text-white focus:outline-none focus:ring-cyan-500 focus:border-cyan-500"
+                >
+                  <option value="">Custom</option>
+                  {parachuteSpecs.map(p => (
+                    <option key={p.id} value={p.id}>{p.manufacturer} {p.model}</option>
+                  ))}
+                </select>
+                <p className="text-xs text-gray-400 mt-1">Fills in the parachute weight and sets the descent from its canopy's drag.</p>
+              </div>
+            </div>
+          )}
           <div className="grid grid-cols-2 gap-4 mb-4">
             <div>
               <label className="block text-sm font-medium text-gray-400">Payload Weight ({wUnit})</label>
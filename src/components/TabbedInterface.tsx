@@ -1,5 +1,5 @@
 import React, { useState, useRef, useEffect, useCallback } from 'react';
-import { LaunchParams, UnitSystem, CalculatorParams, PredictionResult, WeatherData, APRSPosition } from '../types/index';
+import { LaunchParams, UnitSystem, CalculatorParams, PredictionResult, WeatherData, APRSPosition, PredictionApiStatus } from '../types/index';
 import MissionPlanner from './MissionPlanner';
 import SafetyInfo from './SafetyInfo';
 import LeafletVisualization from './LeafletVisualization';
@@ -9,7 +9,8 @@ import { ComprehensiveWeather } from '../types/index';
 import GlobeVisualization from './GlobeVisualization';
 import LivePredictionPanel from './LivePredictionPanel';
 import APRSService from '../services/aprsService';
-import { getCacheStats } from '../services/elevationService';
+import { getCacheStats, setDemFolder } from '../services/elevationService';
+import { DEFAULT_PREDICTION_API_PORT, getPredictionApiStatus, parseAllowedOrigins, startPredictionApi, stopPredictionApi } from '../services/predictionApiService';
 import { AlgorithmComparisonPanel } from './AlgorithmComparisonPanel';
 
 interface TabbedInterfaceProps {
@@ -310,6 +311,13 @@ const SettingsTab: React.FC = () => {
   const [apiKey, setApiKey] = useState('');
   const [callsign, setCallsign] = useState('');
   const [cesiumToken, setCesiumToken] = useState('');
+  const [demFolder, setDemFolderPath] = useState('');
+  const [demTiles, setDemTiles] = useState<number | null>(null);
+  const [apiEnabled, setApiEnabled] = useState(false);
+  const [apiPort, setApiPort] = useState(DEFAULT_PREDICTION_API_PORT);
+  const [apiOrigins, setApiOrigins] = useState('');
+  const [apiStatus, setApiStatus] = useState<PredictionApiStatus | null>(null);
+  const [apiError, setApiError] = useState<string | null>(null);
   const [saved, setSaved] = useState(false);
   const [cacheStats, setCacheStats] = useState<{ singlePoints: number; grids: number; totalSize: number }>({ singlePoints: 0, grids: 0, totalSize: 0 });
 
@@ -320,15 +328,32 @@ const SettingsTab: React.FC = () => {
     if (storedCallsign) setCallsign(storedCallsign);
     const storedCesiumToken = localStorage.getItem('cesiumIonAccessToken');
     if (storedCesiumToken) setCesiumToken(storedCesiumToken);
+    const storedDemFolder = localStorage.getItem('demFolder');
+    if (storedDemFolder) setDemFolderPath(storedDemFolder);
+    setApiEnabled(localStorage.getItem('predictionApiEnabled') === 'true');
+    const storedApiPort = parseInt(localStorage.getItem('predictionApiPort') ?? '', 10);
+    if (Number.isFinite(storedApiPort)) setApiPort(storedApiPort);
+    const storedApiOrigins = localStorage.getItem('predictionApiAllowedOrigins');
+    if (storedApiOrigins) setApiOrigins(storedApiOrigins);
+    getPredictionApiStatus().then(setApiStatus).catch(() => setApiStatus(null));
     
     // Update cache statistics
-    setCacheStats(getCacheStats());
+    getCacheStats().then(setCacheStats);
   }, []);
 
   const handleSave = () => {
     localStorage.setItem('aprsFiApiKey', apiKey);
     localStorage.setItem('aprsFiCallsign', callsign);
     localStorage.setItem('cesiumIonAccessToken', cesiumToken);
+    localStorage.setItem('demFolder', demFolder);
+    setDemFolder(demFolder).then(setDemTiles);
+    localStorage.setItem('predictionApiEnabled', String(apiEnabled));
+    localStorage.setItem('predictionApiPort', String(apiPort));
+    localStorage.setItem('predictionApiAllowedOrigins', apiOrigins);
+    setApiError(null);
+    (apiEnabled ? startPredictionApi(apiPort, parseAllowedOrigins(apiOrigins)) : stopPredictionApi())
+      .then(setApiStatus)
+      .catch(error => setApiError(String(error)));
     setSaved(true);
     setTimeout(() => setSaved(false), 2000);
   };
@@ -384,6 +409,60 @@ const SettingsTab: React.FC = () => {
           placeholder="Enter your Cesium Ion access token"
         />
       </div>
+      <div className="mb-4">
+        <label className="block text-gray-300 font-medium mb-1">Local DEM Folder</label>
+        <div className="text-xs text-gray-400 mb-1">
+          Folder of SRTM <code>.hgt</code> tiles (e.g. <code>N40W106.hgt</code>) or Copernicus DEM GeoTIFFs, used for ground elevation and terrain checks offline. Leave empty to use the online elevation service.
+        </div>
+        <input
+          type="text"
+          value={demFolder}
+          onChange={e => setDemFolderPath(e.target.value)}
+          className="w-full px-3 py-2 rounded bg-gray-700 border border-gray-600 text-white focus:outline-none focus:ring focus:border-cyan-500"
+          placeholder="/path/to/dem"
+        />
+        {demTiles !== null && demFolder && (
+          <div className="text-xs text-gray-400 mt-1">{demTiles} DEM tile{demTiles === 1 ? '' : 's'} found</div>
+        )}
+      </div>
+      <div className="mb-4">
+        <label className="block text-gray-300 font-medium mb-1">Local Prediction API</label>
+        <div className="text-xs text-gray-400 mb-1">
+          Serves BLiPS predictions in the CUSF Tawhiri format (<code>/api/v1/</code>, standard and float profiles) on this computer only, so chase-car dashboards and scripts can use it offline.
+        </div>
+        <div className="flex items-center gap-3">
+          <label className="flex items-center gap-2 text-gray-300">
+            <input
+              type="checkbox"
+              checked={apiEnabled}
+              onChange={e => setApiEnabled(e.target.checked)}
+            />
+            Enable
+          </label>
+          <input
+            type="number"
+            min={1}
+            max={65535}
+            value={apiPort}
+            onChange={e => setApiPort(parseInt(e.target.value, 10) || DEFAULT_PREDICTION_API_PORT)}
+            className="w-28 px-3 py-2 rounded bg-gray-700 border border-gray-600 text-white focus:outline-none focus:ring focus:border-cyan-500"
+          />
+        </div>
+        <div className="text-xs text-gray-400 mt-2 mb-1">
+          Web pages allowed to call the API, separated by commas. Scripts and other non-browser clients need no entry.
+        </div>
+        <input
+          type="text"
+          value={apiOrigins}
+          onChange={e => setApiOrigins(e.target.value)}
+          className="w-full px-3 py-2 rounded bg-gray-700 border border-gray-600 text-white focus:outline-none focus:ring focus:border-cyan-500"
+          placeholder="http://localhost:3000, http://192.168.1.20:8080"
+        />
+        {apiStatus?.running && apiStatus.url && (
+          <div className="text-xs text-gray-400 mt-1">Serving at <code>{apiStatus.url}</code></div>
+        )}
+        {apiError && <div className="text-xs text-red-400 mt-1">{apiError}</div>}
+      </div>
       <button
         onClick={handleSave}
         className="mt-2 px-4 py-2 bg-blue-600 hover:bg-blue-700 text-white font-semibold rounded shadow transition"
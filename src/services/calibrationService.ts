@@ -0,0 +1,33 @@
+/**
+ * Calibration Service
+ * Flight history and the per-balloon drag coefficient and burst diameter
+ * fitted from it
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { Calibration, FlightRecord } from '../types';
+
+/**
+ * Record a flown balloon with its measured ascent rate and burst altitude
+ */
+export async function recordFlight(flight: FlightRecord): Promise<FlightRecord> {
+  return await invoke<FlightRecord>('record_flight', { flight });
+}
+
+/**
+ * Recorded flights, most recent first, optionally of one balloon model
+ */
+export async function listFlights(balloon?: string): Promise<FlightRecord[]> {
+  return await invoke<FlightRecord[]>('list_flights', { balloon });
+}
+
+export async function deleteFlight(id: number): Promise<boolean> {
+  return await invoke<boolean>('delete_flight', { id });
+}
+
+/**
+ * Fitted corrections for a catalog balloon, with confidence intervals
+ */
+export async function getBalloonCalibration(balloon: string): Promise<Calibration> {
+  return await invoke<Calibration>('balloon_calibration', { balloon });
+}
@@ -0,0 +1,50 @@
+/**
+ * Catalog Service
+ * Manufacturer balloon, parachute and lift gas specs from the native catalog,
+ * plus the user's own entries
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { BalloonSpec, Catalog, CatalogEntry, LiftSolution, LiftTargets, ParachuteSpec } from '../types';
+
+/**
+ * Bundled specs merged with the user's entries
+ */
+export async function getCatalog(): Promise<Catalog> {
+  return await invoke<Catalog>('get_catalog');
+}
+
+export async function listBalloons(manufacturer?: string): Promise<BalloonSpec[]> {
+  return await invoke<BalloonSpec[]>('list_balloons', { manufacturer });
+}
+
+export async function listParachutes(): Promise<ParachuteSpec[]> {
+  return await invoke<ParachuteSpec[]>('list_parachutes');
+}
+
+/**
+ * Add a user entry, or replace one (bundled or not) with the same id
+ */
+export async function saveCatalogEntry(entry: CatalogEntry): Promise<CatalogEntry> {
+  return await invoke<CatalogEntry>('save_catalog_entry', { entry });
+}
+
+/**
+ * Remove a user entry; a bundled entry it replaced comes back
+ */
+export async function deleteCatalogEntry(kind: CatalogEntry['kind'], id: string): Promise<boolean> {
+  return await invoke<boolean>('delete_catalog_entry', { kind, id });
+}
+
+/**
+ * Solve for the two of ascent rate, burst altitude, neck lift and payload
+ * mass that `targets` leaves out, for a catalog balloon and gas
+ */
+export async function solveNeckLift(
+  balloon: string,
+  gas: string,
+  launchAltitude: number,
+  targets: LiftTargets
+): Promise<LiftSolution> {
+  return await invoke<LiftSolution>('solve_neck_lift', { balloon, gas, launchAltitude, targets });
+}
@@ -3,6 +3,8 @@
  * Fetches ground altitude for given coordinates with caching
  */
 
+import { invoke } from '@tauri-apps/api/core';
+
 interface ElevationResponse {
   results: Array<{
     latitude: number;
@@ -11,65 +13,15 @@ interface ElevationResponse {
   }>;
 }
 
-interface CachedElevation {
-  elevation: number;
-  timestamp: number;
-  lat: number;
-  lon: number;
-}
-
-interface CachedElevationGrid {
-  grid: number[][];
-  timestamp: number;
-  centerLat: number;
-  centerLon: number;
-  radius: number;
-  gridSize: number;
-}
-
-// Cache configuration
-const CACHE_DURATION_MS = 24 * 60 * 60 * 1000; // 24 hours
-const CACHE_KEY_PREFIX = 'blips_elevation_';
-const GRID_CACHE_KEY_PREFIX = 'blips_elevation_grid_';
-const COORDINATE_PRECISION = 4; // Decimal places for coordinate matching
-
-/**
- * Get cache key for a single coordinate pair
- */
-function getCacheKey(lat: number, lon: number): string {
-  const roundedLat = Math.round(lat * Math.pow(10, COORDINATE_PRECISION)) / Math.pow(10, COORDINATE_PRECISION);
-  const roundedLon = Math.round(lon * Math.pow(10, COORDINATE_PRECISION)) / Math.pow(10, COORDINATE_PRECISION);
-  return `${CACHE_KEY_PREFIX}${roundedLat}_${roundedLon}`;
-}
-
-/**
- * Get cache key for elevation grid
- */
-function getGridCacheKey(lat: number, lon: number, radius: number, gridSize: number): string {
-  const roundedLat = Math.round(lat * Math.pow(10, COORDINATE_PRECISION)) / Math.pow(10, COORDINATE_PRECISION);
-  const roundedLon = Math.round(lon * Math.pow(10, COORDINATE_PRECISION)) / Math.pow(10, COORDINATE_PRECISION);
-  return `${GRID_CACHE_KEY_PREFIX}${roundedLat}_${roundedLon}_${radius}_${gridSize}`;
-}
+// Elevations are cached natively (SQLite in the app data directory) through
+// the elevation cache commands in src-tauri/src/elevation.
 
 /**
  * Get cached elevation data
  */
-function getCachedElevation(lat: number, lon: number): number | null {
+async function getCachedElevation(lat: number, lon: number): Promise<number | null> {
   try {
-    const cacheKey = getCacheKey(lat, lon);
-    const cached = localStorage.getItem(cacheKey);
-    if (!cached) return null;
-
-    const data: CachedElevation = JSON.parse(cached);
-    const now = Date.now();
-
-    // Check if cache is still valid
-    if (now - data.timestamp > CACHE_DURATION_MS) {
-      localStorage.removeItem(cacheKey);
-      return null;
-    }
-
-    return data.elevation;
+    return await invoke<number | null>('get_cached_elevation', { lat, lon });
   } catch (error) {
     console.warn('Failed to read elevation cache:', error);
     return null;
@@ -79,16 +31,9 @@ function getCachedElevation(lat: number, lon: number): number | null {
 /**
  * Cache elevation data
  */
-function cacheElevation(lat: number, lon: number, elevation: number): void {
+async function cacheElevation(lat: number, lon: number, elevation: number): Promise<void> {
   try {
-    const cacheKey = getCacheKey(lat, lon);
-    const data: CachedElevation = {
-      elevation,
-      timestamp: Date.now(),
-      lat,
-      lon
-    };
-    localStorage.setItem(cacheKey, JSON.stringify(data));
+    await invoke('cache_elevations', { points: [{ lat, lon, elevation }] });
   } catch (error) {
     console.warn('Failed to cache elevation data:', error);
   }
@@ -97,22 +42,9 @@ function cacheElevation(lat: number, lon: number, elevation: number): void {
 /**
  * Get cached elevation grid data
  */
-function getCachedElevationGrid(lat: number, lon: number, radius: number, gridSize: number): number[][] | null {
+async function getCachedElevationGrid(lat: number, lon: number, radius: number, gridSize: number): Promise<number[][] | null> {
   try {
-    const cacheKey = getGridCacheKey(lat, lon, radius, gridSize);
-    const cached = localStorage.getItem(cacheKey);
-    if (!cached) return null;
-
-    const data: CachedElevationGrid = JSON.parse(cached);
-    const now = Date.now();
-
-    // Check if cache is still valid
-    if (now - data.timestamp > CACHE_DURATION_MS) {
-      localStorage.removeItem(cacheKey);
-      return null;
-    }
-
-    return data.grid;
+    return await invoke<number[][] | null>('get_cached_elevation_grid', { lat, lon, radius, gridSize });
   } catch (error) {
     console.warn('Failed to read elevation grid cache:', error);
     return null;
@@ -122,18 +54,9 @@ function getCachedElevationGrid(lat: number, lon: number, radius: number, gridSi
 /**
  * Cache elevation grid data
  */
-function cacheElevationGrid(lat: number, lon: number, radius: number, gridSize: number, grid: number[][]): void {
+async function cacheElevationGrid(lat: number, lon: number, radius: number, grid: number[][]): Promise<void> {
   try {
-    const cacheKey = getGridCacheKey(lat, lon, radius, gridSize);
-    const data: CachedElevationGrid = {
-      grid,
-      timestamp: Date.now(),
-      centerLat: lat,
-      centerLon: lon,
-      radius,
-      gridSize
-    };
-    localStorage.setItem(cacheKey, JSON.stringify(data));
+    await invoke('cache_elevation_grid', { lat, lon, radius, grid });
   } catch (error) {
     console.warn('Failed to cache elevation grid data:', error);
   }
@@ -142,28 +65,9 @@ function cacheElevationGrid(lat: number, lon: number, radius: number, gridSize:
 /**
  * Clear old cache entries
  */
-function cleanupCache(): void {
+async function cleanupCache(): Promise<void> {
   try {
-    const keys = Object.keys(localStorage);
-    const now = Date.now();
-    let cleanedCount = 0;
-
-    keys.forEach(key => {
-      if (key.startsWith(CACHE_KEY_PREFIX) || key.startsWith(GRID_CACHE_KEY_PREFIX)) {
-        try {
-          const data = JSON.parse(localStorage.getItem(key) || '{}');
-          if (now - data.timestamp > CACHE_DURATION_MS) {
-            localStorage.removeItem(key);
-            cleanedCount++;
-          }
-        } catch {
-          // Remove invalid cache entries
-          localStorage.removeItem(key);
-          cleanedCount++;
-        }
-      }
-    });
-
+    const cleanedCount = await invoke<number>('cleanup_elevation_cache');
     if (cleanedCount > 0) {
       console.log(`Cleaned up ${cleanedCount} expired elevation cache entries`);
     }
@@ -172,13 +76,46 @@ function cleanupCache(): void {
   }
 }
 
+/**
+ * Look up terrain in the local DEM (SRTM .hgt / Copernicus GeoTIFF tiles in
+ * the configured folder). Returns null when no tile covers the point.
+ */
+async function getDemElevation(lat: number, lon: number): Promise<number | null> {
+  try {
+    return await invoke<number | null>('get_ground_elevation', { lat, lon });
+  } catch (error) {
+    console.warn('Failed to read local DEM:', error);
+    return null;
+  }
+}
+
+/**
+ * Point the native DEM provider at a folder of elevation tiles, or turn it
+ * off with an empty path. Returns the number of tiles found.
+ */
+export async function setDemFolder(path: string): Promise<number> {
+  try {
+    const status = await invoke<{ folder: string | null; tiles: number }>('set_dem_folder', { path: path || null });
+    return status.tiles;
+  } catch (error) {
+    console.warn('Failed to set DEM folder:', error);
+    return 0;
+  }
+}
+
 /**
  * Get ground elevation for given coordinates with caching
- * Uses Open Elevation API as it's free and doesn't require API keys
+ * Uses the local DEM when it covers the point, otherwise the Open Elevation
+ * API as it's free and doesn't require API keys
  */
 export async function getGroundElevation(lat: number, lon: number): Promise<number> {
+  const dem = await getDemElevation(lat, lon);
+  if (dem !== null) {
+    return Math.max(dem + 10, 50); // Same margin as the API result below
+  }
+
   // Check cache first
-  const cached = getCachedElevation(lat, lon);
+  const cached = await getCachedElevation(lat, lon);
   if (cached !== null) {
     return cached;
   }
@@ -210,7 +147,7 @@ export async function getGroundElevation(lat: number, lon: number): Promise<numb
       const finalElevation = Math.max(elevation + 10, 50); // Minimum 50m above sea level
       
       // Cache the result
-      cacheElevation(lat, lon, finalElevation);
+      await cacheElevation(lat, lon, finalElevation);
       
       return finalElevation;
     }
@@ -236,8 +173,17 @@ export async function getElevationGrid(
   radiusMeters: number = 500,
   gridSize: number = 5
 ): Promise<number[][]> {
+  try {
+    const demGrid = await invoke<number[][] | null>('get_elevation_grid', { lat, lon, radius: radiusMeters, gridSize });
+    if (demGrid !== null) {
+      return demGrid;
+    }
+  } catch (error) {
+    console.warn('Failed to read local DEM grid:', error);
+  }
+
   // Check cache first
-  const cached = getCachedElevationGrid(lat, lon, radiusMeters, gridSize);
+  const cached = await getCachedElevationGrid(lat, lon, radiusMeters, gridSize);
   if (cached !== null) {
     return cached;
   }
@@ -282,14 +228,14 @@ export async function getElevationGrid(
     }
     
     // Cache the result
-    cacheElevationGrid(lat, lon, radiusMeters, gridSize, grid);
+    await cacheElevationGrid(lat, lon, radiusMeters, grid);
     
     return grid;
   } catch (error) {
     console.warn('Failed to fetch elevation grid:', error);
     // Fallback: flat grid at 100m
     const fallbackGrid = Array.from({ length: gridSize }, () => Array(gridSize).fill(100));
-    cacheElevationGrid(lat, lon, radiusMeters, gridSize, fallbackGrid);
+    await cacheElevationGrid(lat, lon, radiusMeters, fallbackGrid);
     return fallbackGrid;
   }
 }
@@ -458,6 +404,11 @@ export function formatElevation(meters: number, isImperial: boolean): string {
  * Initialize cache cleanup on app startup
  */
 export function initializeElevationCache(): void {
+  const demFolder = localStorage.getItem('demFolder');
+  if (demFolder) {
+    setDemFolder(demFolder);
+  }
+
   // Clean up old cache entries on startup
   cleanupCache();
   
@@ -468,26 +419,11 @@ export function initializeElevationCache(): void {
 /**
  * Get cache statistics for debugging
  */
-export function getCacheStats(): { singlePoints: number; grids: number; totalSize: number } {
+export async function getCacheStats(): Promise<{ singlePoints: number; grids: number; totalSize: number }> {
   try {
-    const keys = Object.keys(localStorage);
-    let singlePoints = 0;
-    let grids = 0;
-    let totalSize = 0;
-
-    keys.forEach(key => {
-      if (key.startsWith(CACHE_KEY_PREFIX)) {
-        singlePoints++;
-        totalSize += localStorage.getItem(key)?.length || 0;
-      } else if (key.startsWith(GRID_CACHE_KEY_PREFIX)) {
-        grids++;
-        totalSize += localStorage.getItem(key)?.length || 0;
-      }
-    });
-
-    return { singlePoints, grids, totalSize };
+    return await invoke<{ singlePoints: number; grids: number; totalSize: number }>('elevation_cache_stats');
   } catch (error) {
     console.warn('Failed to get cache stats:', error);
     return { singlePoints: 0, grids: 0, totalSize: 0 };
   }
-} 
\ No newline at end of file
+}
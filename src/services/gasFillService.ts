@@ -0,0 +1,33 @@
+/**
+ * Gas Fill Service
+ * Plans the launch-day fill (gas volume, cylinders, checklist) and records
+ * measured neck lift on the mission
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { FillAdjustment, FillPlan, FillRecord, FillRequest } from '../types';
+
+/**
+ * Gas volume at ambient conditions and cylinder use for a target neck lift
+ */
+export async function planGasFill(request: FillRequest): Promise<FillPlan> {
+  return await invoke<FillPlan>('plan_gas_fill', { request });
+}
+
+/**
+ * Save a fill plan on a mission, starting a fresh fill record
+ */
+export async function startGasFill(mission: string, plan: FillPlan): Promise<FillRecord> {
+  return await invoke<FillRecord>('start_gas_fill', { mission, plan });
+}
+
+export async function checkFillStep(mission: string, step: number, done: boolean): Promise<FillRecord> {
+  return await invoke<FillRecord>('check_fill_step', { mission, step, done });
+}
+
+/**
+ * Record a measured neck lift (kg) and get how much gas to add or vent
+ */
+export async function recordNeckLift(mission: string, neckLift: number): Promise<FillAdjustment> {
+  return await invoke<FillAdjustment>('record_neck_lift', { mission, neckLift });
+}
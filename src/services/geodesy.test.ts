@@ -0,0 +1,42 @@
+import { describe, it, expect } from 'vitest';
+import { bearing, distance, inverse, normalizeLon } from './geodesy';
+
+// The same reference cases as the tests in src-tauri/src/geodesy.rs, so the
+// two implementations cannot drift apart unnoticed.
+
+const dms = (degrees: number, minutes: number, seconds: number) =>
+  Math.sign(degrees) * (Math.abs(degrees) + minutes / 60 + seconds / 3600);
+
+describe('geodesy', () => {
+  it('matches the Vincenty reference line', () => {
+    // Flinders Peak to Buninyong, from Vincenty's paper via Geoscience Australia.
+    const [lat1, lon1] = [dms(-37, 57, 3.72030), dms(144, 25, 29.52440)];
+    const [lat2, lon2] = [dms(-37, 39, 10.15610), dms(143, 55, 35.38390)];
+
+    const line = inverse(lat1, lon1, lat2, lon2);
+    expect(Math.abs(line.distance - 54972.271)).toBeLessThan(1e-3);
+    expect(Math.abs(line.initialBearing - dms(306, 52, 5.37))).toBeLessThan(1e-5);
+    expect(Math.abs(line.finalBearing - dms(307, 10, 25.07))).toBeLessThan(1e-5);
+    expect(distance(lat1, lon1, lat2, lon2)).toBe(line.distance);
+    expect(bearing(lat1, lon1, lat2, lon2)).toBe(line.initialBearing);
+  });
+
+  it('crosses the antimeridian', () => {
+    // 0.2° of longitude on the equator, not 359.8°.
+    const line = inverse(0, 179.9, 0, -179.9);
+    expect(Math.abs(line.distance - 22263.9)).toBeLessThan(0.1);
+    expect(Math.abs(line.initialBearing - 90)).toBeLessThan(1e-9);
+    expect(normalizeLon(180)).toBe(-180);
+    expect(normalizeLon(-190)).toBe(170);
+  });
+
+  it('measures nothing between meridians at the pole', () => {
+    expect(Math.abs(distance(90, 0, 90, 123))).toBeLessThan(1e-9);
+  });
+
+  it('falls back near antipodes', () => {
+    const line = inverse(0, 0, 0.5, 179.7);
+    expect(Number.isFinite(line.distance)).toBe(true);
+    expect(Math.abs(line.distance - 19_936_000)).toBeLessThan(40_000);
+  });
+});
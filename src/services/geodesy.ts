@@ -0,0 +1,116 @@
+/**
+ * Geodesics on the WGS84 ellipsoid
+ * Vincenty's inverse solution, as in src-tauri/src/geodesy.rs, for code that
+ * needs distances and bearings synchronously. geodesy.test.ts checks it
+ * against the same reference values as the Rust tests.
+ */
+
+const WGS84_A = 6378137;
+const WGS84_F = 1 / 298.257223563;
+const WGS84_B = WGS84_A * (1 - WGS84_F);
+const MEAN_RADIUS_M = (2 * WGS84_A + WGS84_B) / 3;
+
+const toRad = (deg: number) => deg * Math.PI / 180;
+const toDeg = (rad: number) => rad * 180 / Math.PI;
+const toBearing = (rad: number) => (toDeg(rad) % 360 + 360) % 360;
+
+/**
+ * Wrap a longitude into [-180, 180)
+ */
+export function normalizeLon(lon: number): number {
+  return ((lon + 180) % 360 + 360) % 360 - 180;
+}
+
+export interface Geodesic {
+  /** meters */
+  distance: number;
+  /** Forward azimuth at the first point, degrees clockwise from north */
+  initialBearing: number;
+  /** Forward azimuth at the second point, degrees */
+  finalBearing: number;
+}
+
+/**
+ * Great circle on the mean sphere, for nearly antipodal points where
+ * Vincenty's iteration does not converge
+ */
+function sphericalInverse(lat1: number, lon1: number, lat2: number, lon2: number): Geodesic {
+  const phi1 = toRad(lat1);
+  const phi2 = toRad(lat2);
+  const dLon = toRad(normalizeLon(lon2 - lon1));
+  const a = Math.sin((phi2 - phi1) / 2) ** 2 +
+            Math.cos(phi1) * Math.cos(phi2) * Math.sin(dLon / 2) ** 2;
+  const bearing = (p1: number, p2: number, dl: number) => toBearing(Math.atan2(
+    Math.sin(dl) * Math.cos(p2),
+    Math.cos(p1) * Math.sin(p2) - Math.sin(p1) * Math.cos(p2) * Math.cos(dl)
+  ));
+  return {
+    distance: 2 * MEAN_RADIUS_M * Math.atan2(Math.sqrt(a), Math.sqrt(1 - a)),
+    initialBearing: bearing(phi1, phi2, dLon),
+    finalBearing: (bearing(phi2, phi1, -dLon) + 180) % 360,
+  };
+}
+
+/**
+ * Distance and bearings between two points. Longitudes may lie on either
+ * side of the antimeridian.
+ */
+export function inverse(lat1: number, lon1: number, lat2: number, lon2: number): Geodesic {
+  const L = toRad(normalizeLon(lon2 - lon1));
+  const tanU1 = (1 - WGS84_F) * Math.tan(toRad(lat1));
+  const cosU1 = 1 / Math.sqrt(1 + tanU1 * tanU1);
+  const sinU1 = tanU1 * cosU1;
+  const tanU2 = (1 - WGS84_F) * Math.tan(toRad(lat2));
+  const cosU2 = 1 / Math.sqrt(1 + tanU2 * tanU2);
+  const sinU2 = tanU2 * cosU2;
+
+  let lambda = L;
+  for (let i = 0; i < 200; i++) {
+    const sinLambda = Math.sin(lambda);
+    const cosLambda = Math.cos(lambda);
+    const sinSigma = Math.hypot(cosU2 * sinLambda, cosU1 * sinU2 - sinU1 * cosU2 * cosLambda);
+    if (sinSigma === 0) {
+      return { distance: 0, initialBearing: 0, finalBearing: 0 };
+    }
+    const cosSigma = sinU1 * sinU2 + cosU1 * cosU2 * cosLambda;
+    const sigma = Math.atan2(sinSigma, cosSigma);
+    const sinAlpha = cosU1 * cosU2 * sinLambda / sinSigma;
+    const cosSqAlpha = 1 - sinAlpha * sinAlpha;
+    const cos2SigmaM = cosSqAlpha !== 0 ? cosSigma - 2 * sinU1 * sinU2 / cosSqAlpha : 0;
+    const C = WGS84_F / 16 * cosSqAlpha * (4 + WGS84_F * (4 - 3 * cosSqAlpha));
+    const previous = lambda;
+    lambda = L + (1 - C) * WGS84_F * sinAlpha *
+      (sigma + C * sinSigma * (cos2SigmaM + C * cosSigma * (-1 + 2 * cos2SigmaM * cos2SigmaM)));
+    if (Math.abs(lambda) > Math.PI) break;
+    if (Math.abs(lambda - previous) < 1e-12) {
+      const uSq = cosSqAlpha * (WGS84_A * WGS84_A - WGS84_B * WGS84_B) / (WGS84_B * WGS84_B);
+      const A = 1 + uSq / 16384 * (4096 + uSq * (-768 + uSq * (320 - 175 * uSq)));
+      const B = uSq / 1024 * (256 + uSq * (-128 + uSq * (74 - 47 * uSq)));
+      const c2 = cos2SigmaM * cos2SigmaM;
+      const deltaSigma = B * sinSigma * (cos2SigmaM + B / 4 * (cosSigma * (-1 + 2 * c2) -
+        B / 6 * cos2SigmaM * (-3 + 4 * sinSigma * sinSigma) * (-3 + 4 * c2)));
+      const sinL = Math.sin(lambda);
+      const cosL = Math.cos(lambda);
+      return {
+        distance: WGS84_B * A * (sigma - deltaSigma),
+        initialBearing: toBearing(Math.atan2(cosU2 * sinL, cosU1 * sinU2 - sinU1 * cosU2 * cosL)),
+        finalBearing: toBearing(Math.atan2(cosU1 * sinL, -sinU1 * cosU2 + cosU1 * sinU2 * cosL)),
+      };
+    }
+  }
+  return sphericalInverse(lat1, lon1, lat2, lon2);
+}
+
+/**
+ * Geodesic distance in meters
+ */
+export function distance(lat1: number, lon1: number, lat2: number, lon2: number): number {
+  return inverse(lat1, lon1, lat2, lon2).distance;
+}
+
+/**
+ * Initial bearing from point 1 to point 2, degrees
+ */
+export function bearing(lat1: number, lon1: number, lat2: number, lon2: number): number {
+  return inverse(lat1, lon1, lat2, lon2).initialBearing;
+}
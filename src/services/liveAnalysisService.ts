@@ -8,44 +8,15 @@ import {
   LaunchParams,
   WeatherData
 } from '../types';
-import { MAX_REASONABLE_DEVIATION_M, MAX_ALTITUDE_DEVIATION_M, EARTH_RADIUS_M } from '../constants';
+import { MAX_REASONABLE_DEVIATION_M, MAX_ALTITUDE_DEVIATION_M } from '../constants';
 import { runPredictionSimulation } from './predictionService';
+import * as geodesy from './geodesy';
 
 /**
  * Live analysis and comparison of real-time APRS data with predicted flight path.
  * Provides accuracy metrics, deviation calculations, and recommendations.
  */
 
-/**
- * Calculate distance between two points using Haversine formula
- */
-function haversine(lat1: number, lon1: number, lat2: number, lon2: number): number {
-  const toRad = (deg: number) => deg * Math.PI / 180;
-  const R = EARTH_RADIUS_M;
-  const dLat = toRad(lat2 - lat1);
-  const dLon = toRad(lon2 - lon1);
-  const a = Math.sin(dLat/2) * Math.sin(dLat/2) +
-            Math.cos(toRad(lat1)) * Math.cos(toRad(lat2)) *
-            Math.sin(dLon/2) * Math.sin(dLon/2);
-  const c = 2 * Math.atan2(Math.sqrt(a), Math.sqrt(1-a));
-  return R * c;
-}
-
-/**
- * Calculate bearing from point 1 to point 2
- */
-function calculateBearing(lat1: number, lon1: number, lat2: number, lon2: number): number {
-  const toRad = (deg: number) => deg * Math.PI / 180;
-  const toDeg = (rad: number) => rad * 180 / Math.PI;
-  
-  const dLon = toRad(lon2 - lon1);
-  const y = Math.sin(dLon) * Math.cos(toRad(lat2));
-  const x = Math.cos(toRad(lat1)) * Math.sin(toRad(lat2)) - 
-            Math.sin(toRad(lat1)) * Math.cos(toRad(lat2)) * Math.cos(dLon);
-  
-  return (toDeg(Math.atan2(y, x)) + 360) % 360;
-}
-
 /**
  * Detect current flight phase based on APRS position history
  */
@@ -241,12 +212,12 @@ export function calculateActualFlightMetrics(
   }
 
   // Calculate deviation from predicted
-  const distance = haversine(
+  const distance = geodesy.distance(
     currentPosition.lat, currentPosition.lng,
     closestPredictedPoint.lat, closestPredictedPoint.lon
   );
   
-  const bearing = calculateBearing(
+  const bearing = geodesy.bearing(
     closestPredictedPoint.lat, closestPredictedPoint.lon,
     currentPosition.lat, currentPosition.lng
   );
@@ -325,12 +296,12 @@ export function estimateWindFromTrajectory(positions: APRSPosition[]): LiveWeath
     if (!prev.altitude || !curr.altitude || !next.altitude) continue;
     
     // Calculate horizontal displacement
-    const distance = haversine(prev.lat, prev.lng, next.lat, next.lng);
+    const distance = geodesy.distance(prev.lat, prev.lng, next.lat, next.lng);
     const timeSpan = next.time - prev.time;
     const horizontalSpeed = distance / timeSpan;
     
     // Calculate direction of movement
-    const direction = calculateBearing(prev.lat, prev.lng, next.lat, next.lng);
+    const direction = geodesy.bearing(prev.lat, prev.lng, next.lat, next.lng);
     
     // Estimate wind (assuming balloon moves with wind)
     estimates.push({
@@ -487,8 +458,8 @@ function calculateDeviationFromPredicted(
   if (!closest) {
     return { distance: 0, bearing: 0, altitudeDifference: 0 };
   }
-  const distance = haversine(position.lat, position.lng, closest.lat, closest.lon);
-  const bearing = calculateBearing(closest.lat, closest.lon, position.lat, position.lng);
+  const distance = geodesy.distance(position.lat, position.lng, closest.lat, closest.lon);
+  const bearing = geodesy.bearing(closest.lat, closest.lon, position.lat, position.lng);
   const altitudeDifference = (position.altitude ?? 0) - closest.altitude;
   return { distance, bearing, altitudeDifference };
 }
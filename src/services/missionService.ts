@@ -0,0 +1,29 @@
+/**
+ * Mission Service
+ * Saves named launch plans, flight profile included, in the native mission store
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { Mission } from '../types';
+
+/**
+ * Save a mission, replacing any mission of the same name
+ */
+export async function saveMission(mission: Mission): Promise<Mission> {
+  return await invoke<Mission>('save_mission', { mission });
+}
+
+export async function loadMission(name: string): Promise<Mission> {
+  return await invoke<Mission>('load_mission', { name });
+}
+
+/**
+ * List saved missions, most recently saved first
+ */
+export async function listMissions(): Promise<Mission[]> {
+  return await invoke<Mission[]>('list_missions');
+}
+
+export async function deleteMission(name: string): Promise<boolean> {
+  return await invoke<boolean>('delete_mission', { name });
+}
@@ -0,0 +1,46 @@
+/**
+ * Prediction API Service
+ * Starts and stops the native Tawhiri-compatible prediction server on
+ * localhost, for scripts and dashboards that speak the CUSF Tawhiri API
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { PredictionApiStatus } from '../types';
+
+export const DEFAULT_PREDICTION_API_PORT = 8000;
+
+/**
+ * Serve /api/v1/ on localhost, restarting on the new port if already running.
+ * Web pages may call it only from `allowedOrigins` (e.g. http://localhost:3000);
+ * scripts and other non-browser clients are unaffected
+ */
+export async function startPredictionApi(port?: number, allowedOrigins?: string[]): Promise<PredictionApiStatus> {
+  return await invoke<PredictionApiStatus>('start_prediction_api', { port, allowedOrigins });
+}
+
+export async function stopPredictionApi(): Promise<PredictionApiStatus> {
+  return await invoke<PredictionApiStatus>('stop_prediction_api');
+}
+
+export async function getPredictionApiStatus(): Promise<PredictionApiStatus> {
+  return await invoke<PredictionApiStatus>('prediction_api_status');
+}
+
+/**
+ * Split the comma- or whitespace-separated origins entered in settings
+ */
+export function parseAllowedOrigins(text: string): string[] {
+  return text.split(/[\s,]+/).map(origin => origin.replace(/\/+$/, '')).filter(Boolean);
+}
+
+/**
+ * Restart the server on app startup if it was left enabled in settings
+ */
+export function initializePredictionApi(): void {
+  if (localStorage.getItem('predictionApiEnabled') !== 'true') return;
+  const port = parseInt(localStorage.getItem('predictionApiPort') ?? '', 10);
+  const allowedOrigins = parseAllowedOrigins(localStorage.getItem('predictionApiAllowedOrigins') ?? '');
+  startPredictionApi(Number.isFinite(port) ? port : undefined, allowedOrigins).catch(error => {
+    console.warn('Failed to start the prediction API:', error);
+  });
+}
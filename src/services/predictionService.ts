@@ -342,16 +342,29 @@ export const calculateFlightPerformance = (
   });
 
   // 5. Calculate Burst Altitude (m)
-  // 5a. Empirical formula for burst radius from balloon weight
-      const burstRadiusM = BURST_RADIUS_COEFFICIENT * Math.pow(balloonWeight, BURST_RADIUS_EXPONENT);
+  // 5a. Rated burst diameter from the catalog, or the empirical formula for
+  // burst radius from balloon weight
+  const burstRadiusM = params.burstDiameter
+    ? params.burstDiameter / 2
+    : BURST_RADIUS_COEFFICIENT * Math.pow(balloonWeight, BURST_RADIUS_EXPONENT);
   const burstVolumeM3 = (4 / 3) * Math.PI * Math.pow(burstRadiusM, 3);
-  steps.push({
-      name: "Burst Radius (Empirical)",
-      formula: "r_burst = 0.479 * W_balloon ^ 0.3115",
-      calculation: `r_burst = 0.479 * ${balloonWeight} ^ 0.3115`,
-      result: burstRadiusM.toFixed(3),
-      unit: "m",
-  });
+  if (params.burstDiameter) {
+    steps.push({
+        name: "Burst Radius (Rated)",
+        formula: "r_burst = D_burst / 2",
+        calculation: `r_burst = ${params.burstDiameter} / 2`,
+        result: burstRadiusM.toFixed(3),
+        unit: "m",
+    });
+  } else {
+    steps.push({
+        name: "Burst Radius (Empirical)",
+        formula: "r_burst = 0.479 * W_balloon ^ 0.3115",
+        calculation: `r_burst = 0.479 * ${balloonWeight} ^ 0.3115`,
+        result: burstRadiusM.toFixed(3),
+        unit: "m",
+    });
+  }
   steps.push({
       name: "Burst Volume",
       formula: "V_burst = (4/3) * π * r_burst³",
@@ -24,6 +24,9 @@ export interface CalculatorParams {
   parachuteWeight: number; // g
   neckLift: number; // g
   gas: 'Helium' | 'Hydrogen';
+  balloonModel?: string; // catalog id
+  burstDiameter?: number; // meters, rated; replaces the empirical burst radius
+  parachuteModel?: string; // catalog id; its canopy sets the mission's parachute drag model
 }
 
 export interface LaunchParams {
@@ -33,8 +36,246 @@ export interface LaunchParams {
   launchAltitude: number; // meters
   ascentRate: number; // m/s
   burstAltitude: number; // meters
-  descentRate: number; // m/s
+  balloon?: Balloon; // ascent physics; overrides ascentRate (and burstAltitude with a burst diameter)
+  descentRate: number; // m/s at sea level
+  parachute?: Parachute; // drag model; overrides descentRate when set
   trackingCallsign?: string; // APRS or other tracking callsign
+  integration?: IntegrationSettings; // native engine only
+  float?: FloatProfile; // native engine only; levels off and cuts down instead of bursting
+  profile?: FlightProfile; // native engine only; replaces the standard or float flight
+}
+
+export type FlightSegment =
+  | { type: 'constantRate'; altitude: number; rate?: number } // to altitude (m), at rate (m/s) or the ascent/descent rate
+  | { type: 'floatUntilTime'; duration?: number; endTime?: string; leakRate?: number }
+  | { type: 'floatUntilDistance'; distance: number; leakRate?: number } // meters from the launch site
+  | { type: 'terminalDescent' }; // burst or cutdown; must come last
+
+export interface FlightProfile {
+  segments: FlightSegment[];
+}
+
+export interface Mission {
+  name: string;
+  params: LaunchParams;
+  notes?: string;
+  updatedAt?: string; // set when saved
+  fill?: FillRecord; // launch-day gas fill
+}
+
+export interface FloatProfile {
+  altitude: number; // meters
+  duration?: number; // seconds at float; or
+  endTime?: string; // ISO time of the cutdown
+  leakRate?: number; // m/s while floating, negative for a slow leak
+}
+
+export interface IntegrationSettings {
+  integrator?: 'euler' | 'rk4' | 'rk45'; // default rk4
+  timeStep?: number; // seconds; first step for rk45
+  tolerance?: number; // meters of local error per rk45 step
+  outputInterval?: number; // seconds between returned path points
+}
+
+export interface Balloon {
+  balloonMass: number; // kg
+  payloadMass: number; // kg, payload + parachute + rigging
+  freeLift: number; // kg
+  gas: 'Helium' | 'Hydrogen';
+  gasPurity?: number; // mole fraction of lift gas, defaults to 1
+  dragCoefficient?: number; // defaults to BALLOON_DRAG_COEFFICIENT
+  burstDiameter?: number; // meters
+}
+
+export interface ExpansionPoint {
+  altitude: number; // meters
+  radius: number; // meters
+  ascentRate: number; // m/s
+}
+
+export interface Parachute {
+  diameter: number; // meters
+  dragCoefficient: number;
+  payloadMass: number; // kg
+}
+
+// --- Catalog Types ---
+export interface BalloonSpec {
+  id: string;
+  manufacturer: string;
+  model: string;
+  mass: number; // kg
+  burstDiameter: number; // meters
+  recommendedFreeLift?: number; // kg
+  dragCoefficient?: number;
+  custom?: boolean; // from the user's catalog
+}
+
+export interface ParachuteSpec {
+  id: string;
+  manufacturer: string;
+  model: string;
+  diameter: number; // meters
+  dragCoefficient: number;
+  mass: number; // kg
+  custom?: boolean;
+}
+
+export interface GasSpec {
+  id: string;
+  name: string;
+  gas: 'Helium' | 'Hydrogen';
+  purity: number; // mole fraction, 0-1
+  custom?: boolean;
+}
+
+export interface CylinderSpec {
+  id: string;
+  name: string;
+  waterVolume: number; // liters
+  fillPressure: number; // bar gauge
+  custom?: boolean;
+}
+
+export interface Catalog {
+  version: string;
+  balloons: BalloonSpec[];
+  parachutes: ParachuteSpec[];
+  gases: GasSpec[];
+  cylinders: CylinderSpec[];
+}
+
+export type CatalogEntry =
+  | ({ kind: 'balloon' } & BalloonSpec)
+  | ({ kind: 'parachute' } & ParachuteSpec)
+  | ({ kind: 'gas' } & GasSpec)
+  | ({ kind: 'cylinder' } & CylinderSpec);
+
+// --- Flight History & Calibration Types ---
+export interface FlightRecord {
+  id?: number; // set when recorded
+  balloon: string; // catalog balloon id
+  gas: string; // catalog gas id
+  neckLift: number; // kg, payload plus free lift
+  payloadMass: number; // kg
+  launchAltitude: number; // meters
+  ascentRate: number; // m/s, mean from launch to burst
+  burstAltitude?: number; // meters; omitted when the burst was not seen
+  flownAt: string; // ISO format string
+  notes?: string;
+}
+
+export interface Estimate {
+  value: number;
+  low?: number; // 95% confidence interval; omitted with a single flight
+  high?: number;
+  flights: number;
+}
+
+export interface FlightFit {
+  id?: number;
+  dragCoefficient: number;
+  burstDiameter?: number; // meters
+}
+
+export interface Calibration {
+  spec: BalloonSpec; // with the fitted values applied once two flights back them
+  dragCoefficient?: Estimate;
+  burstDiameter?: Estimate; // meters
+  fits: FlightFit[];
+}
+
+// --- Prediction API Types ---
+export interface PredictionApiStatus {
+  running: boolean;
+  port?: number;
+  url?: string; // endpoint to point Tawhiri clients at
+}
+
+// --- Gas Fill Types ---
+export interface FillRequest {
+  gas: string; // catalog id
+  neckLift: number; // kg
+  balloonMass: number; // kg
+  surface?: { temperature2m?: number; surfacePressure?: number }; // forecast °C and hPa at launch
+  launchAltitude?: number; // meters
+  cylinders?: string[]; // catalog ids; all if omitted
+  residualPressure?: number; // bar gauge, defaults to 2
+}
+
+export interface CylinderUse {
+  cylinder: CylinderSpec;
+  usableVolume: number; // m³ at ambient conditions
+  cylinders: number;
+  fullCylinders: number;
+  lastCylinderDrop: number; // bar
+}
+
+export interface ChecklistStep {
+  title: string;
+  detail: string;
+  done: boolean;
+}
+
+export interface FillPlan {
+  gas: GasSpec;
+  neckLift: number; // kg
+  grossLift: number; // kg
+  temperature: number; // °C
+  pressure: number; // hPa
+  standardAtmosphere: boolean; // true when forecast values were missing
+  liftDensity: number; // kg/m³
+  volume: number; // m³ at ambient conditions
+  cylinders: CylinderUse[];
+  checklist: ChecklistStep[];
+}
+
+export interface LiftMeasurement {
+  neckLift: number; // kg
+  measuredAt: string;
+}
+
+export interface FillRecord {
+  plan: FillPlan;
+  measurements: LiftMeasurement[];
+}
+
+export interface FillAdjustment {
+  measured: number; // kg
+  liftChange: number; // kg to add; negative to vent
+  volumeChange: number; // m³ to add; negative to vent
+  complete: boolean; // within 5 g of the target
+}
+
+// --- Neck-Lift Solver Types ---
+export interface LiftTargets {
+  ascentRate?: number; // m/s, mean to burst
+  burstAltitude?: number; // meters
+  neckLift?: number; // kg, payload plus free lift
+  payloadMass?: number; // kg
+}
+
+export interface Range {
+  min: number;
+  max: number;
+}
+
+export interface FillError {
+  neckLiftChange: number; // kg
+  ascentRate?: number; // m/s; absent if the payload is no longer lifted
+  burstAltitude?: number; // meters
+}
+
+export interface LiftSolution {
+  neckLift: number; // kg
+  payloadMass: number; // kg
+  freeLift: number; // kg
+  ascentRate: number; // m/s, mean to burst
+  launchAscentRate: number; // m/s
+  burstAltitude: number; // meters
+  balloon: Balloon; // ready for LaunchParams.balloon
+  bounds: { neckLift: Range; ascentRate: Range; burstAltitude: Range };
+  sensitivity: FillError[]; // neck lift 10 g under and over
 }
 
 export interface FlightPoint {
@@ -67,6 +308,12 @@ export interface PredictionResult {
   maxAltitude?: number;
   distance?: number;
   flightDuration?: number;
+  forecastExceededAt?: number; // seconds from launch when the flight outran the forecast
+  expansionCurve?: ExpansionPoint[]; // balloon radius vs altitude, with a balloon model
+  floatPoint?: FlightPoint; // where a float flight leveled off
+  cutdownPoint?: FlightPoint; // where it was cut down, unless it leaked to the ground first
+  windField?: boolean; // winds from a field along the track rather than the launch-site column
+  windFieldError?: string; // why the wind field was not used, if the run fell back to the launch column
   terrainAnalysis?: TerrainAnalysis;
 }
 